@@ -0,0 +1,291 @@
+# Messages of the launcher, English.
+# Every key must also exist in ko.ftl with the same variables; the build checks this.
+
+## Common
+
+warning = Warning: { $message }
+yes = yes
+no = no
+windows-only = sclauncher-cli only runs on Windows.
+not-interactive = { $missing } (running non-interactively, nothing was asked).
+config-invalid = Invalid configuration: { $error }
+process-list-failed = Failed to list processes: { $error }
+no-match = No game matches '{ $selector }'.
+no-games-running = No StarCraft.exe is running.
+launch-none = None of the games could be launched.
+launching = Launching StarCraft.exe [{ $number }]
+detached = Detached, the games keep running.
+leaving-running = Leaving the games running.
+not-admin-inspect = Not running as admin, games started elevated cannot be inspected.
+window-place-failed = Could not place the window of pid { $pid }.
+arrange-partial = Only { $arranged } of { $total } windows could be arranged.
+arch-unknown = Unknown architecture '{ $arch }', use x86 or x86_64.
+lang-unknown = Unknown language '{ $lang }', use en or ko.
+layout-unknown = Unknown layout '{ $layout }', use none, grid or cascade.
+profile-limit-invalid = '{ $value }' is not a profile limit, use PROFILE=N such as ladder=2.
+
+## Elevation
+
+admin-required = Admin rights are required. Run from an elevated shell.
+admin-elevating = Not running as admin. Attempting to elevate...
+admin-elevated-window = Continuing in the elevated window.
+admin-elevate-failed = Failed to elevate privileges.
+
+## Launching
+
+need-count = Pass the number of games with -n
+game-path-error = Error getting game path: { $error }
+limits-refuse = Refusing to launch: { $reasons } Pass --ignore-limits to launch anyway.
+plan-invalid = Invalid launch plan:
+plan-guard-stops = The mutex guard stops when the launcher exits.
+
+## Session console
+
+session-kill-all = kill all games
+session-keep = end the session and leave the games running
+wait-ctrl-c = Press Ctrl+C to { $action }...
+console-intro = Session console: type help for the commands, quit to { $action }.
+console-read-failed = Reading the console failed: { $error }
+console-launched-partial = Only { $launched } of { $count } games launched.
+relabel-ambiguous = '{ $selector }' matches { $count } games, pick exactly one.
+guard-started = Mutex guard started.
+guard-already-running = The mutex guard is already running.
+guard-stopped = Mutex guard stopped.
+guard-not-running = The mutex guard is not running.
+guard-failed = Mutex guard stopped: { $error }
+console-help =
+    Commands:
+      list                        Show the games of this session
+      launch [n]                  Launch n more games (1 when omitted)
+      kill <selector>             Kill the matching games (e.g. newest:2, label:bot*)
+      relabel <selector> [label]  Label one game, or remove its label
+      layout <grid|cascade>       Arrange the game windows
+      guard <on|off>              Keep closing the instance mutex in the background, or stop
+      quit [--keep]               End the session; --keep leaves the games running
+      help                        Show this text
+console-empty = Empty command.
+console-bad-count = '{ $value }' is not a number of games.
+console-bad-guard = Use 'guard on' or 'guard off', not '{ $value }'.
+console-bad-args = Wrong arguments for '{ $command }', type help.
+console-unknown = Unknown command '{ $command }', type help.
+
+## Dry run and scan
+
+dry-run-header = Dry run: nothing is started, killed or changed.
+dry-run-install = Install:   { $path }
+dry-run-summary = Would launch { $count ->
+        [one] { $count } game
+       *[other] { $count } games
+    }, { $delay } ms apart, layout { $layout }:
+dry-run-profile = profile { $profile }
+dry-run-label = label { $label }
+dry-run-window-sized = window { $width }x{ $height } at { $x },{ $y }
+dry-run-window = window at { $x },{ $y }
+dry-run-affinity = affinity { $mask }
+dry-run-limits-ok = Limits:    ok
+dry-run-limits-refuse = Limits:    would refuse: { $reason }
+dry-run-limits-warn = Limits:    would warn: { $reason }
+dry-run-running = Running StarCraft.exe:
+scan-dry-run = Dry run: no handle is closed.
+scan-process = pid { $pid }: { $handles } handles, { $matches } instance mutex
+scan-process-error = pid { $pid }: { $error }
+process-open-failed = OpenProcess failed: { $error }
+handle-query-failed = Querying handles failed: NTSTATUS({ $status })
+scan-would-close = would close
+scan-closed = closed
+scan-close-failed = could not close
+scan-hung = { $handle }: name query did not return in time, skipped
+handles-process = pid { $pid }: { $handles } handles, { $shown } shown
+
+## List, status and locate
+
+column-label = LABEL
+column-profile = PROFILE
+column-adopted = ADOPTED
+column-path = PATH
+column-handle = HANDLE
+column-access = ACCESS
+column-type = TYPE
+column-object = NAME
+status-admin-yes = Admin:     yes
+status-admin-no = Admin:     no (launch and kill will ask to elevate)
+status-daemon = Daemon:    pid { $pid }
+status-guard-running = Guard:     running
+status-guard-off = Guard:     off
+status-install = Install:   { $path }
+status-install-unset = Install:   not set
+status-install-missing = Install:   not found ({ $error })
+status-running = Running:   { $count }
+locate-searching = Attempting to locate StarCraft.exe...
+search-cancelled = Registry search cancelled.
+search-timed-out = Registry search stopped after { $seconds } seconds; results may be incomplete.
+drive-scan-started = Walking { $drives } for StarCraft folders...
+drive-scan-timed-out = Drive walk stopped after { $seconds } seconds and { $folders } folders; results may be incomplete.
+scan-cache-failed = Could not update the scan cache { $path }: { $error }
+install-not-found = StarCraft not found.
+install-not-found-hint = StarCraft not found. Pass --game-path to point at it, or --scan-drives to search the disks.
+registry-dry-run = Dry run, the registry is left as it is.
+registry-path-set = Game path set to: { $path } (change #{ $id }, undo with `registry restore --id { $id }`)
+registry-kept = The registry already points at { $current }; left as it is.
+prompt-registry-replace = The registry points at { $current }. Replace it with { $path }? [y/N]
+registry-write-failed = Could not write the game path to the registry: { $error }
+portable-path-saved = Remembered { $path } in { $file }.
+portable-save-failed = Could not remember the install: { $error }
+portable-registry-off = Portable mode leaves the registry alone; run without it to restore registry changes.
+registry-restored = Restored change #{ $id } ({ $key }).
+registry-nothing-to-restore = No registry change to restore.
+registry-restore-failed = Could not restore the registry: { $error }
+registry-exported = Wrote { $count ->
+    [one] one change
+   *[other] { $count } changes
+} to { $path }.
+registry-export-failed = Could not write { $path }: { $error }
+journal-unreadable = Could not read the registry journal: { $error }
+journal-empty = The launcher has not changed the registry.
+journal-restored = restored
+journal-unset = (not set)
+journal-file = Journal: { $path }
+journal-no-change = No registry change #{ $id } in the journal.
+prompt-count = How many StarCraft.exe?:
+prompt-path-example = ex) D:\Games\StarCraft
+prompt-path = Please enter the full path to StarCraft.exe:
+path-none = No path provided.
+path-missing = The provided base path does not exist.
+installs-header = Found { $count ->
+    [one] one StarCraft installation
+   *[other] { $count } StarCraft installations
+}:
+installs-none = No StarCraft installation found.
+install-picked = Using { $install }; pass --install to choose another.
+install-no-arch = Found { $count ->
+    [one] one installation
+   *[other] { $count } installations
+}, but none has a { $arch } StarCraft.exe.
+install-arch-missing = { $install } has no { $arch } StarCraft.exe.
+install-choice-out-of-range = There is no installation { $number }; { $count } were found.
+install-edition-missing = No { $edition } installation was found.
+edition-unknown = Unknown installation '{ $edition }', use a number, remastered, legacy or ptr.
+prompt-install = Choose an installation [1-{ $count }, Enter for 1]:
+source-uninstall-key = uninstall entry { $key }
+source-legacy-key = 1.16.1 installer key { $key }
+source-registry-search = registry search match { $key }
+source-battle-net = Battle.net file { $path }
+source-well-known = usual install folder { $path }
+source-scan-cache = scan cache { $path }
+source-drive-scan = drive walk under { $path }
+path-no-exe = None of '{ $x86 }', '{ $x64 }' or '{ $flat }' exist.
+exe-unreadable = Could not read { $path }: { $error }
+exe-arch-mismatch = { $path } is { $machine }, not { $arch }. Pass --arch to launch the other build.
+arch-auto = Using the { $arch } build (version { $version }); pass --arch to choose.
+
+## Daemon and HTTP
+
+daemon-using = Using the daemon (pid { $pid }).
+daemon-lost = Lost the connection to the daemon: { $error }
+daemon-unexpected = Unexpected response from the daemon: { $response }
+daemon-none = No daemon is running: { $error }
+daemon-stopped-pid = Stopped the daemon (pid { $pid }).
+daemon-running = Daemon running (pid { $pid }). Stop it with `daemon --stop` or Ctrl+C.
+daemon-failed = The daemon failed (is another one already running?): { $error }
+daemon-stopped = Daemon stopped, the games keep running.
+daemon-protocol = Protocol version { $requested } is not supported, the daemon speaks { $supported }.
+daemon-hello-first = Send a hello request first.
+daemon-invalid-request = Invalid request: { $error }
+daemon-no-game-path = The daemon has no game path. Pass one with the launch request.
+daemon-closed = The daemon closed the connection.
+http-unauthorized = Missing or wrong token.
+http-select-required = Pass ?select=<selector>, e.g. ?select=all.
+http-listen-failed = Could not listen on { $address }: { $error }
+http-exposed = The HTTP API on { $address } is reachable from other machines.
+http-listening = HTTP API on http://{ $address } (token: { $token })
+
+## Configuration
+
+column-key = KEY
+column-value = VALUE
+column-source = SOURCE
+config-files = Files are read in this order, later ones win:
+config-file-found = found
+config-file-missing = missing
+config-then = then SCLAUNCHER_* environment variables, then command line flags.
+
+## Game events
+
+launch-no-handle = CreateProcessW returned no process handle
+launch-create-failed = Could not start StarCraft.exe: { $error }
+background-thread-failed = background thread failed: { $error }
+relaunch-no-path = Cannot relaunch pid { $pid }: unknown game path
+mutex-close-refused = DuplicateHandle could not close the instance mutex
+mutex-wait-timeout = no instance mutex appeared within { $seconds } s
+event-launched = Launched game with pid: { $pid }
+event-launched-label = Launched game with pid: { $pid } ({ $label })
+event-launch-failed = Failed to launch game: { $reason }
+event-mutex-closed = Closed proc_handle for StarCraft.exe.{ $pid }
+event-mutex-close-failed = Could not close the instance mutex of { $pid }: { $reason }
+event-handle-hung = pid { $pid }: { $handle }: name query did not return in time, skipped
+event-affinity-failed = Could not set the affinity of { $pid }: { $reason }
+event-adopted = Adopted running game with pid: { $pid }
+event-killed = Killing game with pid: { $pid }
+event-relabeled = Labeled game with pid: { $pid } as { $label }
+event-unlabeled = Removed the label of game with pid: { $pid }
+
+## Selectors and limits
+
+selector-empty = Empty selector.
+selector-bad-count = Invalid count '{ $count }' in selector '{ $selector }'.
+selector-needs-value = Selector '{ $selector }' needs a value, e.g. '{ $kind }:name'.
+selector-bad-index = Invalid index in selector '{ $selector }'.
+selector-bad-pid = Invalid pid in selector '{ $selector }'.
+selector-unknown = Unknown selector '{ $selector }'. Try all, 2, pid:N, oldest[:N], newest[:N], label:NAME, glob:PATTERN, profile:NAME or adopted.
+limit-too-many-games = { $max ->
+    [one] One game
+   *[other] { $max } games
+} may run at once, but { $running } running + { $requested } requested.
+limit-too-many = Profile '{ $profile }' allows { $max } instances, but { $running } running + { $requested } requested.
+limit-low-memory = Only { $available } MiB of memory free, { $required } MiB needed.
+
+## Launch plans
+
+plan-no-instances = The plan has no [[instance]] entries.
+plan-label-empty = The label is empty.
+plan-label-duplicate = The label '{ $label }' is already used on line { $line }.
+plan-window-size = The window size must be positive.
+plan-window-both = Give both width and height, or neither.
+plan-affinity-empty = The affinity lists no processors.
+plan-affinity-range = Processor { $core } does not exist, this machine has { $processors } (0 to { $last }).
+
+## Doctor
+
+doctor-summary = { $pass } passed, { $warn } warnings, { $fail } failed (checked for { $arch }).
+doctor-admin-ok = Running elevated.
+doctor-admin-missing = Not elevated; launch, kill and scan will ask for admin rights.
+doctor-debug-enabled = Enabled.
+doctor-debug-available = Available (disabled until needed).
+doctor-debug-missing = Not available; games started by another user cannot be inspected.
+doctor-registry-ok = InstallLocation = { $location }
+doctor-registry-missing = Not readable: { $error }
+doctor-portable = Portable mode: the registry is not read, settings are next to the executable
+doctor-install-none = No install found in the registry; pass --game-path.
+doctor-install-missing = { $path } does not exist.
+doctor-exe-missing = { $path } is missing.
+doctor-pe-ok = { $path } is { $machine }, as requested.
+doctor-pe-mismatch = { $path } is { $machine }, but { $arch } was requested (-b selects x86_64).
+doctor-pe-unreadable = Could not read { $path }: { $error }
+doctor-version = { $product } { $version }
+doctor-version-missing = { $path } has no version resource.
+doctor-processes-none = None running.
+doctor-processes = { $count } running: { $pids }
+doctor-battle-net-none = Not running (not needed by the launcher).
+doctor-battle-net = Running: { $processes }
+doctor-mutex-no-game = No StarCraft.exe running; start one to check that the mutex can be found.
+doctor-mutex-not-found = Not found in any StarCraft.exe (already closed, or the name changed).
+doctor-mutex-found = Found in { $pids }.
+doctor-scan-failed = Scan failed: { $error }
+doctor-mutex-create-failed = CreateMutexW failed: { $error }
+doctor-handle-renamed = The test mutex came back as '{ $name }'.
+doctor-handle-missing = The test mutex was not among the listed handles.
+doctor-handle-query-failed = ZwQueryInformationProcess failed: NTSTATUS({ $status })
+doctor-handle-ok = Listed { $count } handles and named the test mutex in { $ms } ms.
+doctor-types-unavailable = The object type list could not be read; every handle is asked for its type instead.
+doctor-types-ok = { $count } object types listed, Mutant is type { $index }.
+doctor-types-mismatch = The test mutex has type { $index }, which the type list calls '{ $name }'.
@@ -0,0 +1,276 @@
+# 런처 메시지, 한국어.
+# en.ftl의 모든 키가 같은 변수와 함께 여기에도 있어야 합니다. 빌드할 때 확인합니다.
+
+## 공통
+
+warning = 경고: { $message }
+yes = 예
+no = 아니요
+windows-only = sclauncher-cli는 Windows에서만 실행됩니다.
+not-interactive = { $missing } (비대화형으로 실행 중이라 묻지 않았습니다).
+config-invalid = 설정이 잘못되었습니다: { $error }
+process-list-failed = 프로세스 목록을 가져오지 못했습니다: { $error }
+no-match = '{ $selector }'에 해당하는 게임이 없습니다.
+no-games-running = 실행 중인 StarCraft.exe가 없습니다.
+launch-none = 게임을 하나도 실행하지 못했습니다.
+launching = StarCraft.exe 실행 중 [{ $number }]
+detached = 분리되었습니다. 게임은 계속 실행됩니다.
+leaving-running = 게임을 실행 중인 채로 둡니다.
+not-admin-inspect = 관리자 권한이 아니라서 관리자 권한으로 실행된 게임은 검사할 수 없습니다.
+window-place-failed = pid { $pid }의 창 위치를 정하지 못했습니다.
+arrange-partial = 창 { $total }개 중 { $arranged }개만 정렬했습니다.
+arch-unknown = 알 수 없는 아키텍처 '{ $arch }'입니다. x86 또는 x86_64를 쓰세요.
+lang-unknown = 알 수 없는 언어 '{ $lang }'입니다. en 또는 ko를 쓰세요.
+layout-unknown = 알 수 없는 배치 '{ $layout }'입니다. none, grid 또는 cascade를 쓰세요.
+profile-limit-invalid = '{ $value }'은(는) 프로필 제한이 아닙니다. ladder=2처럼 PROFILE=N 형식으로 쓰세요.
+
+## 권한 상승
+
+admin-required = 관리자 권한이 필요합니다. 관리자 권한 셸에서 실행하세요.
+admin-elevating = 관리자 권한이 아닙니다. 권한 상승을 시도합니다...
+admin-elevated-window = 관리자 권한 창에서 계속합니다.
+admin-elevate-failed = 권한 상승에 실패했습니다.
+
+## 실행
+
+need-count = -n으로 게임 수를 지정하세요
+game-path-error = 게임 경로를 가져오지 못했습니다: { $error }
+limits-refuse = 실행을 거부합니다: { $reasons } 그래도 실행하려면 --ignore-limits를 지정하세요.
+plan-invalid = 실행 계획이 잘못되었습니다:
+plan-guard-stops = 런처가 끝나면 뮤텍스 가드도 멈춥니다.
+
+## 세션 콘솔
+
+session-kill-all = 모든 게임을 종료
+session-keep = 세션을 끝내고 게임은 그대로 실행
+wait-ctrl-c = Ctrl+C를 누르면 { $action }합니다...
+console-intro = 세션 콘솔: 명령어는 help, { $action }하려면 quit를 입력하세요.
+console-read-failed = 콘솔을 읽지 못했습니다: { $error }
+console-launched-partial = 게임 { $count }개 중 { $launched }개만 실행했습니다.
+relabel-ambiguous = '{ $selector }'에 게임 { $count }개가 해당합니다. 하나만 고르세요.
+guard-started = 뮤텍스 가드를 시작했습니다.
+guard-already-running = 뮤텍스 가드가 이미 실행 중입니다.
+guard-stopped = 뮤텍스 가드를 멈췄습니다.
+guard-not-running = 뮤텍스 가드가 실행 중이 아닙니다.
+guard-failed = 뮤텍스 가드가 멈췄습니다: { $error }
+console-help =
+    명령어:
+      list                        이 세션의 게임 보기
+      launch [n]                  게임 n개 더 실행 (생략하면 1개)
+      kill <selector>             해당하는 게임 종료 (예: newest:2, label:bot*)
+      relabel <selector> [label]  게임 하나에 라벨 지정, 생략하면 라벨 제거
+      layout <grid|cascade>       게임 창 정렬
+      guard <on|off>              백그라운드에서 인스턴스 뮤텍스를 계속 닫기, 또는 멈추기
+      quit [--keep]               세션 종료, --keep이면 게임은 그대로 실행
+      help                        이 도움말 보기
+console-empty = 빈 명령어입니다.
+console-bad-count = '{ $value }'은(는) 게임 수가 아닙니다.
+console-bad-guard = '{ $value }' 대신 'guard on' 또는 'guard off'를 쓰세요.
+console-bad-args = '{ $command }'의 인자가 잘못되었습니다. help를 입력하세요.
+console-unknown = 알 수 없는 명령어 '{ $command }'입니다. help를 입력하세요.
+
+## 시험 실행과 스캔
+
+dry-run-header = 시험 실행: 아무것도 실행, 종료, 변경하지 않습니다.
+dry-run-install = 설치:      { $path }
+dry-run-summary = 게임 { $count }개를 { $delay } ms 간격, 배치 { $layout }(으)로 실행합니다:
+dry-run-profile = 프로필 { $profile }
+dry-run-label = 라벨 { $label }
+dry-run-window-sized = 창 { $width }x{ $height }, 위치 { $x },{ $y }
+dry-run-window = 창 위치 { $x },{ $y }
+dry-run-affinity = 선호도 { $mask }
+dry-run-limits-ok = 제한:      통과
+dry-run-limits-refuse = 제한:      거부 예정: { $reason }
+dry-run-limits-warn = 제한:      경고 예정: { $reason }
+dry-run-running = 실행 중인 StarCraft.exe:
+scan-dry-run = 시험 실행: 핸들을 닫지 않습니다.
+scan-process = pid { $pid }: 핸들 { $handles }개, 인스턴스 뮤텍스 { $matches }개
+scan-process-error = pid { $pid }: { $error }
+process-open-failed = OpenProcess 실패: { $error }
+handle-query-failed = 핸들 조회 실패: NTSTATUS({ $status })
+scan-would-close = 닫을 예정
+scan-closed = 닫음
+scan-close-failed = 닫지 못함
+scan-hung = { $handle }: 이름 조회가 제때 끝나지 않아 건너뜀
+handles-process = pid { $pid }: 핸들 { $handles }개 중 { $shown }개 표시
+
+## 목록, 상태, 위치 찾기
+
+column-label = 라벨
+column-profile = 프로필
+column-adopted = 편입
+column-path = 경로
+column-handle = 핸들
+column-access = 권한
+column-type = 종류
+column-object = 이름
+status-admin-yes = 관리자:    예
+status-admin-no = 관리자:    아니요 (launch와 kill은 권한 상승을 요청합니다)
+status-daemon = 데몬:      pid { $pid }
+status-guard-running = 가드:      실행 중
+status-guard-off = 가드:      꺼짐
+status-install = 설치:      { $path }
+status-install-unset = 설치:      설정 안 됨
+status-install-missing = 설치:      찾지 못함 ({ $error })
+status-running = 실행 중:   { $count }
+locate-searching = StarCraft.exe를 찾는 중...
+search-cancelled = 레지스트리 검색을 취소했습니다.
+search-timed-out = 레지스트리 검색을 { $seconds }초 만에 멈췄습니다. 결과가 빠졌을 수 있습니다.
+drive-scan-started = { $drives }에서 StarCraft 폴더를 찾는 중...
+drive-scan-timed-out = 드라이브 탐색을 { $seconds }초, 폴더 { $folders }개 만에 멈췄습니다. 결과가 빠졌을 수 있습니다.
+scan-cache-failed = 검색 캐시 { $path }을(를) 갱신하지 못했습니다: { $error }
+install-not-found = StarCraft를 찾지 못했습니다.
+install-not-found-hint = StarCraft를 찾지 못했습니다. --game-path로 위치를 지정하거나 --scan-drives로 디스크를 검색하세요.
+registry-dry-run = 시험 실행이라 레지스트리는 그대로 둡니다.
+registry-path-set = 게임 경로를 설정했습니다: { $path } (변경 #{ $id }, `registry restore --id { $id }`로 되돌릴 수 있습니다)
+registry-kept = 레지스트리가 이미 { $current }을(를) 가리키고 있어 그대로 둡니다.
+prompt-registry-replace = 레지스트리가 { $current }을(를) 가리키고 있습니다. { $path }(으)로 바꿀까요? [y/N]
+registry-write-failed = 게임 경로를 레지스트리에 쓰지 못했습니다: { $error }
+portable-path-saved = { $path }을(를) { $file }에 기억했습니다.
+portable-save-failed = 설치 위치를 기억하지 못했습니다: { $error }
+portable-registry-off = 포터블 모드에서는 레지스트리를 건드리지 않습니다. 레지스트리 변경을 되돌리려면 포터블 모드 없이 실행하세요.
+registry-restored = 변경 #{ $id }을(를) 되돌렸습니다 ({ $key }).
+registry-nothing-to-restore = 되돌릴 레지스트리 변경이 없습니다.
+registry-restore-failed = 레지스트리를 되돌리지 못했습니다: { $error }
+registry-exported = 변경 { $count }개를 { $path }에 썼습니다.
+registry-export-failed = { $path }에 쓰지 못했습니다: { $error }
+journal-unreadable = 레지스트리 기록을 읽지 못했습니다: { $error }
+journal-empty = 런처가 레지스트리를 바꾼 적이 없습니다.
+journal-restored = 되돌림
+journal-unset = (없음)
+journal-file = 기록 파일: { $path }
+journal-no-change = 기록에 레지스트리 변경 #{ $id }이(가) 없습니다.
+prompt-count = StarCraft.exe를 몇 개 실행할까요?:
+prompt-path-example = 예) D:\Games\StarCraft
+prompt-path = StarCraft.exe의 전체 경로를 입력하세요:
+path-none = 경로를 입력하지 않았습니다.
+path-missing = 입력한 기본 경로가 없습니다.
+installs-header = StarCraft 설치를 { $count }개 찾았습니다:
+installs-none = StarCraft 설치를 찾지 못했습니다.
+install-picked = { $install }을(를) 사용합니다. 다른 설치는 --install로 고르세요.
+install-no-arch = 설치를 { $count }개 찾았지만 { $arch } StarCraft.exe가 있는 설치가 없습니다.
+install-arch-missing = { $install }에는 { $arch } StarCraft.exe가 없습니다.
+install-choice-out-of-range = { $number }번 설치가 없습니다. 찾은 설치는 { $count }개입니다.
+install-edition-missing = { $edition } 설치를 찾지 못했습니다.
+edition-unknown = 알 수 없는 설치 '{ $edition }'입니다. 번호나 remastered, legacy, ptr을 쓰세요.
+prompt-install = 설치를 고르세요 [1-{ $count }, Enter는 1]:
+source-uninstall-key = 제거 항목 { $key }
+source-legacy-key = 1.16.1 설치 키 { $key }
+source-registry-search = 레지스트리 검색 결과 { $key }
+source-battle-net = Battle.net 파일 { $path }
+source-well-known = 일반 설치 폴더 { $path }
+source-scan-cache = 검색 캐시 { $path }
+source-drive-scan = { $path } 아래 드라이브 탐색
+path-no-exe = '{ $x86 }', '{ $x64 }', '{ $flat }' 중 어느 것도 없습니다.
+exe-unreadable = { $path }을(를) 읽지 못했습니다: { $error }
+exe-arch-mismatch = { $path }은(는) { $arch }가 아니라 { $machine }입니다. 다른 빌드는 --arch로 실행하세요.
+arch-auto = { $arch } 빌드(버전 { $version })를 사용합니다. --arch로 바꿀 수 있습니다.
+
+## 데몬과 HTTP
+
+daemon-using = 데몬을 사용합니다 (pid { $pid }).
+daemon-lost = 데몬과의 연결이 끊겼습니다: { $error }
+daemon-unexpected = 데몬이 예상하지 못한 응답을 보냈습니다: { $response }
+daemon-none = 실행 중인 데몬이 없습니다: { $error }
+daemon-stopped-pid = 데몬을 멈췄습니다 (pid { $pid }).
+daemon-running = 데몬 실행 중 (pid { $pid }). `daemon --stop` 또는 Ctrl+C로 멈추세요.
+daemon-failed = 데몬이 실패했습니다 (이미 다른 데몬이 실행 중인가요?): { $error }
+daemon-stopped = 데몬을 멈췄습니다. 게임은 계속 실행됩니다.
+daemon-protocol = 프로토콜 버전 { $requested }은(는) 지원하지 않습니다. 데몬은 { $supported } 버전을 씁니다.
+daemon-hello-first = 먼저 hello 요청을 보내세요.
+daemon-invalid-request = 잘못된 요청입니다: { $error }
+daemon-no-game-path = 데몬에 게임 경로가 없습니다. 실행 요청에 경로를 넣으세요.
+daemon-closed = 데몬이 연결을 끊었습니다.
+http-unauthorized = 토큰이 없거나 틀렸습니다.
+http-select-required = ?select=<선택자>를 지정하세요. 예: ?select=all
+http-listen-failed = { $address }에서 대기하지 못했습니다: { $error }
+http-exposed = { $address }의 HTTP API는 다른 컴퓨터에서도 접근할 수 있습니다.
+http-listening = HTTP API: http://{ $address } (토큰: { $token })
+
+## 설정
+
+column-key = 키
+column-value = 값
+column-source = 출처
+config-files = 파일은 이 순서로 읽으며, 뒤의 것이 우선합니다:
+config-file-found = 있음
+config-file-missing = 없음
+config-then = 그다음 SCLAUNCHER_* 환경 변수, 마지막으로 명령줄 옵션을 적용합니다.
+
+## 게임 이벤트
+
+launch-no-handle = CreateProcessW가 프로세스 핸들을 돌려주지 않았습니다
+launch-create-failed = StarCraft.exe를 시작하지 못했습니다: { $error }
+background-thread-failed = 백그라운드 스레드가 실패했습니다: { $error }
+relaunch-no-path = pid { $pid }를 다시 실행할 수 없습니다: 게임 경로를 모릅니다
+mutex-close-refused = DuplicateHandle로 인스턴스 뮤텍스를 닫지 못했습니다
+mutex-wait-timeout = { $seconds }초 안에 인스턴스 뮤텍스가 생기지 않았습니다
+event-launched = 게임 실행, pid: { $pid }
+event-launched-label = 게임 실행, pid: { $pid } ({ $label })
+event-launch-failed = 게임을 실행하지 못했습니다: { $reason }
+event-mutex-closed = StarCraft.exe.{ $pid }의 proc_handle을 닫았습니다
+event-mutex-close-failed = { $pid }의 인스턴스 뮤텍스를 닫지 못했습니다: { $reason }
+event-handle-hung = pid { $pid }: { $handle }: 이름 조회가 제때 끝나지 않아 건너뜀
+event-affinity-failed = { $pid }의 선호도를 설정하지 못했습니다: { $reason }
+event-adopted = 실행 중인 게임을 편입했습니다, pid: { $pid }
+event-killed = 게임 종료 중, pid: { $pid }
+event-relabeled = pid { $pid } 게임의 라벨을 { $label }(으)로 바꿨습니다
+event-unlabeled = pid { $pid } 게임의 라벨을 지웠습니다
+
+## 선택자와 제한
+
+selector-empty = 선택자가 비어 있습니다.
+selector-bad-count = 선택자 '{ $selector }'의 개수 '{ $count }'이(가) 잘못되었습니다.
+selector-needs-value = 선택자 '{ $selector }'에는 값이 필요합니다. 예: '{ $kind }:name'.
+selector-bad-index = 선택자 '{ $selector }'의 순번이 잘못되었습니다.
+selector-bad-pid = 선택자 '{ $selector }'의 pid가 잘못되었습니다.
+selector-unknown = 알 수 없는 선택자 '{ $selector }'입니다. all, 2, pid:N, oldest[:N], newest[:N], label:NAME, glob:PATTERN, profile:NAME, adopted 중에서 쓰세요.
+limit-too-many-games = 게임은 동시에 { $max }개까지 실행할 수 있지만, { $running }개 실행 중 + { $requested }개 요청입니다.
+limit-too-many = 프로필 '{ $profile }'은(는) 인스턴스를 { $max }개까지 허용하지만, { $running }개 실행 중 + { $requested }개 요청입니다.
+limit-low-memory = 여유 메모리가 { $available } MiB뿐입니다. { $required } MiB가 필요합니다.
+
+## 실행 계획
+
+plan-no-instances = 계획에 [[instance]] 항목이 없습니다.
+plan-label-empty = 라벨이 비어 있습니다.
+plan-label-duplicate = 라벨 '{ $label }'은(는) 이미 { $line }번째 줄에서 쓰고 있습니다.
+plan-window-size = 창 크기는 양수여야 합니다.
+plan-window-both = width와 height를 둘 다 쓰거나 둘 다 빼세요.
+plan-affinity-empty = 선호도에 프로세서가 없습니다.
+plan-affinity-range = 프로세서 { $core }은(는) 없습니다. 이 컴퓨터에는 { $processors }개(0부터 { $last }까지)가 있습니다.
+
+## 진단
+
+doctor-summary = 통과 { $pass }개, 경고 { $warn }개, 실패 { $fail }개 ({ $arch } 기준).
+doctor-admin-ok = 관리자 권한으로 실행 중입니다.
+doctor-admin-missing = 관리자 권한이 아닙니다. launch, kill, scan은 관리자 권한을 요청합니다.
+doctor-debug-enabled = 활성화되어 있습니다.
+doctor-debug-available = 사용 가능 (필요할 때까지 비활성).
+doctor-debug-missing = 사용할 수 없습니다. 다른 사용자가 실행한 게임은 검사할 수 없습니다.
+doctor-registry-ok = InstallLocation = { $location }
+doctor-registry-missing = 읽을 수 없습니다: { $error }
+doctor-portable = 포터블 모드: 레지스트리를 읽지 않고 설정은 실행 파일 옆에 둡니다
+doctor-install-none = 레지스트리에서 설치를 찾지 못했습니다. --game-path를 지정하세요.
+doctor-install-missing = { $path }이(가) 없습니다.
+doctor-exe-missing = { $path }이(가) 없습니다.
+doctor-pe-ok = { $path }은(는) 요청한 대로 { $machine }입니다.
+doctor-pe-mismatch = { $path }은(는) { $machine }이지만 { $arch }을(를) 요청했습니다 (-b는 x86_64를 선택합니다).
+doctor-pe-unreadable = { $path }을(를) 읽지 못했습니다: { $error }
+doctor-version = { $product } { $version }
+doctor-version-missing = { $path }에 버전 리소스가 없습니다.
+doctor-processes-none = 실행 중인 것이 없습니다.
+doctor-processes = { $count }개 실행 중: { $pids }
+doctor-battle-net-none = 실행 중이 아닙니다 (런처에는 필요 없습니다).
+doctor-battle-net = 실행 중: { $processes }
+doctor-mutex-no-game = 실행 중인 StarCraft.exe가 없습니다. 뮤텍스를 찾을 수 있는지 보려면 하나를 실행하세요.
+doctor-mutex-not-found = 어떤 StarCraft.exe에서도 찾지 못했습니다 (이미 닫혔거나 이름이 바뀌었습니다).
+doctor-mutex-found = { $pids }에서 찾았습니다.
+doctor-scan-failed = 스캔에 실패했습니다: { $error }
+doctor-mutex-create-failed = CreateMutexW에 실패했습니다: { $error }
+doctor-handle-renamed = 시험용 뮤텍스가 '{ $name }'(으)로 돌아왔습니다.
+doctor-handle-missing = 시험용 뮤텍스가 핸들 목록에 없습니다.
+doctor-handle-query-failed = ZwQueryInformationProcess에 실패했습니다: NTSTATUS({ $status })
+doctor-handle-ok = 핸들 { $count }개를 나열하고 시험용 뮤텍스 이름을 { $ms } ms 만에 읽었습니다.
+doctor-types-unavailable = 객체 종류 목록을 읽지 못해 핸들마다 종류를 따로 묻습니다.
+doctor-types-ok = 객체 종류 { $count }개를 읽었고 Mutant는 { $index }번입니다.
+doctor-types-mismatch = 시험용 뮤텍스의 종류는 { $index }번인데 목록에서는 '{ $name }'입니다.
@@ -0,0 +1,2473 @@
+use std::{
+    fmt::Display,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+#[cfg(feature = "http")]
+use sclauncher::util::http;
+use sclauncher::util::{
+    admin::{is_admin, run_as_admin},
+    config::{
+        parse_profile_limit, system_config_path, user_config_path, write_game_path, Arch, Config,
+        ConfigSource, Settings, DIRECTORY_FILE,
+    },
+    console::ConsoleCommand,
+    daemon::Daemon,
+    doctor::run_diagnostics,
+    events::GameEvent,
+    fsscan::{cache_path, fixed_drives, FsScan, ScanCache},
+    game::{command_line, find_starcraft_pids, GameManager},
+    handles::{inspect_handles, HandleFilter, ProcessHandles},
+    i18n::{self, Lang},
+    install::{
+        discover_installations, native_arch, rank_installations, select_installation,
+        DiscoverySource, Installation,
+    },
+    instance::{GameInstance, LaunchOptions, MutexScope, DEFAULT_PROFILE},
+    ipc::{IpcClient, Request, Response},
+    journal::Journal,
+    layout::{install_root, InstallLayout, EXE_NAME},
+    limits::{
+        GuardPolicy, LaunchLimits, LimitViolation, Running, WindowsSystemInfo,
+        DEFAULT_MAX_INSTANCES,
+    },
+    output::{Document, ErrorClass, ErrorInfo, EventLine, SCHEMA_VERSION},
+    pattern::KeyPattern,
+    pe::check_arch,
+    plan::{LaunchPlan, OnExit, WindowPlacement},
+    portable,
+    reg::{set_game_path, GamePathUpdate, RegistrySearch},
+    scan::{scan_instance_mutexes, HandleAction, MutexScan},
+    selector::Selector,
+    window::{arrange_windows, move_process_window, Layout},
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use rustyline::{error::ReadlineError, DefaultEditor};
+use sclauncher::t;
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::broadcast;
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+use winconsole::console::{self};
+use winreg::enums::HKEY_LOCAL_MACHINE;
+
+use tokio::time::{sleep, Duration};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "SC1 Mutli Loader",
+    version = "1.0",
+    author = "Seok Won Choi",
+    about = "StarCraft 1 Multi launcher",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Print human-readable text or versioned JSON documents
+    #[arg(short, long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Never read from stdin; missing values become errors (implied when stdin is not a terminal)
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    non_interactive: bool,
+
+    /// Run in this process even when a daemon is running
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    no_daemon: bool,
+
+    /// Language of the messages: en or ko (defaults to the OS language)
+    #[arg(long, global = true)]
+    lang: Option<Lang>,
+
+    /// Keep settings next to the executable and leave the registry alone (on when a
+    /// sclauncher.portable file is next to it)
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    portable: bool,
+
+    /// Running without a subcommand is the same as `launch`
+    #[command(flatten)]
+    launch: LaunchArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Launch StarCraft one or more times
+    Launch(LaunchArgs),
+    /// Show the tracked and adopted StarCraft instances
+    List,
+    /// Kill the StarCraft instances matching a selector
+    Kill(KillArgs),
+    /// Show the install, elevation and running instances
+    Status(LocateArgs),
+    /// Run install discovery only and print the StarCraft.exe path
+    Locate(LocateArgs),
+    /// List every StarCraft installation found, in the order they are offered
+    Installs(InstallsArgs),
+    /// Run in the background, owning the games and the mutex guard, for other CLI calls to control
+    Daemon(DaemonArgs),
+    /// Close the instance mutex in every running StarCraft.exe once
+    Scan(ScanArgs),
+    /// List the handles open in the selected games, with their type and name
+    Handles(HandlesArgs),
+    /// Inspect the layered configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Review, undo or export the registry changes the launcher made
+    #[command(subcommand)]
+    Registry(RegistryCommand),
+    /// Check the install, privileges and handle access, and report what would stop a launch
+    Doctor(LocateArgs),
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the effective settings and the file, variable or flag each one came from
+    Show,
+}
+
+#[derive(Subcommand, Debug)]
+enum RegistryCommand {
+    /// List every registry change in the journal
+    Log,
+    /// Put back what the changes replaced, every one not restored yet unless --id is given
+    Restore {
+        /// Only restore the change with this number, as shown by `registry log`
+        #[arg(long)]
+        id: Option<u64>,
+    },
+    /// Write the changes to a .reg file for regedit or `reg import`
+    Export {
+        path: PathBuf,
+
+        /// Write the values from before the changes instead, undoing them when imported
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        undo: bool,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+struct LocateArgs {
+    /// Use this StarCraft folder or StarCraft.exe instead of searching
+    #[arg(short, long)]
+    game_path: Option<PathBuf>,
+
+    /// Performs an asynchronous search in the registry
+    #[arg(short, long, action = clap::ArgAction::SetTrue)]
+    async_registry_search: bool,
+
+    /// Walk the fixed drives for StarCraft folders when nothing else finds one
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    scan_drives: bool,
+
+    /// 64bits or 32bits
+    #[arg(short = 'b', long, default_value_t = false)]
+    is_64bit: bool,
+
+    /// x86 or x86_64, overriding the configured architecture (-b is the same as x86_64)
+    #[arg(long, conflicts_with = "is_64bit")]
+    arch: Option<Arch>,
+
+    /// Which of several installs to use: its number in `installs`, or remastered, legacy or ptr
+    #[arg(long, conflicts_with = "game_path")]
+    install: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+struct LaunchArgs {
+    #[command(flatten)]
+    locate: LocateArgs,
+
+    /// Number of times to launch the game (asks when omitted and not configured)
+    #[arg(short, long)]
+    num_launches: Option<u32>,
+
+    /// Profile the launched games belong to
+    #[arg(short, long)]
+    profile: Option<String>,
+
+    /// Labels for the launched games, in launch order (e.g. --label main,bot1)
+    #[arg(short, long = "label", value_delimiter = ',')]
+    labels: Vec<String>,
+
+    /// Launch the session described in a TOML plan file instead
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["num_launches", "labels"])]
+    plan: Option<PathBuf>,
+
+    /// Milliseconds to wait between two launches
+    #[arg(long, value_name = "MS")]
+    launch_delay: Option<u64>,
+
+    /// Arrange the game windows after launching: none, grid or cascade
+    #[arg(long)]
+    layout: Option<Layout>,
+
+    /// Show the command lines, layout, limit checks and mutex handles without launching anything
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    dry_run: bool,
+
+    /// Exit right after launching and leave the games running
+    #[arg(short, long, action = clap::ArgAction::SetTrue)]
+    detach: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    /// Close the instance mutex of any running StarCraft.exe, not only the launched one
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    scan_all: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+struct LimitArgs {
+    /// Maximum number of StarCraft.exe allowed to run at once (8 unless configured)
+    #[arg(long)]
+    max_instances: Option<u32>,
+
+    /// Maximum for one profile, overriding --max-instances (e.g. --profile-max-instances ladder=2)
+    #[arg(long, value_name = "PROFILE=N", value_delimiter = ',', value_parser = parse_profile_limit)]
+    profile_max_instances: Vec<(String, u32)>,
+
+    /// Only warn (instead of refusing) when the instance or memory limits are exceeded
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    ignore_limits: bool,
+}
+
+impl LimitArgs {
+    fn max_instances(&self) -> u32 {
+        self.max_instances.unwrap_or(DEFAULT_MAX_INSTANCES)
+    }
+}
+
+impl LaunchArgs {
+    /// Number of games to launch, 0 when it still has to be asked.
+    fn count(&self) -> u32 {
+        self.num_launches.unwrap_or(0)
+    }
+
+    fn profile(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    fn launch_delay(&self) -> Duration {
+        Duration::from_millis(self.launch_delay.unwrap_or(1000))
+    }
+
+    fn mutex_scope(&self) -> MutexScope {
+        if self.scan_all {
+            MutexScope::System
+        } else {
+            MutexScope::Launched
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct DaemonArgs {
+    #[command(flatten)]
+    locate: LocateArgs,
+
+    /// Do not keep closing the instance mutex of every StarCraft.exe in the background
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    no_guard: bool,
+
+    /// Limits the daemon enforces on every launch it is asked for
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    /// Stop the running daemon instead of starting one
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    stop: bool,
+
+    /// Also serve the HTTP/JSON API, on 127.0.0.1:7878 unless an address is given
+    #[cfg(feature = "http")]
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = http::DEFAULT_BIND)]
+    http: Option<std::net::SocketAddr>,
+
+    /// Token HTTP clients must send; a random one is printed when omitted
+    #[cfg(feature = "http")]
+    #[arg(long, requires = "http")]
+    token: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct InstallsArgs {
+    /// Also run the full registry search
+    #[arg(short, long, action = clap::ArgAction::SetTrue)]
+    async_registry_search: bool,
+
+    /// Walk the fixed drives for StarCraft folders (with -a even when others were found)
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    scan_drives: bool,
+}
+
+#[derive(Args, Debug)]
+struct ScanArgs {
+    /// Only report which handles would be closed
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct HandlesArgs {
+    /// Which games to inspect, e.g. all, 2, pid:1234, newest:2, label:bot*
+    #[arg(conflicts_with = "select")]
+    selector: Option<Selector>,
+
+    /// Same as the positional selector
+    #[arg(short, long)]
+    select: Option<Selector>,
+
+    /// Only show handles of this object type, e.g. Mutant, Event or File
+    #[arg(long = "type", value_name = "TYPE")]
+    type_name: Option<String>,
+
+    /// Only show handles whose object name matches this regular expression
+    #[arg(long, value_name = "REGEX")]
+    name: Option<Regex>,
+}
+
+#[derive(Args, Debug)]
+struct KillArgs {
+    /// Which games to kill, e.g. all, 2, pid:1234, newest:2, label:bot*
+    #[arg(conflicts_with = "select", required_unless_present = "select")]
+    selector: Option<Selector>,
+
+    /// Same as the positional selector
+    #[arg(short, long)]
+    select: Option<Selector>,
+}
+
+impl KillArgs {
+    /// Killing every game by accident is too easy, so there is no default selector.
+    fn selector(self) -> Selector {
+        self.selector
+            .or(self.select)
+            .expect("clap requires a selector")
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Prints progress and results either as text or as JSON.
+///
+/// In JSON mode, events are printed as one JSON object per line and every command ends
+/// with exactly one `Document` line. Prompts go to stderr so stdout stays parseable.
+struct Reporter {
+    format: OutputFormat,
+    command: &'static str,
+    /// `false` when prompts must not block on stdin
+    interactive: bool,
+}
+
+impl Reporter {
+    fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Fails with a usage error instead of prompting when running non-interactively.
+    fn require_interactive(&self, missing: &str) -> Result<(), ErrorInfo> {
+        if self.interactive {
+            Ok(())
+        } else {
+            Err(ErrorInfo::new(
+                ErrorClass::Usage,
+                t!("not-interactive", missing = missing),
+            ))
+        }
+    }
+
+    fn event<E: Serialize + Display>(&self, event: &E) {
+        if self.is_json() {
+            let line = EventLine {
+                version: SCHEMA_VERSION,
+                command: self.command,
+                event,
+            };
+            println!("{}", serde_json::to_string(&line).unwrap());
+        } else {
+            println!("{}", event);
+        }
+    }
+
+    /// Prints every `GameEvent` published since the last call.
+    fn drain(&self, events: &mut broadcast::Receiver<GameEvent>) {
+        while let Ok(event) = events.try_recv() {
+            self.event(&event);
+        }
+    }
+
+    fn warn(&self, message: impl Display) {
+        if self.is_json() {
+            self.event(&Notice(
+                json!({ "event": "warning", "message": message.to_string() }),
+            ));
+        } else {
+            println!("{}", t!("warning", message = message));
+        }
+    }
+
+    /// Free-form progress text, only shown in text mode.
+    fn info(&self, message: impl Display) {
+        if !self.is_json() {
+            println!("{}", message);
+        }
+    }
+
+    fn prompt(&self, message: impl Display) {
+        if self.is_json() {
+            eprint!("{}", message);
+            std::io::stderr().flush().expect("Failed to flush stderr");
+        } else {
+            print!("{}", message);
+            std::io::stdout().flush().expect("Failed to flush stdout");
+        }
+    }
+
+    fn finish<T: Serialize>(&self, data: T) {
+        if self.is_json() {
+            let document = Document::success(self.command, data);
+            println!("{}", serde_json::to_string(&document).unwrap());
+        }
+    }
+
+    fn fail(&self, error: ErrorInfo) -> ExitCode {
+        let code = error.exit_code;
+        if self.is_json() {
+            let document = Document::<()>::failure(self.command, error);
+            println!("{}", serde_json::to_string(&document).unwrap());
+        } else {
+            eprintln!("{}", error.message);
+        }
+        ExitCode::from(code)
+    }
+}
+
+/// An ad-hoc JSON event that has no text form.
+struct Notice(serde_json::Value);
+
+impl Serialize for Notice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl Display for Notice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+type CommandResult = Result<(), ErrorInfo>;
+
+#[tokio::main]
+pub async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Launch(cli.launch));
+    let reporter = Reporter {
+        interactive: !cli.non_interactive && std::io::stdin().is_terminal(),
+        format: cli.output,
+        command: match command {
+            Command::Launch(_) => "launch",
+            Command::List => "list",
+            Command::Kill(_) => "kill",
+            Command::Status(_) => "status",
+            Command::Locate(_) => "locate",
+            Command::Installs(_) => "installs",
+            Command::Daemon(_) => "daemon",
+            Command::Scan(_) => "scan",
+            Command::Handles(_) => "handles",
+            Command::Config(_) => "config",
+            Command::Registry(_) => "registry",
+            Command::Doctor(_) => "doctor",
+        },
+    };
+
+    if cli.portable {
+        portable::enable();
+    }
+
+    // Files and SCLAUNCHER_* variables fill in whatever the flags leave out
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            if let Some(lang) = cli.lang {
+                i18n::set_language(lang);
+            }
+            return reporter.fail(ErrorInfo::new(
+                ErrorClass::Usage,
+                t!("config-invalid", error = e),
+            ));
+        }
+    };
+    config.apply(
+        Settings {
+            lang: cli.lang,
+            ..Settings::default()
+        },
+        ConfigSource::Cli,
+    );
+    i18n::set_language(config.lang());
+    let mut command = command;
+    apply_config(&mut config, &mut command);
+
+    // Hand the command to a running daemon, which already has admin rights.
+    // Plans set up windows and affinity, and dry runs must not touch anything, so they run here.
+    if !cli.no_daemon
+        && matches!(
+            &command,
+            Command::Launch(LaunchArgs {
+                plan: None,
+                dry_run: false,
+                ..
+            }) | Command::List
+                | Command::Kill(_)
+                | Command::Status(_)
+        )
+    {
+        if let Ok(client) = IpcClient::connect().await {
+            return match run_remote(&reporter, client, command).await {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => reporter.fail(error),
+            };
+        }
+    }
+
+    // Only commands that touch other processes need admin rights
+    let needs_admin = match &command {
+        Command::Launch(args) => !args.dry_run,
+        Command::Scan(args) => !args.dry_run,
+        Command::Kill(_) => true,
+        Command::Daemon(args) => !args.stop,
+        Command::Registry(RegistryCommand::Restore { .. }) => !portable::enabled(),
+        _ => false,
+    };
+    if needs_admin && !is_admin() {
+        // An elevated window would swallow the JSON output and a UAC prompt blocks scripts,
+        // so both must elevate themselves
+        if reporter.is_json() || !reporter.interactive {
+            return reporter.fail(ErrorInfo::new(
+                ErrorClass::NotElevated,
+                t!("admin-required"),
+            ));
+        }
+
+        println!("{}", t!("admin-elevating"));
+        if run_as_admin() {
+            println!("{}", t!("admin-elevated-window"));
+            return ExitCode::SUCCESS;
+        } else {
+            return reporter.fail(ErrorInfo::new(
+                ErrorClass::NotElevated,
+                t!("admin-elevate-failed"),
+            ));
+        }
+    }
+
+    let result = match command {
+        Command::Launch(args) => launch(&reporter, args).await,
+        Command::List => list(&reporter).await,
+        Command::Kill(args) => kill(&reporter, args).await,
+        Command::Status(args) => status(&reporter, args).await,
+        Command::Locate(args) => locate(&reporter, args).await,
+        Command::Installs(args) => installs(&reporter, args).await,
+        Command::Daemon(args) => daemon(&reporter, args).await,
+        Command::Scan(args) => scan(&reporter, args).await,
+        Command::Handles(args) => handles(&reporter, args).await,
+        Command::Config(ConfigCommand::Show) => config_show(&reporter, &config),
+        Command::Registry(RegistryCommand::Log) => registry_log(&reporter),
+        Command::Registry(RegistryCommand::Restore { id }) => registry_restore(&reporter, id),
+        Command::Registry(RegistryCommand::Export { path, undo }) => {
+            registry_export(&reporter, &path, undo)
+        }
+        Command::Doctor(args) => doctor(&reporter, args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => reporter.fail(error),
+    }
+}
+
+async fn launch(reporter: &Reporter, mut args: LaunchArgs) -> CommandResult {
+    // Started without a console (e.g. from a scheduled task) there is no title to set
+    let _ = console::set_title("SC1 Multi Launcher");
+
+    if args.dry_run {
+        return dry_run_launch(reporter, args).await;
+    }
+    if let Some(plan_path) = args.plan.clone() {
+        return launch_plan(reporter, args, &plan_path).await;
+    }
+
+    // ask user for input
+    if args.count() == 0 {
+        reporter.require_interactive(&t!("need-count"))?;
+        args.num_launches = Some(prompt_user_for_n(reporter));
+    }
+
+    // Setup and run the game management logic
+    let game_manager = GameManager::new();
+    let mut events = game_manager.subscribe();
+    let game_path = get_game_path_or_search(reporter, &args.locate, true, true)
+        .await
+        .map_err(|e| {
+            ErrorInfo::new(
+                ErrorClass::InstallNotFound,
+                t!("game-path-error", error = e),
+            )
+        })?;
+
+    // Check the guardrails before starting anything
+    check_limits(reporter, &args, None)?;
+
+    // Launch the game the specified number of times
+    let launched =
+        launch_game_multiple_times(reporter, &mut events, &game_manager, &game_path, &args).await;
+    if launched == 0 && args.count() > 0 {
+        return Err(ErrorInfo::new(ErrorClass::LaunchFailed, t!("launch-none")));
+    }
+
+    let pids: Vec<u32> = game_manager
+        .instances()
+        .await
+        .iter()
+        .map(|i| i.pid)
+        .collect();
+    arrange_launched_windows(reporter, &pids, args.layout.unwrap_or_default()).await;
+
+    if args.detach {
+        reporter.info(t!("detached"));
+        reporter.finish(LaunchReport {
+            game_path: &game_path,
+            detached: true,
+            instances: game_manager.instances().await,
+        });
+        return Ok(());
+    }
+
+    let keep = supervise(
+        reporter,
+        &game_manager,
+        &mut events,
+        &game_path,
+        &args,
+        &t!("session-kill-all"),
+    )
+    .await;
+    game_manager.stop_guard();
+    let instances = game_manager.instances().await;
+
+    // Ensure games are killed before exiting
+    if keep {
+        reporter.info(t!("leaving-running"));
+    } else {
+        game_manager.kill_all_games().await;
+    }
+    reporter.drain(&mut events);
+    reporter.finish(LaunchReport {
+        game_path: &game_path,
+        detached: false,
+        instances,
+    });
+    Ok(())
+}
+
+/// Runs the session console until the user quits; returns `true` when the games should be kept.
+///
+/// Non-interactive runs wait for Ctrl+C (or the console closing) instead.
+async fn supervise(
+    reporter: &Reporter,
+    game_manager: &GameManager,
+    events: &mut broadcast::Receiver<GameEvent>,
+    game_path: &Path,
+    args: &LaunchArgs,
+    on_quit: &str,
+) -> bool {
+    if !reporter.interactive {
+        // Nobody can type, so keep the games until Ctrl+C (or the console closing)
+        reporter.info(t!("wait-ctrl-c", action = on_quit));
+        wait_for_shutdown_signal().await;
+        return false;
+    }
+
+    let mut lines = LineReader::new(reporter);
+    reporter.prompt(format!("{}\n", t!("console-intro", action = on_quit)));
+
+    loop {
+        let Some(line) = lines.read(reporter, "sclauncher> ") else {
+            // Ctrl+C, Ctrl+D or stdin closing
+            return false;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = match line.parse::<ConsoleCommand>() {
+            Ok(command) => command,
+            Err(e) => {
+                reporter.warn(e);
+                continue;
+            }
+        };
+        match command {
+            ConsoleCommand::List => {
+                let instances = game_manager.instances().await;
+                if reporter.is_json() {
+                    reporter.event(&Notice(
+                        json!({ "event": "instances", "instances": instances }),
+                    ));
+                } else {
+                    print_instances(&instances);
+                }
+            }
+            ConsoleCommand::Launch(count) => {
+                let more = LaunchArgs {
+                    num_launches: Some(count),
+                    labels: Vec::new(),
+                    ..args.clone()
+                };
+                let in_profile = profile_count(&game_manager.instances().await, more.profile());
+                if let Err(error) = check_limits(reporter, &more, Some(in_profile)) {
+                    reporter.warn(error.message);
+                    continue;
+                }
+                let launched =
+                    launch_game_multiple_times(reporter, events, game_manager, game_path, &more)
+                        .await;
+                if launched < count {
+                    reporter.warn(t!(
+                        "console-launched-partial",
+                        launched = launched,
+                        count = count
+                    ));
+                }
+            }
+            ConsoleCommand::Kill(selector) => {
+                if game_manager.kill_selected(&selector).await.is_empty() {
+                    reporter.warn(t!("no-match", selector = &selector));
+                }
+            }
+            ConsoleCommand::Relabel { selector, label } => {
+                match game_manager.select(&selector).await.as_slice() {
+                    [] => reporter.warn(t!("no-match", selector = &selector)),
+                    [target] => {
+                        game_manager.relabel(target.pid, label).await;
+                    }
+                    matched => reporter.warn(t!(
+                        "relabel-ambiguous",
+                        selector = &selector,
+                        count = matched.len()
+                    )),
+                }
+            }
+            ConsoleCommand::Layout(layout) => {
+                let pids: Vec<u32> = game_manager
+                    .instances()
+                    .await
+                    .iter()
+                    .map(|i| i.pid)
+                    .collect();
+                arrange_launched_windows(reporter, &pids, layout).await;
+            }
+            ConsoleCommand::Guard(true) => {
+                if game_manager.start_guard() {
+                    reporter.info(t!("guard-started"));
+                } else {
+                    reporter.warn(t!("guard-already-running"));
+                }
+            }
+            ConsoleCommand::Guard(false) => {
+                if game_manager.stop_guard() {
+                    reporter.info(t!("guard-stopped"));
+                } else {
+                    reporter.warn(t!("guard-not-running"));
+                }
+            }
+            ConsoleCommand::Quit { keep } => return keep,
+            ConsoleCommand::Help => reporter.prompt(format!("{}\n", t!("console-help"))),
+        }
+        reporter.drain(events);
+    }
+}
+
+/// Reads console lines with editing and history, or plainly from stdin in JSON mode
+/// (where stdout must stay parseable) and when no terminal editor can be set up.
+enum LineReader {
+    Editor(Box<DefaultEditor>),
+    Plain,
+}
+
+impl LineReader {
+    fn new(reporter: &Reporter) -> Self {
+        if reporter.is_json() {
+            return LineReader::Plain;
+        }
+        match DefaultEditor::new() {
+            Ok(editor) => LineReader::Editor(Box::new(editor)),
+            Err(_) => LineReader::Plain,
+        }
+    }
+
+    /// Returns `None` when the user or stdin ends the input.
+    fn read(&mut self, reporter: &Reporter, prompt: &str) -> Option<String> {
+        match self {
+            LineReader::Editor(editor) => match editor.readline(prompt) {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        let _ = editor.add_history_entry(line.as_str());
+                    }
+                    Some(line)
+                }
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) => None,
+                Err(e) => {
+                    reporter.warn(t!("console-read-failed", error = e));
+                    None
+                }
+            },
+            LineReader::Plain => {
+                reporter.prompt(prompt);
+                let mut line = String::new();
+                match std::io::stdin().read_line(&mut line) {
+                    Ok(0) | Err(_) => None,
+                    Ok(_) => Some(line),
+                }
+            }
+        }
+    }
+}
+
+/// Runs a launch plan file: every instance with its own settings, then the session's exit action.
+async fn launch_plan(reporter: &Reporter, mut args: LaunchArgs, plan_path: &Path) -> CommandResult {
+    // Nothing starts until the whole plan is valid
+    let plan = load_plan(plan_path)?;
+    apply_plan_session(&plan, &mut args);
+
+    let game_manager = GameManager::new();
+    let mut events = game_manager.subscribe();
+    let game_path = get_game_path_or_search(reporter, &args.locate, true, true)
+        .await
+        .map_err(|e| {
+            ErrorInfo::new(
+                ErrorClass::InstallNotFound,
+                t!("game-path-error", error = e),
+            )
+        })?;
+    check_limits(reporter, &args, None)?;
+
+    if plan.session.keep_guard {
+        game_manager.start_guard();
+    }
+
+    let mut placements = Vec::new();
+    for (i, instance) in plan.instances.iter().enumerate() {
+        let instance = instance.get_ref();
+        if i > 0 {
+            sleep(plan.session.stagger()).await;
+        }
+        reporter.info(format!(" ~ {}", t!("launching", number = i + 1)));
+        let options = LaunchOptions {
+            mutex_scope: args.mutex_scope(),
+            ..instance.launch_options(args.profile())
+        };
+        if let Some(pid) = game_manager
+            .launch_game_with(game_path.clone(), options)
+            .await
+        {
+            if let Some(window) = instance.window() {
+                placements.push((pid, window));
+            }
+        }
+        reporter.drain(&mut events);
+    }
+
+    if game_manager.instances().await.is_empty() {
+        game_manager.stop_guard();
+        return Err(ErrorInfo::new(ErrorClass::LaunchFailed, t!("launch-none")));
+    }
+    place_windows(reporter, placements).await;
+
+    if args.detach {
+        if plan.session.keep_guard {
+            reporter.warn(t!("plan-guard-stops"));
+        }
+        reporter.info(t!("detached"));
+        reporter.finish(LaunchReport {
+            game_path: &game_path,
+            detached: true,
+            instances: game_manager.instances().await,
+        });
+        return Ok(());
+    }
+
+    let on_quit = match plan.session.on_exit {
+        OnExit::Kill => t!("session-kill-all"),
+        OnExit::Keep => t!("session-keep"),
+    };
+    let keep = supervise(
+        reporter,
+        &game_manager,
+        &mut events,
+        &game_path,
+        &args,
+        &on_quit,
+    )
+    .await;
+    game_manager.stop_guard();
+    let instances = game_manager.instances().await;
+
+    match plan.session.on_exit {
+        OnExit::Kill if !keep => game_manager.kill_all_games().await,
+        _ => reporter.info(t!("leaving-running")),
+    }
+    reporter.drain(&mut events);
+    reporter.finish(LaunchReport {
+        game_path: &game_path,
+        detached: false,
+        instances,
+    });
+    Ok(())
+}
+
+fn load_plan(plan_path: &Path) -> Result<LaunchPlan, ErrorInfo> {
+    LaunchPlan::load(plan_path).map_err(|errors| {
+        let lines: Vec<String> = errors
+            .iter()
+            .map(|e| match e.line {
+                Some(line) => format!("{}:{}: {}", plan_path.display(), line, e.message),
+                None => format!("{}: {}", plan_path.display(), e.message),
+            })
+            .collect();
+        ErrorInfo::new(
+            ErrorClass::Usage,
+            format!("{}\n{}", t!("plan-invalid"), lines.join("\n")),
+        )
+    })
+}
+
+/// The plan's session settings win over the configuration.
+fn apply_plan_session(plan: &LaunchPlan, args: &mut LaunchArgs) {
+    if let Some(game_path) = &plan.session.game_path {
+        args.locate.game_path = Some(game_path.clone());
+    }
+    if let Some(arch) = plan.session.arch {
+        args.locate.arch = Some(arch);
+        args.locate.is_64bit = arch == Arch::X86_64;
+    }
+    if let Some(profile) = &plan.session.profile {
+        args.profile = Some(profile.clone());
+    }
+    args.num_launches = Some(plan.instances.len() as u32);
+    args.launch_delay = Some(plan.session.stagger_ms);
+}
+
+/// What `launch` would start, in order.
+#[derive(Serialize)]
+struct PlannedLaunch {
+    label: Option<String>,
+    profile: String,
+    command_line: String,
+    window: Option<WindowPlacement>,
+    affinity: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct DryRunReport<'a> {
+    game_path: &'a Path,
+    launch_delay_ms: u64,
+    layout: Layout,
+    launches: Vec<PlannedLaunch>,
+    limit_violations: Vec<LimitViolation>,
+    would_refuse: bool,
+    scan: MutexScan,
+}
+
+/// Resolves everything `launch` would do and prints it, without starting, killing or writing anything.
+async fn dry_run_launch(reporter: &Reporter, mut args: LaunchArgs) -> CommandResult {
+    let plan = match &args.plan {
+        Some(plan_path) => {
+            let plan = load_plan(plan_path)?;
+            apply_plan_session(&plan, &mut args);
+            Some(plan)
+        }
+        None => None,
+    };
+    if args.count() == 0 {
+        reporter.require_interactive(&t!("need-count"))?;
+        args.num_launches = Some(prompt_user_for_n(reporter));
+    }
+
+    // Discovery may still search, but never writes the registry in a dry run
+    let game_path = get_game_path_or_search(reporter, &args.locate, true, false)
+        .await
+        .map_err(|e| {
+            ErrorInfo::new(
+                ErrorClass::InstallNotFound,
+                t!("game-path-error", error = e),
+            )
+        })?;
+    let exe = game_path.to_str().unwrap_or_default();
+
+    let launches: Vec<PlannedLaunch> = match &plan {
+        Some(plan) => plan
+            .instances
+            .iter()
+            .map(|instance| {
+                let instance = instance.get_ref();
+                let options = LaunchOptions {
+                    mutex_scope: args.mutex_scope(),
+                    ..instance.launch_options(args.profile())
+                };
+                PlannedLaunch {
+                    command_line: command_line(exe, &options.command_args()),
+                    label: options.label,
+                    profile: options.profile,
+                    window: instance.window(),
+                    affinity: options.affinity,
+                }
+            })
+            .collect(),
+        None => (0..args.count())
+            .map(|i| {
+                let options = LaunchOptions {
+                    label: args.labels.get(i as usize).cloned(),
+                    profile: args.profile().to_string(),
+                    ..LaunchOptions::default()
+                };
+                PlannedLaunch {
+                    command_line: command_line(exe, &options.command_args()),
+                    label: options.label,
+                    profile: options.profile,
+                    window: None,
+                    affinity: None,
+                }
+            })
+            .collect(),
+    };
+
+    let (limit_violations, would_refuse) = match launch_limits(&args.limits).check(
+        &WindowsSystemInfo,
+        args.profile(),
+        running_games(None),
+        args.count(),
+    ) {
+        Ok(warnings) => (warnings, false),
+        Err(violations) => (violations, true),
+    };
+
+    if !is_admin() {
+        reporter.warn(t!("not-admin-inspect"));
+    }
+    let scan = scan_instance_mutexes(true).map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+
+    let report = DryRunReport {
+        game_path: &game_path,
+        launch_delay_ms: args.launch_delay().as_millis() as u64,
+        layout: args.layout.unwrap_or_default(),
+        launches,
+        limit_violations,
+        would_refuse,
+        scan,
+    };
+    if reporter.is_json() {
+        reporter.finish(report);
+        return Ok(());
+    }
+
+    println!("{}", t!("dry-run-header"));
+    println!(
+        "{}",
+        t!("dry-run-install", path = report.game_path.display())
+    );
+    println!(
+        "{}",
+        t!(
+            "dry-run-summary",
+            count = report.launches.len(),
+            delay = report.launch_delay_ms,
+            layout = report.layout
+        )
+    );
+    for (i, launch) in report.launches.iter().enumerate() {
+        println!("  [{}] {}", i + 1, launch.command_line);
+        let mut details = vec![t!("dry-run-profile", profile = &launch.profile)];
+        if let Some(label) = &launch.label {
+            details.push(t!("dry-run-label", label = label));
+        }
+        if let Some(window) = &launch.window {
+            details.push(match window.size() {
+                Some((width, height)) => t!(
+                    "dry-run-window-sized",
+                    width = width,
+                    height = height,
+                    x = window.x,
+                    y = window.y
+                ),
+                None => t!("dry-run-window", x = window.x, y = window.y),
+            });
+        }
+        if let Some(affinity) = launch.affinity {
+            details.push(t!("dry-run-affinity", mask = format!("{:#x}", affinity)));
+        }
+        println!("      {}", details.join(", "));
+    }
+    if report.limit_violations.is_empty() {
+        println!("{}", t!("dry-run-limits-ok"));
+    } else {
+        for violation in &report.limit_violations {
+            if report.would_refuse {
+                println!("{}", t!("dry-run-limits-refuse", reason = violation));
+            } else {
+                println!("{}", t!("dry-run-limits-warn", reason = violation));
+            }
+        }
+    }
+    println!("{}", t!("dry-run-running"));
+    print_scan(&report.scan);
+    Ok(())
+}
+
+async fn scan(reporter: &Reporter, args: ScanArgs) -> CommandResult {
+    if args.dry_run && !is_admin() {
+        reporter.warn(t!("not-admin-inspect"));
+    }
+    let scan = scan_instance_mutexes(args.dry_run).map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+
+    if reporter.is_json() {
+        reporter.finish(scan);
+        return Ok(());
+    }
+    if args.dry_run {
+        println!("{}", t!("scan-dry-run"));
+    }
+    print_scan(&scan);
+    Ok(())
+}
+
+async fn handles(reporter: &Reporter, args: HandlesArgs) -> CommandResult {
+    let selector = args.selector.or(args.select).unwrap_or(Selector::All);
+    if !is_admin() {
+        reporter.warn(t!("not-admin-inspect"));
+    }
+
+    let game_manager = GameManager::new();
+    game_manager.adopt_running_games().await.map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+    let instances = game_manager.select(&selector).await;
+    if instances.is_empty() {
+        return Err(ErrorInfo::new(
+            ErrorClass::NoMatch,
+            t!("no-match", selector = &selector),
+        ));
+    }
+
+    let filter = HandleFilter {
+        type_name: args.type_name,
+        name: args.name,
+    };
+    let processes: Vec<ProcessHandles> = instances
+        .iter()
+        .map(|instance| inspect_handles(instance.pid, &filter))
+        .collect();
+
+    if reporter.is_json() {
+        reporter.finish(json!({ "selector": selector.to_string(), "processes": processes }));
+        return Ok(());
+    }
+    for process in &processes {
+        print_handles(process);
+    }
+    Ok(())
+}
+
+fn print_handles(process: &ProcessHandles) {
+    if let Some(error) = &process.error {
+        println!(
+            "{}",
+            t!("scan-process-error", pid = process.pid, error = error)
+        );
+        return;
+    }
+    println!(
+        "{}",
+        t!(
+            "handles-process",
+            pid = process.pid,
+            handles = process.handle_count,
+            shown = process.handles.len()
+        )
+    );
+    for handle in &process.hung_handles {
+        println!("  {}", t!("scan-hung", handle = format!("{:#x}", handle)));
+    }
+    if process.handles.is_empty() {
+        return;
+    }
+
+    let type_width = process
+        .handles
+        .iter()
+        .filter_map(|handle| handle.type_name.as_ref().map(String::len))
+        .max()
+        .unwrap_or(0)
+        .max(t!("column-type").len());
+    println!(
+        "  {:<8}  {:<10}  {:<type_width$}  {}",
+        t!("column-handle"),
+        t!("column-access"),
+        t!("column-type"),
+        t!("column-object")
+    );
+    for handle in &process.handles {
+        println!(
+            "  {:<#8x}  {:#010x}  {:<type_width$}  {}",
+            handle.handle,
+            handle.granted_access,
+            handle.type_name.as_deref().unwrap_or("?"),
+            handle.name.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+fn doctor(reporter: &Reporter, args: LocateArgs) -> CommandResult {
+    let arch = args.arch.unwrap_or_else(native_arch);
+    let report = run_diagnostics(args.game_path.as_deref(), arch);
+
+    if reporter.is_json() {
+        reporter.finish(report);
+        return Ok(());
+    }
+
+    println!("{}", report);
+    Ok(())
+}
+
+fn print_scan(scan: &MutexScan) {
+    if scan.processes.is_empty() {
+        println!("  {}", t!("no-games-running"));
+        return;
+    }
+    for process in &scan.processes {
+        match &process.error {
+            Some(error) => println!(
+                "  {}",
+                t!("scan-process-error", pid = process.pid, error = error)
+            ),
+            None => println!(
+                "  {}",
+                t!(
+                    "scan-process",
+                    pid = process.pid,
+                    handles = process.handle_count,
+                    matches = process.matches.len()
+                )
+            ),
+        }
+        for handle in &process.matches {
+            let action = match handle.action {
+                HandleAction::WouldClose => t!("scan-would-close"),
+                HandleAction::Closed => t!("scan-closed"),
+                HandleAction::CloseFailed => t!("scan-close-failed"),
+            };
+            println!("    {:#06x} {} ({})", handle.handle, handle.name, action);
+        }
+        for handle in &process.hung_handles {
+            println!(
+                "    {}",
+                t!("scan-hung", handle = format!("{:#06x}", handle))
+            );
+        }
+    }
+}
+
+/// Waits a few seconds for the games to open their windows, then moves them into place.
+async fn place_windows(reporter: &Reporter, mut pending: Vec<(u32, WindowPlacement)>) {
+    for _ in 0..20 {
+        pending
+            .retain(|(pid, window)| !move_process_window(*pid, window.x, window.y, window.size()));
+        if pending.is_empty() {
+            return;
+        }
+        sleep(Duration::from_millis(500)).await;
+    }
+    for (pid, _) in pending {
+        reporter.warn(t!("window-place-failed", pid = pid));
+    }
+}
+
+#[derive(Serialize)]
+struct LaunchReport<'a> {
+    game_path: &'a Path,
+    detached: bool,
+    instances: Vec<GameInstance>,
+}
+
+async fn list(reporter: &Reporter) -> CommandResult {
+    let game_manager = GameManager::new();
+    game_manager.adopt_running_games().await.map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+
+    let instances = game_manager.instances().await;
+    if reporter.is_json() {
+        reporter.finish(json!({ "instances": instances }));
+        return Ok(());
+    }
+
+    print_instances(&instances);
+    Ok(())
+}
+
+fn print_instances(instances: &[GameInstance]) {
+    if instances.is_empty() {
+        println!("{}", t!("no-games-running"));
+        return;
+    }
+
+    println!(
+        "{:>3}  {:>7}  {:<12}  {:<10}  {:<7}  {}",
+        "#",
+        "PID",
+        t!("column-label"),
+        t!("column-profile"),
+        t!("column-adopted"),
+        t!("column-path")
+    );
+    for (index, instance) in instances.iter().enumerate() {
+        println!(
+            "{:>3}  {:>7}  {:<12}  {:<10}  {:<7}  {}",
+            index + 1,
+            instance.pid,
+            instance.label.as_deref().unwrap_or("-"),
+            instance.profile,
+            if instance.adopted {
+                t!("yes")
+            } else {
+                t!("no")
+            },
+            instance
+                .game_path
+                .as_ref()
+                .map_or("-".to_string(), |path| path.display().to_string())
+        );
+    }
+}
+
+/// Every running StarCraft.exe, with `in_profile` games of the profile being launched when a
+/// game manager or the daemon tracks them.
+fn running_games(in_profile: Option<u32>) -> Running {
+    Running {
+        total: find_starcraft_pids().map_or(0, |pids| pids.len() as u32),
+        in_profile,
+    }
+}
+
+fn profile_count(instances: &[GameInstance], profile: &str) -> u32 {
+    instances
+        .iter()
+        .filter(|instance| instance.profile == profile)
+        .count() as u32
+}
+
+/// Refuses (or warns about) a launch that would exceed the instance or memory limits.
+///
+/// Without `in_profile` only the machine-wide maximum is checked, as nothing tells which
+/// profile a StarCraft.exe started elsewhere belongs to.
+fn check_limits(reporter: &Reporter, args: &LaunchArgs, in_profile: Option<u32>) -> CommandResult {
+    let running = running_games(in_profile);
+    match launch_limits(&args.limits).check(
+        &WindowsSystemInfo,
+        args.profile(),
+        running,
+        args.count(),
+    ) {
+        Ok(warnings) => {
+            for warning in warnings {
+                reporter.warn(warning);
+            }
+        }
+        Err(violations) => {
+            let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            return Err(ErrorInfo::new(
+                ErrorClass::LimitExceeded,
+                t!("limits-refuse", reasons = reasons.join(" ")),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn launch_limits(args: &LimitArgs) -> LaunchLimits {
+    LaunchLimits {
+        max_instances: args.max_instances(),
+        profile_max_instances: args.profile_max_instances.iter().cloned().collect(),
+        policy: if args.ignore_limits {
+            GuardPolicy::Warn
+        } else {
+            GuardPolicy::Refuse
+        },
+        ..LaunchLimits::default()
+    }
+}
+
+/// Sends one request to the daemon, turning transport failures and error responses into `ErrorInfo`.
+async fn call(client: &mut IpcClient, request: Request) -> Result<Response, ErrorInfo> {
+    match client.call(&request).await {
+        Ok(Response::Error { error }) => Err(error),
+        Ok(response) => Ok(response),
+        Err(e) => Err(ErrorInfo::new(
+            ErrorClass::Daemon,
+            t!("daemon-lost", error = e),
+        )),
+    }
+}
+
+fn unexpected(response: Response) -> ErrorInfo {
+    ErrorInfo::new(
+        ErrorClass::Daemon,
+        t!("daemon-unexpected", response = format!("{:?}", response)),
+    )
+}
+
+/// Runs a command through the background daemon instead of in this process.
+async fn run_remote(reporter: &Reporter, mut client: IpcClient, command: Command) -> CommandResult {
+    reporter.info(t!("daemon-using", pid = client.daemon_pid));
+
+    match command {
+        Command::Launch(mut args) => {
+            if args.count() == 0 {
+                reporter.require_interactive(&t!("need-count"))?;
+                args.num_launches = Some(prompt_user_for_n(reporter));
+            }
+            // The daemon falls back to its own path when this one cannot be found
+            let game_path = get_game_path_or_search(reporter, &args.locate, false, false)
+                .await
+                .ok();
+            // The daemon knows the profile of every game it launched
+            let in_profile = match call(&mut client, Request::List).await? {
+                Response::Instances { instances } => profile_count(&instances, args.profile()),
+                other => return Err(unexpected(other)),
+            };
+            check_limits(reporter, &args, Some(in_profile))?;
+
+            let request = Request::Launch {
+                count: args.count(),
+                profile: args.profile().to_string(),
+                labels: args.labels.clone(),
+                game_path,
+            };
+            match call(&mut client, request).await? {
+                Response::Launched { pids } => {
+                    for pid in &pids {
+                        reporter.info(format!(" + {}", t!("event-launched", pid = pid)));
+                    }
+                    // Windows belong to the desktop, so the layout can be applied from here
+                    arrange_launched_windows(reporter, &pids, args.layout.unwrap_or_default())
+                        .await;
+                    reporter.finish(json!({ "daemon_pid": client.daemon_pid, "pids": pids }));
+                    Ok(())
+                }
+                other => Err(unexpected(other)),
+            }
+        }
+        Command::List => match call(&mut client, Request::List).await? {
+            Response::Instances { instances } => {
+                if reporter.is_json() {
+                    reporter.finish(json!({ "instances": instances }));
+                } else {
+                    print_instances(&instances);
+                }
+                Ok(())
+            }
+            other => Err(unexpected(other)),
+        },
+        Command::Kill(args) => {
+            let selector = args.selector();
+            let request = Request::Kill {
+                selector: selector.to_string(),
+            };
+            match call(&mut client, request).await? {
+                Response::Killed { pids } => {
+                    for pid in &pids {
+                        reporter.info(t!("event-killed", pid = pid));
+                    }
+                    reporter.finish(json!({ "selector": selector.to_string(), "killed": pids }));
+                    Ok(())
+                }
+                other => Err(unexpected(other)),
+            }
+        }
+        Command::Status(_) => match call(&mut client, Request::Status).await? {
+            Response::Status {
+                instances,
+                guard_running,
+                game_path,
+            } => {
+                if reporter.is_json() {
+                    reporter.finish(json!({
+                        "admin": is_admin(),
+                        "daemon_pid": client.daemon_pid,
+                        "guard_running": guard_running,
+                        "install": game_path,
+                        "running": instances.iter().map(|i| i.pid).collect::<Vec<_>>(),
+                    }));
+                    return Ok(());
+                }
+
+                println!("{}", t!("status-daemon", pid = client.daemon_pid));
+                if guard_running {
+                    println!("{}", t!("status-guard-running"));
+                } else {
+                    println!("{}", t!("status-guard-off"));
+                }
+                match game_path {
+                    Some(path) => println!("{}", t!("status-install", path = path.display())),
+                    None => println!("{}", t!("status-install-unset")),
+                }
+                println!("{}", t!("status-running", count = instances.len()));
+                for instance in instances {
+                    println!("  - pid {}", instance.pid);
+                }
+                Ok(())
+            }
+            other => Err(unexpected(other)),
+        },
+        _ => unreachable!("only launch, list, kill and status are sent to the daemon"),
+    }
+}
+
+async fn daemon(reporter: &Reporter, args: DaemonArgs) -> CommandResult {
+    if args.stop {
+        let mut client = IpcClient::connect()
+            .await
+            .map_err(|e| ErrorInfo::new(ErrorClass::Daemon, t!("daemon-none", error = e)))?;
+        let daemon_pid = client.daemon_pid;
+        call(&mut client, Request::Shutdown).await?;
+        reporter.info(t!("daemon-stopped-pid", pid = daemon_pid));
+        reporter.finish(json!({ "stopped": daemon_pid }));
+        return Ok(());
+    }
+
+    // Launch requests may carry their own path, so a missing install is not fatal here
+    let game_path = get_game_path_or_search(reporter, &args.locate, false, true)
+        .await
+        .ok();
+
+    let manager = GameManager::new();
+    let mut events = manager.subscribe();
+    let _ = manager.adopt_running_games().await;
+    if !args.no_guard {
+        manager.start_guard();
+    }
+
+    let daemon = Daemon::new(manager, game_path, launch_limits(&args.limits));
+    reporter.info(t!("daemon-running", pid = std::process::id()));
+
+    #[cfg(feature = "http")]
+    let http_server = match args.http {
+        Some(bind) => Some(start_http(reporter, &daemon, bind, args.token).await?),
+        None => None,
+    };
+
+    let serve = daemon.clone().serve();
+    let signal = wait_for_shutdown_signal();
+    tokio::pin!(serve, signal);
+    let mut signalled = false;
+
+    let result = loop {
+        tokio::select! {
+            result = &mut serve => break result,
+            event = events.recv() => {
+                if let Ok(event) = event {
+                    reporter.event(&event);
+                }
+            }
+            _ = &mut signal, if !signalled => {
+                signalled = true;
+                daemon.shutdown();
+            }
+        }
+    };
+    daemon.backend().stop_guard();
+    #[cfg(feature = "http")]
+    if let Some(http_server) = http_server {
+        http_server.abort();
+    }
+
+    result.map_err(|e| ErrorInfo::new(ErrorClass::Daemon, t!("daemon-failed", error = e)))?;
+    reporter.info(t!("daemon-stopped"));
+    reporter.finish(json!({ "instances": daemon.backend().instances().await }));
+    Ok(())
+}
+
+/// Binds the HTTP API and serves it in the background.
+#[cfg(feature = "http")]
+async fn start_http(
+    reporter: &Reporter,
+    daemon: &std::sync::Arc<Daemon<GameManager>>,
+    bind: std::net::SocketAddr,
+    token: Option<String>,
+) -> Result<tokio::task::JoinHandle<std::io::Result<()>>, ErrorInfo> {
+    let listener = tokio::net::TcpListener::bind(bind).await.map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::Daemon,
+            t!("http-listen-failed", address = bind, error = e),
+        )
+    })?;
+    if !bind.ip().is_loopback() {
+        reporter.warn(t!("http-exposed", address = bind));
+    }
+
+    let token = token.unwrap_or_else(http::generate_token);
+    if reporter.is_json() {
+        reporter.event(&Notice(
+            json!({ "event": "http_listening", "address": bind, "token": token }),
+        ));
+    } else {
+        reporter.info(t!("http-listening", address = bind, token = &token));
+    }
+    Ok(tokio::spawn(http::serve_http(
+        daemon.clone(),
+        listener,
+        token,
+    )))
+}
+
+async fn kill(reporter: &Reporter, args: KillArgs) -> CommandResult {
+    let selector = args.selector();
+
+    let game_manager = GameManager::new();
+    game_manager.adopt_running_games().await.map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+
+    let mut events = game_manager.subscribe();
+    let killed = game_manager.kill_selected(&selector).await;
+    reporter.drain(&mut events);
+    if killed.is_empty() {
+        return Err(ErrorInfo::new(
+            ErrorClass::NoMatch,
+            t!("no-match", selector = &selector),
+        ));
+    }
+
+    reporter.finish(json!({ "selector": selector.to_string(), "killed": killed }));
+    Ok(())
+}
+
+async fn status(reporter: &Reporter, args: LocateArgs) -> CommandResult {
+    let admin = is_admin();
+    let install = get_game_path_or_search(reporter, &args, false, false).await;
+    let running = find_starcraft_pids().map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::ProcessQuery,
+            t!("process-list-failed", error = e),
+        )
+    })?;
+
+    if reporter.is_json() {
+        reporter.finish(json!({
+            "admin": admin,
+            "install": install.as_ref().ok(),
+            "install_error": install.as_ref().err(),
+            "running": running,
+        }));
+        return Ok(());
+    }
+
+    if admin {
+        println!("{}", t!("status-admin-yes"));
+    } else {
+        println!("{}", t!("status-admin-no"));
+    }
+    match install {
+        Ok(path) => println!("{}", t!("status-install", path = path.display())),
+        Err(e) => println!("{}", t!("status-install-missing", error = e)),
+    }
+    println!("{}", t!("status-running", count = running.len()));
+    for pid in running {
+        println!("  - pid {}", pid);
+    }
+
+    Ok(())
+}
+
+async fn locate(reporter: &Reporter, args: LocateArgs) -> CommandResult {
+    let path = get_game_path_or_search(reporter, &args, false, false)
+        .await
+        .map_err(|e| ErrorInfo::new(ErrorClass::InstallNotFound, e))?;
+
+    if reporter.is_json() {
+        reporter.finish(json!({ "game_path": path }));
+    } else {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+async fn installs(reporter: &Reporter, args: InstallsArgs) -> CommandResult {
+    let installs =
+        find_installations(reporter, args.async_registry_search, args.scan_drives, true).await;
+
+    if reporter.is_json() {
+        reporter.finish(json!({ "installations": installs }));
+        return Ok(());
+    }
+    if installs.is_empty() {
+        println!("{}", t!("installs-none"));
+        return Ok(());
+    }
+    print!("{}", installation_list(&installs));
+    Ok(())
+}
+
+/// Uninstall entries are 5 levels below SOFTWARE, 6 under WOW6432Node.
+const REGISTRY_SEARCH_DEPTH: usize = 8;
+const REGISTRY_SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a drive walk may take before giving up with what it found.
+const DRIVE_SCAN_BUDGET: Duration = Duration::from_secs(30);
+
+/// Runs install discovery, adding the registry search when asked or when nothing was found,
+/// and then the drive walk if allowed.
+///
+/// Asked for, the searches run to their end; as a fallback they stop at the first install.
+/// Without `persist` the drive walk leaves its cache alone.
+async fn find_installations(
+    reporter: &Reporter,
+    full_search: bool,
+    scan_drives: bool,
+    persist: bool,
+) -> Vec<Installation> {
+    let mut installs = discover_installations();
+    if !full_search && !installs.is_empty() {
+        return installs;
+    }
+
+    let cancel = CancellationToken::new();
+    let ctrl_c = tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancel.cancel();
+            }
+        }
+    });
+
+    if !portable::enabled() {
+        search_registry(reporter, &mut installs, full_search, &cancel).await;
+    }
+    if scan_drives && (full_search || installs.is_empty()) && !cancel.is_cancelled() {
+        walk_drives(reporter, &mut installs, persist, &cancel).await;
+    }
+    ctrl_c.abort();
+
+    if cancel.is_cancelled() {
+        reporter.warn(t!("search-cancelled"));
+    }
+    rank_installations(installs)
+}
+
+async fn search_registry(
+    reporter: &Reporter,
+    installs: &mut Vec<Installation>,
+    full_search: bool,
+    cancel: &CancellationToken,
+) {
+    reporter.info(t!("locate-searching"));
+    let started = std::time::Instant::now();
+    let mut hits = RegistrySearch::new(HKEY_LOCAL_MACHINE)
+        .start_path("SOFTWARE")
+        .max_depth(REGISTRY_SEARCH_DEPTH)
+        .key_name(KeyPattern::exact("StarCraft"))
+        .value_name("InstallLocation")
+        .timeout(REGISTRY_SEARCH_TIMEOUT)
+        .cancel_token(cancel.clone())
+        .stream();
+    while let Some(hit) = hits.next().await {
+        let Some(path) = hit.value else {
+            continue;
+        };
+        let source = DiscoverySource::RegistrySearch(hit.key_path);
+        if let Some(install) = Installation::detect(path, source, None, None) {
+            installs.push(install);
+            if !full_search {
+                return;
+            }
+        }
+    }
+
+    if !cancel.is_cancelled() && started.elapsed() >= REGISTRY_SEARCH_TIMEOUT {
+        reporter.warn(t!(
+            "search-timed-out",
+            seconds = REGISTRY_SEARCH_TIMEOUT.as_secs()
+        ));
+    }
+}
+
+/// Walks the fixed drives and remembers what it finds in the scan cache.
+async fn walk_drives(
+    reporter: &Reporter,
+    installs: &mut Vec<Installation>,
+    persist: bool,
+    cancel: &CancellationToken,
+) {
+    let drives = fixed_drives();
+    let names: Vec<String> = drives
+        .iter()
+        .map(|drive| drive.display().to_string())
+        .collect();
+    reporter.info(t!("drive-scan-started", drives = names.join(" ")));
+
+    let scan = FsScan::new()
+        .roots(drives)
+        .time_budget(DRIVE_SCAN_BUDGET)
+        .cancel_token(cancel.clone());
+    let result = tokio::task::spawn_blocking(move || scan.run())
+        .await
+        .unwrap_or_default();
+    if result.timed_out {
+        reporter.warn(t!(
+            "drive-scan-timed-out",
+            seconds = DRIVE_SCAN_BUDGET.as_secs(),
+            folders = result.visited
+        ));
+    }
+
+    let mut folders = Vec::new();
+    for found in result.found {
+        let source = DiscoverySource::DriveScan(found.root.display().to_string());
+        if let Some(install) = Installation::detect(found.folder.clone(), source, None, None) {
+            installs.push(install);
+            folders.push(found.folder);
+        }
+    }
+    if folders.is_empty() || !persist {
+        return;
+    }
+    if let Some(cache) = cache_path() {
+        if let Err(e) = ScanCache::update(&cache, folders) {
+            reporter.warn(t!("scan-cache-failed", path = cache.display(), error = e));
+        }
+    }
+}
+
+/// Numbered installs with where each was found, as printed by `installs` and the prompt.
+fn installation_list(installs: &[Installation]) -> String {
+    let mut list = format!("{}\n", t!("installs-header", count = installs.len()));
+    for (i, install) in installs.iter().enumerate() {
+        list.push_str(&format!("{:>3}. {}\n", i + 1, install));
+        for source in &install.sources {
+            list.push_str(&format!("       {}\n", source));
+        }
+    }
+    list
+}
+
+/// Resolves StarCraft.exe; `persist` lets a path found by the registry search be written back
+/// and the drive walk update its cache.
+async fn get_game_path_or_search(
+    reporter: &Reporter,
+    args: &LocateArgs,
+    allow_prompt: bool,
+    persist: bool,
+) -> Result<PathBuf, String> {
+    // Without --arch or -b the install decides
+    let requested = args.arch.or(args.is_64bit.then_some(Arch::X86_64));
+
+    // An explicit path always wins over discovery
+    if let Some(game_path) = &args.game_path {
+        return resolve_user_path(game_path.clone(), requested);
+    }
+
+    let installs = find_installations(
+        reporter,
+        args.async_registry_search,
+        args.scan_drives,
+        persist,
+    )
+    .await;
+
+    let install = if let Some(choice) = &args.install {
+        let install = select_installation(&installs, choice)?;
+        if let Some(arch) = requested.filter(|&arch| !install.supports(arch)) {
+            return Err(t!("install-arch-missing", install = install, arch = arch));
+        }
+        install.clone()
+    } else {
+        let candidates: Vec<Installation> = installs
+            .iter()
+            .filter(|install| requested.is_none_or(|arch| install.supports(arch)))
+            .cloned()
+            .collect();
+        match candidates.as_slice() {
+            [] if !installs.is_empty() => {
+                return Err(t!(
+                    "install-no-arch",
+                    count = installs.len(),
+                    arch = requested.unwrap_or_default()
+                ));
+            }
+            [] => {
+                // Nothing in the registry, ask for the folder instead
+                if !allow_prompt || !reporter.interactive {
+                    return Err(t!("install-not-found-hint"));
+                }
+                let game_path = prompt_user_for_path(reporter, requested)?;
+                if persist && portable::enabled() {
+                    remember_portable_install(reporter, &game_path);
+                }
+                return Ok(game_path);
+            }
+            [install] => install.clone(),
+            [first, ..] if !allow_prompt || !reporter.interactive => {
+                reporter.info(t!("install-picked", install = first));
+                first.clone()
+            }
+            _ => prompt_user_for_install(reporter, &candidates),
+        }
+    };
+
+    if portable::enabled() {
+        if persist {
+            remember_portable_install(reporter, &install.root);
+        }
+    } else if install.search_only() {
+        // try to set new path registry
+        if !persist {
+            reporter.info(t!("registry-dry-run"));
+        } else {
+            persist_game_path(reporter, &install.root, allow_prompt);
+        }
+    }
+
+    let arch = match requested.or_else(|| install.default_arch()) {
+        Some(arch) => arch,
+        None => return Err(t!("install-not-found")),
+    };
+    if requested.is_none() {
+        reporter.info(t!(
+            "arch-auto",
+            arch = arch,
+            version = install.version.as_deref().unwrap_or("?")
+        ));
+    }
+    install.exe(arch).ok_or_else(|| t!("install-not-found"))
+}
+
+/// Writes the install a portable copy settled on to the config next to it, so later runs
+/// skip discovery.
+fn remember_portable_install(reporter: &Reporter, game_path: &Path) {
+    let Some(file) = portable::config_path() else {
+        return;
+    };
+    match write_game_path(&file, game_path) {
+        Ok(()) => reporter.info(t!(
+            "portable-path-saved",
+            path = game_path.display(),
+            file = file.display()
+        )),
+        Err(e) => reporter.warn(t!("portable-save-failed", error = e)),
+    }
+}
+
+/// Writes a discovered install to the uninstall key, asking before replacing another one.
+fn persist_game_path(reporter: &Reporter, root: &Path, allow_prompt: bool) {
+    let root = root.to_string_lossy();
+    let update = match set_game_path(&root, false) {
+        Ok(GamePathUpdate::Conflict(current)) => {
+            if !allow_prompt || !reporter.interactive {
+                reporter.info(t!("registry-kept", current = current));
+                return;
+            }
+            reporter.prompt(format!(
+                "{} ",
+                t!(
+                    "prompt-registry-replace",
+                    current = current.as_str(),
+                    path = root.as_ref()
+                )
+            ));
+            if !confirm() {
+                reporter.info(t!("registry-kept", current = current));
+                return;
+            }
+            set_game_path(&root, true)
+        }
+        update => update,
+    };
+
+    match update {
+        Ok(GamePathUpdate::Written(change)) => {
+            reporter.info(t!("registry-path-set", path = change.value, id = change.id))
+        }
+        Ok(_) => {}
+        Err(e) => reporter.warn(t!("registry-write-failed", error = e)),
+    }
+}
+
+/// Reads a yes/no answer; anything but yes, including no terminal, is no.
+fn confirm() -> bool {
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap_or(0);
+    matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "예" | "네"
+    )
+}
+
+/// Lists the candidates and asks for one; an empty answer takes the first.
+fn prompt_user_for_install(reporter: &Reporter, candidates: &[Installation]) -> Installation {
+    reporter.prompt(installation_list(candidates));
+    loop {
+        reporter.prompt(format!(
+            "{} ",
+            t!("prompt-install", count = candidates.len())
+        ));
+        let mut choice = String::new();
+        if std::io::stdin().read_line(&mut choice).unwrap_or(0) == 0 || choice.trim().is_empty() {
+            return candidates[0].clone();
+        }
+        match select_installation(candidates, &choice) {
+            Ok(install) => return install.clone(),
+            Err(e) => reporter.warn(e),
+        }
+    }
+}
+
+// As default, it retuns 2 on errors
+fn prompt_user_for_n(reporter: &Reporter) -> u32 {
+    reporter.prompt(format!("{} ", t!("prompt-count")));
+
+    let mut n = String::new();
+    std::io::stdin()
+        .read_line(&mut n)
+        .map_err(|e| e.to_string())
+        .expect("Failed to read line.");
+
+    let trimmed_n = n.trim();
+    if trimmed_n.is_empty() {
+        return 2;
+    }
+
+    trimmed_n.parse::<u32>().unwrap_or(2)
+}
+
+fn prompt_user_for_path(reporter: &Reporter, arch: Option<Arch>) -> Result<PathBuf, String> {
+    reporter.prompt(format!("{}\n", t!("prompt-path-example")));
+    reporter.prompt(t!("prompt-path"));
+    let mut path_input = String::new();
+    std::io::stdin()
+        .read_line(&mut path_input)
+        .map_err(|e| e.to_string())?;
+
+    let trimmed_path = path_input.trim();
+    if trimmed_path.is_empty() {
+        return Err(t!("path-none"));
+    }
+
+    resolve_user_path(PathBuf::from(trimmed_path), arch)
+}
+
+/// Accepts a StarCraft folder in any [`InstallLayout`], or a path to a `StarCraft.exe`.
+///
+/// Without `arch` the build of the OS architecture is preferred; a StarCraft.exe built for
+/// another architecture than the one asked for is refused.
+fn resolve_user_path(path: PathBuf, arch: Option<Arch>) -> Result<PathBuf, String> {
+    let root = install_root(&path);
+    if !root.exists() {
+        return Err(t!("path-missing"));
+    }
+
+    // A StarCraft.exe named directly is the one to launch
+    if path.is_file() && path != root {
+        if let Some(arch) = arch {
+            check_arch(&path, arch)?;
+        }
+        return Ok(path);
+    }
+
+    match InstallLayout::detect(&root) {
+        Some(layout) if layout.per_arch() => {
+            let order = match arch {
+                Some(arch) => vec![arch],
+                None if native_arch() == Arch::X86_64 => vec![Arch::X86_64, Arch::X86],
+                None => vec![Arch::X86, Arch::X86_64],
+            };
+            for candidate in order {
+                let exe = layout.exe(&root, candidate);
+                if exe.exists() {
+                    check_arch(&exe, candidate)?;
+                    return Ok(exe);
+                }
+            }
+        }
+        Some(layout) => {
+            let exe = layout.exe(&root, arch.unwrap_or_default());
+            if let Some(arch) = arch {
+                check_arch(&exe, arch)?;
+            }
+            return Ok(exe);
+        }
+        None => {}
+    }
+
+    let per_arch = InstallLayout::PerArch;
+    Err(t!(
+        "path-no-exe",
+        x86 = per_arch.exe(&root, Arch::X86).display(),
+        x64 = per_arch.exe(&root, Arch::X86_64).display(),
+        flat = root.join(EXE_NAME).display()
+    ))
+}
+
+async fn launch_game_multiple_times(
+    reporter: &Reporter,
+    events: &mut broadcast::Receiver<GameEvent>,
+    game_manager: &GameManager,
+    path: &Path,
+    args: &LaunchArgs,
+) -> u32 {
+    let mut launched = 0;
+    for i in 0..args.count() {
+        reporter.info(format!(" ~ {}", t!("launching", number = i + 1)));
+        let options = LaunchOptions {
+            label: args.labels.get(i as usize).cloned(),
+            profile: args.profile().to_string(),
+            mutex_scope: args.mutex_scope(),
+            ..LaunchOptions::default()
+        };
+        if game_manager
+            .launch_game_with(path.to_path_buf(), options)
+            .await
+            .is_some()
+        {
+            launched += 1;
+        }
+        reporter.drain(events);
+        sleep(args.launch_delay()).await;
+    }
+    launched
+}
+
+/// Waits a few seconds for the games to open their windows, then arranges them.
+async fn arrange_launched_windows(reporter: &Reporter, pids: &[u32], layout: Layout) {
+    if layout == Layout::None || pids.is_empty() {
+        return;
+    }
+
+    let mut arranged = 0;
+    for _ in 0..20 {
+        arranged = arrange_windows(pids, layout);
+        if arranged == pids.len() {
+            break;
+        }
+        sleep(Duration::from_millis(500)).await;
+    }
+    if arranged < pids.len() {
+        reporter.warn(t!(
+            "arrange-partial",
+            arranged = arranged,
+            total = pids.len()
+        ));
+    }
+}
+
+/// Merges the command line flags over the loaded configuration and writes the result back.
+fn apply_config(config: &mut Config, command: &mut Command) {
+    fn locate_layer(args: &LocateArgs) -> Settings {
+        Settings {
+            arch: args.arch.or(args.is_64bit.then_some(Arch::X86_64)),
+            game_path: args.game_path.clone(),
+            ..Settings::default()
+        }
+    }
+
+    fn limit_layer(args: &LimitArgs) -> Settings {
+        Settings {
+            max_instances: args.max_instances,
+            profile_max_instances: (!args.profile_max_instances.is_empty())
+                .then(|| args.profile_max_instances.iter().cloned().collect()),
+            ..Settings::default()
+        }
+    }
+
+    fn resolve_limits(config: &Config, args: &mut LimitArgs) {
+        args.max_instances = Some(config.max_instances());
+        args.profile_max_instances = config.profile_max_instances().into_iter().collect();
+    }
+
+    fn resolve_locate(config: &Config, args: &mut LocateArgs) {
+        args.game_path = config.game_path().map(Path::to_path_buf);
+        args.arch = config.arch();
+        args.is_64bit = config.arch() == Some(Arch::X86_64);
+    }
+
+    match command {
+        Command::Launch(args) => {
+            config.apply(
+                Settings {
+                    // -n 0 has always meant "ask"
+                    count: args.num_launches.filter(|&n| n > 0),
+                    profile: args.profile.clone(),
+                    launch_delay_ms: args.launch_delay,
+                    layout: args.layout,
+                    labels: (!args.labels.is_empty()).then(|| args.labels.clone()),
+                    ..locate_layer(&args.locate)
+                },
+                ConfigSource::Cli,
+            );
+            config.apply(limit_layer(&args.limits), ConfigSource::Cli);
+            resolve_locate(config, &mut args.locate);
+            args.num_launches = config.count();
+            args.profile = Some(config.profile().to_string());
+            args.launch_delay = Some(config.launch_delay().as_millis() as u64);
+            args.layout = Some(config.layout());
+            args.labels = config.labels().to_vec();
+            resolve_limits(config, &mut args.limits);
+        }
+        Command::Status(args) | Command::Locate(args) | Command::Doctor(args) => {
+            config.apply(locate_layer(args), ConfigSource::Cli);
+            resolve_locate(config, args);
+        }
+        Command::Daemon(args) => {
+            config.apply(locate_layer(&args.locate), ConfigSource::Cli);
+            config.apply(limit_layer(&args.limits), ConfigSource::Cli);
+            resolve_locate(config, &mut args.locate);
+            resolve_limits(config, &mut args.limits);
+        }
+        _ => {}
+    }
+}
+
+fn config_show(reporter: &Reporter, config: &Config) -> CommandResult {
+    // A portable copy reads its own file instead of the system and user ones
+    let portable_file = portable::config_path();
+    let (system_file, user_file) = match portable_file {
+        Some(_) => (None, None),
+        None => (system_config_path(), user_config_path()),
+    };
+    let files = json!({
+        "system": system_file,
+        "user": user_file,
+        "portable": portable_file,
+        "directory": DIRECTORY_FILE,
+    });
+    if reporter.is_json() {
+        reporter.finish(json!({ "settings": config.entries(), "files": files }));
+        return Ok(());
+    }
+
+    println!(
+        "{:<16}  {:<28}  {}",
+        t!("column-key"),
+        t!("column-value"),
+        t!("column-source")
+    );
+    for entry in config.entries() {
+        let value = match &entry.value {
+            serde_json::Value::Null => "-".to_string(),
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        println!("{:<16}  {:<28}  {}", entry.key, value, entry.source);
+    }
+
+    println!();
+    println!("{}", t!("config-files"));
+    for path in [
+        system_file,
+        user_file,
+        portable_file,
+        Some(PathBuf::from(DIRECTORY_FILE)),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let state = if path.exists() {
+            t!("config-file-found")
+        } else {
+            t!("config-file-missing")
+        };
+        println!("  {} ({})", path.display(), state);
+    }
+    println!("{}", t!("config-then"));
+    Ok(())
+}
+
+fn open_journal() -> Result<Journal, ErrorInfo> {
+    Journal::open_default()
+        .map_err(|e| ErrorInfo::new(ErrorClass::Registry, t!("journal-unreadable", error = e)))
+}
+
+/// Seconds since the Unix epoch as `YYYY-MM-DD HH:MM` in UTC.
+fn format_utc(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let minutes = seconds % 86_400 / 60;
+    // Civil date from a day count, after Howard Hinnant's days_from_civil
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+fn registry_log(reporter: &Reporter) -> CommandResult {
+    let journal = open_journal()?;
+    if reporter.is_json() {
+        reporter.finish(json!({ "journal": journal.path(), "changes": journal.changes() }));
+        return Ok(());
+    }
+    if journal.changes().is_empty() {
+        println!("{}", t!("journal-empty"));
+        return Ok(());
+    }
+
+    for change in journal.changes() {
+        let state = if change.restored {
+            format!("  ({})", t!("journal-restored"))
+        } else {
+            String::new()
+        };
+        println!(
+            "#{:<3} {} UTC  HKLM\\{}\\{}{}",
+            change.id,
+            format_utc(change.time),
+            change.key_path,
+            change.value_name,
+            state
+        );
+        let previous = change
+            .previous
+            .clone()
+            .unwrap_or_else(|| t!("journal-unset"));
+        println!("     {} -> {}", previous, change.value);
+    }
+    println!();
+    println!("{}", t!("journal-file", path = journal.path().display()));
+    Ok(())
+}
+
+fn registry_restore(reporter: &Reporter, id: Option<u64>) -> CommandResult {
+    if portable::enabled() {
+        return Err(ErrorInfo::new(
+            ErrorClass::Usage,
+            t!("portable-registry-off"),
+        ));
+    }
+    let mut journal = open_journal()?;
+    let restored = match id {
+        Some(id) if !journal.changes().iter().any(|change| change.id == id) => {
+            return Err(ErrorInfo::new(
+                ErrorClass::NoMatch,
+                t!("journal-no-change", id = id),
+            ));
+        }
+        Some(id) => journal.restore(id).map(|change| vec![change]),
+        None => journal.restore_all(),
+    }
+    .map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::Registry,
+            t!("registry-restore-failed", error = e),
+        )
+    })?;
+
+    for change in &restored {
+        reporter.info(t!(
+            "registry-restored",
+            id = change.id,
+            key = format!("{}\\{}", change.key_path, change.value_name)
+        ));
+    }
+    if restored.is_empty() {
+        reporter.info(t!("registry-nothing-to-restore"));
+    }
+    reporter.finish(json!({ "restored": restored }));
+    Ok(())
+}
+
+fn registry_export(reporter: &Reporter, path: &Path, undo: bool) -> CommandResult {
+    let journal = open_journal()?;
+    journal.export_reg(path, undo).map_err(|e| {
+        ErrorInfo::new(
+            ErrorClass::Failure,
+            t!("registry-export-failed", path = path.display(), error = e),
+        )
+    })?;
+    reporter.info(t!(
+        "registry-exported",
+        path = path.display(),
+        count = journal.changes().len()
+    ));
+    reporter.finish(json!({ "path": path, "undo": undo, "changes": journal.changes().len() }));
+    Ok(())
+}
+
+/// Waits for Ctrl+C, or on Windows for Ctrl+Break or the console window closing.
+async fn wait_for_shutdown_signal() {
+    #[cfg(windows)]
+    {
+        use tokio::signal::windows::{ctrl_break, ctrl_close};
+
+        let (mut ctrl_break, mut ctrl_close) = match (ctrl_break(), ctrl_close()) {
+            (Ok(ctrl_break), Ok(ctrl_close)) => (ctrl_break, ctrl_close),
+            _ => {
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = ctrl_break.recv() => {}
+            _ = ctrl_close.recv() => {}
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,16 @@
+//! The launcher drives StarCraft through Win32, so the command line only runs on Windows.
+//! Elsewhere the library still builds, which keeps its parsers testable.
+
+#[cfg(windows)]
+mod app;
+
+#[cfg(windows)]
+fn main() -> std::process::ExitCode {
+    app::main()
+}
+
+#[cfg(not(windows))]
+fn main() -> std::process::ExitCode {
+    eprintln!("{}", sclauncher::t!("windows-only"));
+    std::process::ExitCode::FAILURE
+}
@@ -1,14 +1,19 @@
 use std::ptr;
-use windows::core::PWSTR;
-use windows::Win32::Foundation::{BOOL, HANDLE, HWND, PSID};
+use windows::core::{w, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, BOOL, HANDLE, HWND, LUID, PSID};
 use windows::Win32::Security::{
-    AllocateAndInitializeSid, CheckTokenMembership, FreeSid, SID_IDENTIFIER_AUTHORITY,
+    AllocateAndInitializeSid, CheckTokenMembership, FreeSid, GetTokenInformation,
+    LookupPrivilegeValueW, TokenPrivileges, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED,
+    SID_IDENTIFIER_AUTHORITY, TOKEN_PRIVILEGES, TOKEN_QUERY,
 };
 use windows::Win32::System::SystemServices::{
     DOMAIN_ALIAS_RID_ADMINS, SECURITY_BUILTIN_DOMAIN_RID,
 };
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 use windows::Win32::UI::Shell::ShellExecuteW;
 
+use super::game::quote_arg;
+
 pub fn is_admin() -> bool {
     unsafe {
         // pub struct PSID(pub *mut core::ffi::c_void);
@@ -42,7 +47,19 @@ pub fn is_admin() -> bool {
     false
 }
 
+/// Restarts the current executable elevated, forwarding the command line arguments.
 pub fn run_as_admin() -> bool {
+    // Quoted the way the C runtime splits them, so paths and labels with spaces or quotes
+    // survive the restart
+    let parameters: Vec<u16> = std::env::args()
+        .skip(1)
+        .map(|arg| quote_arg(&arg))
+        .collect::<Vec<String>>()
+        .join(" ")
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
     unsafe {
         let result = ShellExecuteW(
             HWND(0),
@@ -56,7 +73,7 @@ pub fn run_as_admin() -> bool {
                     .collect::<Vec<u16>>()
                     .as_ptr() as _,
             ),
-            PWSTR(ptr::null_mut()),
+            PWSTR(parameters.as_ptr() as _),
             PWSTR(ptr::null_mut()),
             windows::Win32::UI::WindowsAndMessaging::SW_SHOW,
         );
@@ -64,3 +81,44 @@ pub fn run_as_admin() -> bool {
         !result.is_invalid()
     }
 }
+
+/// Whether the process token holds `SeDebugPrivilege`: `None` when it does not,
+/// `Some(enabled)` when it does.
+///
+/// Elevated administrators hold it disabled; it only has to be enabled to open processes
+/// of other users.
+pub fn debug_privilege() -> Option<bool> {
+    unsafe {
+        let mut luid = LUID::default();
+        LookupPrivilegeValueW(None, w!("SeDebugPrivilege"), &mut luid).ok()?;
+
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).ok()?;
+
+        let mut length = 0u32;
+        let _ = GetTokenInformation(token, TokenPrivileges, None, 0, &mut length);
+        // u64 storage keeps the buffer aligned for TOKEN_PRIVILEGES
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        let queried = GetTokenInformation(
+            token,
+            TokenPrivileges,
+            Some(buffer.as_mut_ptr() as *mut _),
+            length,
+            &mut length,
+        );
+        let _ = CloseHandle(token);
+        queried.ok()?;
+
+        let privileges = &*(buffer.as_ptr() as *const TOKEN_PRIVILEGES);
+        let entries: &[LUID_AND_ATTRIBUTES] = std::slice::from_raw_parts(
+            privileges.Privileges.as_ptr(),
+            privileges.PrivilegeCount as usize,
+        );
+        entries
+            .iter()
+            .find(|entry| {
+                entry.Luid.LowPart == luid.LowPart && entry.Luid.HighPart == luid.HighPart
+            })
+            .map(|entry| entry.Attributes.0 & SE_PRIVILEGE_ENABLED.0 != 0)
+    }
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Product codes and uids of StarCraft start with this; StarCraft II is `s2`.
+const STARCRAFT_PRODUCT: &str = "s1";
+
+/// One entry of `product.db`, the Battle.net agent's list of installed games.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProductInstall {
+    /// `s1`, `s1_ptr`, ...
+    pub uid: String,
+    pub product_code: String,
+    pub install_path: Option<PathBuf>,
+    /// Version branch, e.g. `ptr` for the public test build; empty for live
+    pub branch: Option<String>,
+}
+
+impl ProductInstall {
+    pub fn is_starcraft(&self) -> bool {
+        [&self.uid, &self.product_code].iter().any(|code| {
+            let code = code.to_ascii_lowercase();
+            code == STARCRAFT_PRODUCT || code.starts_with("s1_")
+        })
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// A protobuf field as it is on the wire; only what `product.db` uses is kept.
+enum Field<'a> {
+    Varint,
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Reads protobuf messages field by field, without a schema.
+struct WireReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        WireReader { data, position: 0 }
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| invalid("Truncated varint"))?;
+            self.position += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("Varint too long"))
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("Truncated field"))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// The next field number and value, `None` at the end of the message.
+    fn next_field(&mut self) -> io::Result<Option<(u64, Field<'a>)>> {
+        if self.position >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = match key & 7 {
+            0 => {
+                self.varint()?;
+                Field::Varint
+            }
+            1 => {
+                self.take(8)?;
+                Field::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                Field::Bytes(self.take(length)?)
+            }
+            5 => {
+                self.take(4)?;
+                Field::Fixed
+            }
+            _ => return Err(invalid("Unsupported wire type")),
+        };
+        Ok(Some((key >> 3, field)))
+    }
+}
+
+fn text(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Parses `product.db`: a protobuf `Database` whose field 1 repeats `ProductInstall`
+/// (1 uid, 2 product code, 3 `UserSettings` with 1 install path and 10 version branch).
+pub fn parse_product_db(data: &[u8]) -> io::Result<Vec<ProductInstall>> {
+    let mut products = Vec::new();
+    let mut database = WireReader::new(data);
+
+    while let Some((number, field)) = database.next_field()? {
+        let (1, Field::Bytes(message)) = (number, field) else {
+            continue;
+        };
+        let mut product = ProductInstall::default();
+        let mut install = WireReader::new(message);
+        while let Some((number, field)) = install.next_field()? {
+            match (number, field) {
+                (1, Field::Bytes(uid)) => product.uid = String::from_utf8_lossy(uid).into(),
+                (2, Field::Bytes(code)) => {
+                    product.product_code = String::from_utf8_lossy(code).into()
+                }
+                (3, Field::Bytes(settings)) => {
+                    let mut settings = WireReader::new(settings);
+                    while let Some((number, field)) = settings.next_field()? {
+                        match (number, field) {
+                            (1, Field::Bytes(path)) => {
+                                product.install_path = text(path).map(PathBuf::from)
+                            }
+                            (10, Field::Bytes(branch)) => product.branch = text(branch),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        products.push(product);
+    }
+    Ok(products)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ClientConfig {
+    #[serde(default)]
+    client: ClientSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ClientSection {
+    #[serde(default)]
+    install: InstallSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstallSection {
+    default_install_path: Option<String>,
+}
+
+/// Reads `Client.Install.DefaultInstallPath` of `Battle.net.config`, the folder new games
+/// are installed into.
+pub fn parse_client_config(json: &str) -> io::Result<Option<PathBuf>> {
+    let config: ClientConfig = serde_json::from_str(json)?;
+    Ok(config
+        .client
+        .install
+        .default_install_path
+        .as_deref()
+        .and_then(|path| text(path.as_bytes()))
+        .map(PathBuf::from))
+}
+
+/// `%ProgramData%\Battle.net\Agent\product.db`
+pub fn product_db_path() -> Option<PathBuf> {
+    std::env::var_os("ProgramData").map(|dir| {
+        PathBuf::from(dir)
+            .join("Battle.net")
+            .join("Agent")
+            .join("product.db")
+    })
+}
+
+/// `%APPDATA%\Battle.net\Battle.net.config`
+pub fn client_config_path() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|dir| {
+        PathBuf::from(dir)
+            .join("Battle.net")
+            .join("Battle.net.config")
+    })
+}
+
+/// Where Battle.net says a StarCraft install is.
+#[derive(Debug, Clone)]
+pub struct BattleNetInstall {
+    pub path: PathBuf,
+    /// uid of the product, e.g. `s1_ptr`
+    pub uid: Option<String>,
+    pub branch: Option<String>,
+    /// The file it was read from
+    pub file: PathBuf,
+}
+
+/// StarCraft installs in `product.db`, plus the `StarCraft` folder of the default install
+/// path in `Battle.net.config`. Missing or unreadable files give nothing.
+pub fn starcraft_installs() -> Vec<BattleNetInstall> {
+    let mut installs = Vec::new();
+
+    if let Some(file) = product_db_path() {
+        let products = fs::read(&file).and_then(|data| parse_product_db(&data));
+        for product in products.unwrap_or_default() {
+            if !product.is_starcraft() {
+                continue;
+            }
+            let Some(path) = product.install_path.clone() else {
+                continue;
+            };
+            installs.push(BattleNetInstall {
+                path,
+                uid: Some(product.uid),
+                branch: product.branch,
+                file: file.clone(),
+            });
+        }
+    }
+
+    if let Some(file) = client_config_path() {
+        let library = fs::read_to_string(&file).and_then(|text| parse_client_config(&text));
+        if let Ok(Some(library)) = library {
+            installs.push(BattleNetInstall {
+                path: library.join("StarCraft"),
+                uid: None,
+                branch: None,
+                file,
+            });
+        }
+    }
+    installs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    /// A length-delimited protobuf field.
+    fn bytes(number: u64, value: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        varint(number << 3 | 2, &mut out);
+        varint(value.len() as u64, &mut out);
+        out.extend_from_slice(value);
+        out
+    }
+
+    fn product(uid: &str, code: &str, path: &str, branch: &str) -> Vec<u8> {
+        // Fields the parser skips: a varint (2), a fixed64 (8) and a fixed32 (9)
+        let mut settings = bytes(1, path.as_bytes());
+        settings.extend([2 << 3, 0xac, 0x02]);
+        settings.extend([8 << 3 | 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        settings.extend([9 << 3 | 5, 0, 0, 0, 0]);
+        settings.extend(bytes(10, branch.as_bytes()));
+        [
+            bytes(1, uid.as_bytes()),
+            bytes(2, code.as_bytes()),
+            bytes(3, &settings),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn products_are_read_with_path_and_branch() {
+        let data = [
+            bytes(
+                1,
+                &product("s1", "s1", r"C:\Program Files (x86)\StarCraft", ""),
+            ),
+            bytes(2, b"ignored top-level field"),
+            bytes(1, &product("s1_ptr", "s1", r"D:\StarCraft PTR", "ptr")),
+            bytes(
+                1,
+                &product("s2", "s2", r"C:\Program Files (x86)\StarCraft II", ""),
+            ),
+        ]
+        .concat();
+        let products = parse_product_db(&data).unwrap();
+
+        assert_eq!(products.len(), 3);
+        assert_eq!(
+            products[0],
+            ProductInstall {
+                uid: "s1".to_string(),
+                product_code: "s1".to_string(),
+                install_path: Some(PathBuf::from(r"C:\Program Files (x86)\StarCraft")),
+                branch: None,
+            }
+        );
+        assert_eq!(products[1].branch.as_deref(), Some("ptr"));
+        assert_eq!(
+            products
+                .iter()
+                .map(ProductInstall::is_starcraft)
+                .collect::<Vec<_>>(),
+            [true, true, false]
+        );
+    }
+
+    #[test]
+    fn damaged_databases_are_errors() {
+        let data = bytes(1, &product("s1", "s1", r"C:\StarCraft", ""));
+        assert!(parse_product_db(&data[..data.len() - 3]).is_err());
+        assert!(parse_product_db(&[0x08, 0xff]).is_err());
+        // Wire type 3 (start group) is not used by product.db
+        assert!(parse_product_db(&[0x0b]).is_err());
+        assert!(parse_product_db(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn the_default_install_path_is_read() {
+        let json = r#"{
+            "Client": {
+                "Install": { "DefaultInstallPath": "D:\\Games\\Battle.net" },
+                "Language": "koKR"
+            },
+            "Games": { "s1": { "Resumable": "false" } }
+        }"#;
+        assert_eq!(
+            parse_client_config(json).unwrap(),
+            Some(PathBuf::from(r"D:\Games\Battle.net"))
+        );
+    }
+
+    #[test]
+    fn missing_or_blank_install_paths_give_nothing() {
+        assert_eq!(parse_client_config("{}").unwrap(), None);
+        assert_eq!(
+            parse_client_config(r#"{ "Client": { "Install": {} } }"#).unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_client_config(r#"{ "Client": { "Install": { "DefaultInstallPath": " " } } }"#)
+                .unwrap(),
+            None
+        );
+        assert!(parse_client_config("not json").is_err());
+    }
+}
@@ -0,0 +1,815 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::i18n::Lang;
+use super::instance::DEFAULT_PROFILE;
+use super::limits::DEFAULT_MAX_INSTANCES;
+use super::portable;
+use super::window::Layout;
+use crate::t;
+
+/// Name of the per-directory config file, looked up in the working directory.
+pub const DIRECTORY_FILE: &str = "sclauncher.toml";
+
+/// Which StarCraft.exe to launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Arch {
+    #[default]
+    #[serde(rename = "x86")]
+    X86,
+    #[serde(rename = "x86_64")]
+    X86_64,
+}
+
+impl Arch {
+    /// Folder of the StarCraft.exe for this architecture, relative to the install root.
+    pub fn folder(self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.folder())
+    }
+}
+
+impl FromStr for Arch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "x86" | "32" | "i386" => Ok(Arch::X86),
+            "x86_64" | "x64" | "64" | "amd64" => Ok(Arch::X86_64),
+            _ => Err(t!("arch-unknown", arch = s)),
+        }
+    }
+}
+
+/// One layer of settings. Every field is optional so layers only override what they set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Number of games to launch
+    pub count: Option<u32>,
+    pub arch: Option<Arch>,
+    pub profile: Option<String>,
+    /// StarCraft folder or StarCraft.exe, skipping discovery
+    pub game_path: Option<PathBuf>,
+    /// Pause between two launches, in milliseconds
+    pub launch_delay_ms: Option<u64>,
+    /// How to arrange the game windows after launching
+    pub layout: Option<Layout>,
+    /// Labels for the launched games, in launch order
+    pub labels: Option<Vec<String>>,
+    /// Language of the messages, `en` or `ko`
+    pub lang: Option<Lang>,
+    /// Maximum number of StarCraft.exe allowed to run at once
+    pub max_instances: Option<u32>,
+    /// Maximum per profile, for the profiles that need a different one than `max_instances`.
+    /// Unlike the other settings, each layer only overrides the profiles it names.
+    pub profile_max_instances: Option<BTreeMap<String, u32>>,
+}
+
+/// Where a setting came from, from weakest to strongest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "at", rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Portable(PathBuf),
+    Directory(PathBuf),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::System(path) => write!(f, "system ({})", path.display()),
+            ConfigSource::User(path) => write!(f, "user ({})", path.display()),
+            ConfigSource::Portable(path) => write!(f, "portable ({})", path.display()),
+            ConfigSource::Directory(path) => write!(f, "directory ({})", path.display()),
+            ConfigSource::Env(var) => write!(f, "env ({})", var),
+            ConfigSource::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// One row of `config show`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEntry {
+    pub key: &'static str,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+}
+
+/// The merged settings of every layer, remembering which layer set each value.
+///
+/// Layers are applied in order: defaults, system file, user file, `sclauncher.toml` in the
+/// working directory, `SCLAUNCHER_*` environment variables and finally command line flags.
+/// A portable copy reads the file next to the executable instead of the system and user files.
+#[derive(Debug, Clone)]
+pub struct Config {
+    settings: Settings,
+    sources: BTreeMap<&'static str, ConfigSource>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut config = Config {
+            settings: Settings::default(),
+            sources: BTreeMap::new(),
+        };
+        config.apply(
+            Settings {
+                profile: Some(DEFAULT_PROFILE.to_string()),
+                launch_delay_ms: Some(1000),
+                layout: Some(Layout::None),
+                lang: Some(Lang::detect()),
+                max_instances: Some(DEFAULT_MAX_INSTANCES),
+                ..Settings::default()
+            },
+            ConfigSource::Default,
+        );
+        config
+    }
+}
+
+impl Config {
+    /// Loads every file and environment layer. Command line flags are applied by the caller.
+    pub fn load() -> Result<Config, String> {
+        let mut config = Config::default();
+
+        let portable_path = portable::config_path();
+        if let Some(path) = &portable_path {
+            if let Some(layer) = read_file(path)? {
+                config.apply(layer, ConfigSource::Portable(path.clone()));
+            }
+        } else {
+            if let Some(path) = system_config_path() {
+                if let Some(layer) = read_file(&path)? {
+                    config.apply(layer, ConfigSource::System(path));
+                }
+            }
+            if let Some(path) = user_config_path() {
+                if let Some(layer) = read_file(&path)? {
+                    config.apply(layer, ConfigSource::User(path));
+                }
+            }
+        }
+        let path = PathBuf::from(DIRECTORY_FILE);
+        // Started from its own folder, a portable copy would read its file twice
+        let same_file = portable_path.is_some_and(|portable| same_path(&portable, &path));
+        if !same_file {
+            if let Some(layer) = read_file(&path)? {
+                config.apply(layer, ConfigSource::Directory(path));
+            }
+        }
+        config.apply_env()?;
+
+        Ok(config)
+    }
+
+    /// Overrides the values `layer` sets.
+    pub fn apply(&mut self, layer: Settings, source: ConfigSource) {
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(
+                    if layer.$field.is_some() {
+                        self.settings.$field = layer.$field;
+                        self.sources.insert(stringify!($field), source.clone());
+                    }
+                )*
+            };
+        }
+        merge!(
+            count,
+            arch,
+            profile,
+            game_path,
+            launch_delay_ms,
+            layout,
+            labels,
+            lang,
+            max_instances
+        );
+        // A layer only replaces the limits of the profiles it names
+        if let Some(limits) = layer.profile_max_instances {
+            self.settings
+                .profile_max_instances
+                .get_or_insert_with(BTreeMap::new)
+                .extend(limits);
+            self.sources.insert("profile_max_instances", source);
+        }
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        fn var<T: FromStr>(name: &str) -> Result<Option<T>, String>
+        where
+            T::Err: fmt::Display,
+        {
+            match std::env::var(name) {
+                Ok(value) if !value.trim().is_empty() => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|e| format!("{}: {}", name, e)),
+                _ => Ok(None),
+            }
+        }
+
+        // One layer per variable, so `config show` can name the exact variable
+        let layers = [
+            (
+                "SCLAUNCHER_COUNT",
+                Settings {
+                    count: var("SCLAUNCHER_COUNT")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_ARCH",
+                Settings {
+                    arch: var("SCLAUNCHER_ARCH")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_PROFILE",
+                Settings {
+                    profile: var("SCLAUNCHER_PROFILE")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_GAME_PATH",
+                Settings {
+                    game_path: var("SCLAUNCHER_GAME_PATH")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_LAUNCH_DELAY_MS",
+                Settings {
+                    launch_delay_ms: var("SCLAUNCHER_LAUNCH_DELAY_MS")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_LAYOUT",
+                Settings {
+                    layout: var("SCLAUNCHER_LAYOUT")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_LABELS",
+                Settings {
+                    labels: var::<String>("SCLAUNCHER_LABELS")?
+                        .map(|labels| labels.split(',').map(|l| l.trim().to_string()).collect()),
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_LANG",
+                Settings {
+                    lang: var("SCLAUNCHER_LANG")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_MAX_INSTANCES",
+                Settings {
+                    max_instances: var("SCLAUNCHER_MAX_INSTANCES")?,
+                    ..Settings::default()
+                },
+            ),
+            (
+                "SCLAUNCHER_PROFILE_MAX_INSTANCES",
+                Settings {
+                    profile_max_instances: var::<String>("SCLAUNCHER_PROFILE_MAX_INSTANCES")?
+                        .map(|limits| parse_profile_limits(&limits))
+                        .transpose()
+                        .map_err(|e| format!("SCLAUNCHER_PROFILE_MAX_INSTANCES: {}", e))?,
+                    ..Settings::default()
+                },
+            ),
+        ];
+        for (name, layer) in layers {
+            self.apply(layer, ConfigSource::Env(name.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn source_of(&self, key: &str) -> ConfigSource {
+        self.sources
+            .get(key)
+            .cloned()
+            .unwrap_or(ConfigSource::Default)
+    }
+
+    /// `None` means ask the user.
+    pub fn count(&self) -> Option<u32> {
+        self.settings.count
+    }
+
+    /// `None` means the install decides, see `Installation::default_arch`.
+    pub fn arch(&self) -> Option<Arch> {
+        self.settings.arch
+    }
+
+    pub fn profile(&self) -> &str {
+        self.settings.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    pub fn game_path(&self) -> Option<&Path> {
+        self.settings.game_path.as_deref()
+    }
+
+    pub fn launch_delay(&self) -> Duration {
+        Duration::from_millis(self.settings.launch_delay_ms.unwrap_or(1000))
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.settings.layout.unwrap_or_default()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        self.settings.labels.as_deref().unwrap_or_default()
+    }
+
+    pub fn lang(&self) -> Lang {
+        self.settings.lang.unwrap_or_default()
+    }
+
+    pub fn max_instances(&self) -> u32 {
+        self.settings.max_instances.unwrap_or(DEFAULT_MAX_INSTANCES)
+    }
+
+    pub fn profile_max_instances(&self) -> BTreeMap<String, u32> {
+        self.settings
+            .profile_max_instances
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// Every setting with its effective value and source, for `config show`.
+    pub fn entries(&self) -> Vec<ConfigEntry> {
+        let values = serde_json::to_value(&self.settings).unwrap_or_default();
+        SETTING_KEYS
+            .iter()
+            .map(|key| ConfigEntry {
+                key,
+                value: values.get(key).cloned().unwrap_or_default(),
+                source: self.source_of(key),
+            })
+            .collect()
+    }
+}
+
+const SETTING_KEYS: [&str; 10] = [
+    "count",
+    "arch",
+    "profile",
+    "game_path",
+    "launch_delay_ms",
+    "layout",
+    "labels",
+    "lang",
+    "max_instances",
+    "profile_max_instances",
+];
+
+/// Parses one `PROFILE=N` entry of `profile_max_instances`.
+pub fn parse_profile_limit(s: &str) -> Result<(String, u32), String> {
+    match s.split_once('=') {
+        Some((profile, max)) if !profile.trim().is_empty() => match max.trim().parse() {
+            Ok(max) => Ok((profile.trim().to_string(), max)),
+            Err(_) => Err(t!("profile-limit-invalid", value = s)),
+        },
+        _ => Err(t!("profile-limit-invalid", value = s)),
+    }
+}
+
+/// Parses a comma separated list of `PROFILE=N` entries, such as `ladder=2,practice=4`.
+pub fn parse_profile_limits(s: &str) -> Result<BTreeMap<String, u32>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_profile_limit)
+        .collect()
+}
+
+/// Reads one TOML layer, returning `None` when the file does not exist.
+fn read_file(path: &Path) -> Result<Option<Settings>, String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    // toml's message already carries the line and column
+    toml::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Sets `game_path` in the TOML file at `path`, creating it if needed and keeping the other
+/// settings. Comments in the file are not kept.
+pub fn write_game_path(path: &Path, game_path: &Path) -> Result<(), String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let mut table: toml::Table =
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    table.insert(
+        "game_path".to_string(),
+        toml::Value::String(game_path.to_string_lossy().into_owned()),
+    );
+    let text = toml::to_string_pretty(&table).map_err(|e| format!("{}: {}", path.display(), e))?;
+    std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// `%ProgramData%\sclauncher\config.toml` on Windows, `/etc/sclauncher/config.toml` elsewhere.
+pub fn system_config_path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        std::env::var_os("ProgramData")
+            .map(|dir| PathBuf::from(dir).join("sclauncher").join("config.toml"))
+    }
+    #[cfg(not(windows))]
+    {
+        Some(PathBuf::from("/etc/sclauncher/config.toml"))
+    }
+}
+
+/// `%APPDATA%\sclauncher\config.toml` on Windows, `$XDG_CONFIG_HOME/sclauncher/config.toml` elsewhere.
+pub fn user_config_path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    dir.map(|dir| dir.join("sclauncher").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Serializes the tests that set `SCLAUNCHER_*` variables, which the whole process shares.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const ENV_VARS: [&str; 10] = [
+        "SCLAUNCHER_COUNT",
+        "SCLAUNCHER_ARCH",
+        "SCLAUNCHER_PROFILE",
+        "SCLAUNCHER_GAME_PATH",
+        "SCLAUNCHER_LAUNCH_DELAY_MS",
+        "SCLAUNCHER_LAYOUT",
+        "SCLAUNCHER_LABELS",
+        "SCLAUNCHER_LANG",
+        "SCLAUNCHER_MAX_INSTANCES",
+        "SCLAUNCHER_PROFILE_MAX_INSTANCES",
+    ];
+
+    /// Runs `test` with exactly the given `SCLAUNCHER_*` variables set.
+    fn with_env<R>(vars: &[(&str, &str)], test: impl FnOnce() -> R) -> R {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for name in ENV_VARS {
+            std::env::remove_var(name);
+        }
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        let result = test();
+        for name in ENV_VARS {
+            std::env::remove_var(name);
+        }
+        result
+    }
+
+    fn layer(text: &str) -> Settings {
+        toml::from_str(text).unwrap()
+    }
+
+    fn env(name: &str) -> ConfigSource {
+        ConfigSource::Env(name.to_string())
+    }
+
+    #[test]
+    fn each_layer_overrides_the_ones_before() {
+        let system = PathBuf::from("/etc/sclauncher/config.toml");
+        let user = PathBuf::from("/home/user/.config/sclauncher/config.toml");
+        let directory = PathBuf::from(DIRECTORY_FILE);
+
+        let mut config = with_env(
+            &[
+                ("SCLAUNCHER_GAME_PATH", "/env/StarCraft"),
+                ("SCLAUNCHER_MAX_INSTANCES", "5"),
+                ("SCLAUNCHER_ARCH", "x86"),
+                ("SCLAUNCHER_COUNT", "4"),
+            ],
+            || {
+                let mut config = Config::default();
+                config.apply(
+                    layer(
+                        r#"
+                        count = 1
+                        arch = "x86"
+                        profile = "system"
+                        game_path = "/system/StarCraft"
+                        launch_delay_ms = 500
+                        layout = "cascade"
+                        labels = ["system"]
+                        lang = "en"
+                        max_instances = 2
+                        [profile_max_instances]
+                        ladder = 1
+                        practice = 1
+                        "#,
+                    ),
+                    ConfigSource::System(system.clone()),
+                );
+                config.apply(
+                    layer(
+                        r#"
+                        count = 2
+                        arch = "x86"
+                        profile = "user"
+                        game_path = "/user/StarCraft"
+                        layout = "grid"
+                        labels = ["user"]
+                        lang = "ko"
+                        max_instances = 3
+                        "#,
+                    ),
+                    ConfigSource::User(user.clone()),
+                );
+                config.apply(
+                    layer(
+                        r#"
+                        count = 3
+                        profile = "directory"
+                        game_path = "/directory/StarCraft"
+                        labels = ["left", "right"]
+                        max_instances = 4
+                        [profile_max_instances]
+                        ladder = 3
+                        "#,
+                    ),
+                    ConfigSource::Directory(directory.clone()),
+                );
+                config.apply_env().unwrap();
+                config
+            },
+        );
+        config.apply(
+            Settings {
+                count: Some(6),
+                arch: Some(Arch::X86_64),
+                profile_max_instances: Some(BTreeMap::from([("practice".to_string(), 5)])),
+                ..Settings::default()
+            },
+            ConfigSource::Cli,
+        );
+
+        assert_eq!(config.launch_delay(), Duration::from_millis(500));
+        assert_eq!(
+            config.source_of("launch_delay_ms"),
+            ConfigSource::System(system)
+        );
+        assert_eq!(config.layout(), Layout::Grid);
+        assert_eq!(config.source_of("layout"), ConfigSource::User(user.clone()));
+        assert_eq!(config.lang(), Lang::Ko);
+        assert_eq!(config.source_of("lang"), ConfigSource::User(user));
+        assert_eq!(config.profile(), "directory");
+        assert_eq!(
+            config.source_of("profile"),
+            ConfigSource::Directory(directory.clone())
+        );
+        assert_eq!(config.labels(), ["left", "right"]);
+        assert_eq!(
+            config.source_of("labels"),
+            ConfigSource::Directory(directory)
+        );
+        assert_eq!(config.game_path(), Some(Path::new("/env/StarCraft")));
+        assert_eq!(config.source_of("game_path"), env("SCLAUNCHER_GAME_PATH"));
+        assert_eq!(config.max_instances(), 5);
+        assert_eq!(
+            config.source_of("max_instances"),
+            env("SCLAUNCHER_MAX_INSTANCES")
+        );
+        assert_eq!(config.count(), Some(6));
+        assert_eq!(config.source_of("count"), ConfigSource::Cli);
+        assert_eq!(config.arch(), Some(Arch::X86_64));
+        assert_eq!(config.source_of("arch"), ConfigSource::Cli);
+        assert_eq!(
+            config.profile_max_instances(),
+            BTreeMap::from([("ladder".to_string(), 3), ("practice".to_string(), 5)])
+        );
+        assert_eq!(config.source_of("profile_max_instances"), ConfigSource::Cli);
+    }
+
+    #[test]
+    fn portable_file_layers_like_the_user_file() {
+        let portable = PathBuf::from("/apps/sclauncher/sclauncher.toml");
+        let mut config = Config::default();
+        config.apply(
+            layer("profile = \"usb\"\nmax_instances = 3\n"),
+            ConfigSource::Portable(portable.clone()),
+        );
+        config.apply(
+            layer("max_instances = 4\n"),
+            ConfigSource::Directory(PathBuf::from(DIRECTORY_FILE)),
+        );
+
+        assert_eq!(config.profile(), "usb");
+        assert_eq!(
+            config.source_of("profile"),
+            ConfigSource::Portable(portable)
+        );
+        assert_eq!(
+            config.source_of("max_instances"),
+            ConfigSource::Directory(PathBuf::from(DIRECTORY_FILE))
+        );
+        assert_eq!(config.source_of("count"), ConfigSource::Default);
+        assert_eq!(config.source_of("launch_delay_ms"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn env_values_are_parsed() {
+        let config = with_env(
+            &[
+                ("SCLAUNCHER_COUNT", " 3 "),
+                ("SCLAUNCHER_ARCH", "amd64"),
+                ("SCLAUNCHER_PROFILE", "ladder"),
+                ("SCLAUNCHER_LAUNCH_DELAY_MS", "250"),
+                ("SCLAUNCHER_LAYOUT", "cascade"),
+                ("SCLAUNCHER_LABELS", "main, alt ,third"),
+                ("SCLAUNCHER_LANG", "ko_KR.UTF-8"),
+                ("SCLAUNCHER_PROFILE_MAX_INSTANCES", "ladder=2, practice=4"),
+                // Blank variables are treated as unset
+                ("SCLAUNCHER_MAX_INSTANCES", "  "),
+            ],
+            || {
+                let mut config = Config::default();
+                config.apply_env().map(|()| config)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(config.count(), Some(3));
+        assert_eq!(config.arch(), Some(Arch::X86_64));
+        assert_eq!(config.profile(), "ladder");
+        assert_eq!(config.launch_delay(), Duration::from_millis(250));
+        assert_eq!(config.layout(), Layout::Cascade);
+        assert_eq!(config.labels(), ["main", "alt", "third"]);
+        assert_eq!(config.lang(), Lang::Ko);
+        assert_eq!(
+            config.profile_max_instances(),
+            BTreeMap::from([("ladder".to_string(), 2), ("practice".to_string(), 4)])
+        );
+        assert_eq!(config.source_of("labels"), env("SCLAUNCHER_LABELS"));
+        assert_eq!(config.max_instances(), DEFAULT_MAX_INSTANCES);
+        assert_eq!(config.source_of("max_instances"), ConfigSource::Default);
+    }
+
+    #[test]
+    fn invalid_env_values_name_the_variable() {
+        for (name, value) in [
+            ("SCLAUNCHER_COUNT", "three"),
+            ("SCLAUNCHER_ARCH", "arm"),
+            ("SCLAUNCHER_LAUNCH_DELAY_MS", "-1"),
+            ("SCLAUNCHER_MAX_INSTANCES", "many"),
+            ("SCLAUNCHER_PROFILE_MAX_INSTANCES", "ladder"),
+        ] {
+            let error = with_env(&[(name, value)], || Config::default().apply_env()).unwrap_err();
+            assert!(error.starts_with(name), "{}: {}", name, error);
+        }
+    }
+
+    #[test]
+    fn game_path_is_written_to_a_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        write_game_path(&path, Path::new("/games/StarCraft")).unwrap();
+
+        let settings = read_file(&path).unwrap().unwrap();
+        assert_eq!(settings.game_path, Some(PathBuf::from("/games/StarCraft")));
+        assert_eq!(settings.count, None);
+    }
+
+    #[test]
+    fn game_path_is_written_over_the_old_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "count = 2\ngame_path = \"/old/StarCraft\"\n[profile_max_instances]\nladder = 1\n",
+        )
+        .unwrap();
+
+        write_game_path(&path, Path::new("/new/StarCraft")).unwrap();
+
+        let settings = read_file(&path).unwrap().unwrap();
+        assert_eq!(settings.game_path, Some(PathBuf::from("/new/StarCraft")));
+        assert_eq!(settings.count, Some(2));
+        assert_eq!(
+            settings.profile_max_instances,
+            Some(BTreeMap::from([("ladder".to_string(), 1)]))
+        );
+    }
+
+    #[test]
+    fn game_path_is_not_written_over_a_broken_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "count = \n").unwrap();
+
+        let error = write_game_path(&path, Path::new("/games/StarCraft")).unwrap_err();
+
+        assert!(error.starts_with(&path.display().to_string()), "{}", error);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "count = \n");
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_file(&dir.path().join("config.toml")), Ok(None));
+    }
+
+    #[test]
+    fn profile_limits_are_parsed() {
+        let limits = parse_profile_limits(" ladder=2, practice = 4,").unwrap();
+        assert_eq!(limits.get("ladder"), Some(&2));
+        assert_eq!(limits.get("practice"), Some(&4));
+        assert_eq!(limits.len(), 2);
+
+        assert!(parse_profile_limit("ladder").is_err());
+        assert!(parse_profile_limit("=2").is_err());
+        assert!(parse_profile_limit("ladder=two").is_err());
+    }
+
+    #[test]
+    fn instance_limits_are_layered() {
+        let mut config = Config::default();
+        assert_eq!(config.max_instances(), DEFAULT_MAX_INSTANCES);
+        assert!(config.profile_max_instances().is_empty());
+
+        let file: Settings =
+            toml::from_str("max_instances = 4\n[profile_max_instances]\nladder = 2\n").unwrap();
+        config.apply(file, ConfigSource::Directory(PathBuf::from(DIRECTORY_FILE)));
+        config.apply(
+            Settings {
+                max_instances: Some(6),
+                ..Settings::default()
+            },
+            ConfigSource::Env("SCLAUNCHER_MAX_INSTANCES".to_string()),
+        );
+        config.apply(
+            Settings {
+                profile_max_instances: Some(BTreeMap::from([("practice".to_string(), 4)])),
+                ..Settings::default()
+            },
+            ConfigSource::Cli,
+        );
+
+        assert_eq!(config.max_instances(), 6);
+        assert_eq!(config.profile_max_instances().get("ladder"), Some(&2));
+        assert_eq!(config.profile_max_instances().get("practice"), Some(&4));
+        assert_eq!(
+            config.source_of("max_instances"),
+            ConfigSource::Env("SCLAUNCHER_MAX_INSTANCES".to_string())
+        );
+        assert_eq!(config.source_of("profile_max_instances"), ConfigSource::Cli);
+    }
+}
@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use super::selector::Selector;
+use super::window::Layout;
+use crate::t;
+
+/// A line typed into the interactive session console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    List,
+    Launch(u32),
+    Kill(Selector),
+    Relabel {
+        selector: Selector,
+        label: Option<String>,
+    },
+    Layout(Layout),
+    Guard(bool),
+    Quit {
+        keep: bool,
+    },
+    Help,
+}
+
+impl FromStr for ConsoleCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let Some(name) = words.next() else {
+            return Err(t!("console-empty"));
+        };
+        let rest: Vec<&str> = words.collect();
+
+        let command = match (name.to_ascii_lowercase().as_str(), rest.as_slice()) {
+            ("list" | "ls", []) => ConsoleCommand::List,
+            ("launch", []) => ConsoleCommand::Launch(1),
+            ("launch", [count]) => match count.parse::<u32>() {
+                Ok(count) if count > 0 => ConsoleCommand::Launch(count),
+                _ => return Err(t!("console-bad-count", value = count)),
+            },
+            ("kill", [_, ..]) => ConsoleCommand::Kill(rest.join(" ").parse()?),
+            ("relabel", [selector]) => ConsoleCommand::Relabel {
+                selector: selector.parse()?,
+                label: None,
+            },
+            ("relabel", [selector, label @ ..]) => ConsoleCommand::Relabel {
+                selector: selector.parse()?,
+                label: Some(label.join(" ")),
+            },
+            ("layout", [layout]) => ConsoleCommand::Layout(layout.parse()?),
+            ("guard", [state]) => match state.to_ascii_lowercase().as_str() {
+                "on" => ConsoleCommand::Guard(true),
+                "off" => ConsoleCommand::Guard(false),
+                _ => return Err(t!("console-bad-guard", value = state)),
+            },
+            ("quit" | "exit", []) => ConsoleCommand::Quit { keep: false },
+            ("quit" | "exit", ["--keep"]) => ConsoleCommand::Quit { keep: true },
+            ("help" | "?", []) => ConsoleCommand::Help,
+            (
+                "list" | "ls" | "launch" | "kill" | "relabel" | "layout" | "guard" | "quit"
+                | "exit" | "help" | "?",
+                _,
+            ) => return Err(t!("console-bad-args", command = name)),
+            _ => return Err(t!("console-unknown", command = name)),
+        };
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+
+    fn parse(line: &str) -> Result<ConsoleCommand, String> {
+        set_language(Lang::En);
+        line.parse()
+    }
+
+    #[test]
+    fn commands_and_their_arguments() {
+        assert_eq!(parse("list"), Ok(ConsoleCommand::List));
+        assert_eq!(parse("  LS  "), Ok(ConsoleCommand::List));
+        assert_eq!(parse("launch"), Ok(ConsoleCommand::Launch(1)));
+        assert_eq!(parse("launch 3"), Ok(ConsoleCommand::Launch(3)));
+        assert_eq!(parse("kill all"), Ok(ConsoleCommand::Kill(Selector::All)));
+        assert_eq!(
+            parse("kill newest:2"),
+            Ok(ConsoleCommand::Kill(Selector::Newest(2)))
+        );
+        assert_eq!(
+            parse("layout GRID"),
+            Ok(ConsoleCommand::Layout(Layout::Grid))
+        );
+        assert_eq!(parse("guard on"), Ok(ConsoleCommand::Guard(true)));
+        assert_eq!(parse("guard Off"), Ok(ConsoleCommand::Guard(false)));
+        assert_eq!(parse("quit"), Ok(ConsoleCommand::Quit { keep: false }));
+        assert_eq!(
+            parse("exit --keep"),
+            Ok(ConsoleCommand::Quit { keep: true })
+        );
+        assert_eq!(parse("?"), Ok(ConsoleCommand::Help));
+    }
+
+    #[test]
+    fn relabel_joins_the_label_and_clears_without_one() {
+        assert_eq!(
+            parse("relabel 2 practice partner"),
+            Ok(ConsoleCommand::Relabel {
+                selector: Selector::Index(2),
+                label: Some("practice partner".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("relabel label:bot*"),
+            Ok(ConsoleCommand::Relabel {
+                selector: Selector::LabelGlob("bot*".to_string()),
+                label: None,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_commands_are_named() {
+        assert_eq!(
+            parse("launh 2"),
+            Err("Unknown command 'launh', type help.".to_string())
+        );
+        assert_eq!(parse("   "), Err("Empty command.".to_string()));
+    }
+
+    #[test]
+    fn argument_errors() {
+        assert_eq!(
+            parse("launch 0"),
+            Err("'0' is not a number of games.".to_string())
+        );
+        assert_eq!(
+            parse("launch two"),
+            Err("'two' is not a number of games.".to_string())
+        );
+        assert_eq!(
+            parse("guard maybe"),
+            Err("Use 'guard on' or 'guard off', not 'maybe'.".to_string())
+        );
+        for line in [
+            "list all",
+            "launch 1 2",
+            "kill",
+            "relabel",
+            "layout",
+            "guard",
+            "quit now",
+            "help me",
+        ] {
+            let command = line.split_whitespace().next().unwrap();
+            assert_eq!(
+                parse(line),
+                Err(format!("Wrong arguments for '{}', type help.", command)),
+                "{:?}",
+                line
+            );
+        }
+        // Selector and layout errors come from their own parsers
+        assert!(parse("kill pid:x").is_err());
+        assert!(parse("relabel 0 main").is_err());
+        assert!(parse("layout spiral").is_err());
+    }
+}
@@ -16,6 +16,10 @@ extern "system" {
         ProcessInformationLength: u32,
         ReturnLength: *mut u32,
     ) -> NTSTATUS;
+
+    pub fn NtSuspendProcess(ProcessHandle: HANDLE) -> NTSTATUS;
+
+    pub fn NtResumeProcess(ProcessHandle: HANDLE) -> NTSTATUS;
 }
 
 #[repr(C)]
@@ -26,6 +30,7 @@ pub struct PROCESS_HANDLE_SNAPSHOT_INFORMATION {
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct PROCESS_HANDLE_TABLE_ENTRY_INFO {
     pub HandleValue: HANDLE,
     pub HandleCount: usize,
@@ -0,0 +1,636 @@
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::sync::{broadcast, Notify};
+use tokio::time::sleep;
+
+use super::events::GameEvent;
+#[cfg(windows)]
+use super::game::GameManager;
+use super::instance::{GameInstance, LaunchOptions};
+use super::ipc::{answer_hello, read_message, write_message, Request, Response, PROTOCOL_VERSION};
+#[cfg(windows)]
+use super::limits::WindowsSystemInfo;
+use super::limits::{LaunchLimits, LimitViolation, Running, SystemInfo};
+use super::output::{ErrorClass, ErrorInfo};
+use super::selector::Selector;
+use crate::t;
+
+/// The games a daemon controls: `GameManager` on Windows, a fake in the tests.
+pub trait GameBackend: SystemInfo + Send + Sync + 'static {
+    /// The tracked instances in launch order.
+    fn instances(&self) -> impl Future<Output = Vec<GameInstance>> + Send;
+
+    /// Every StarCraft.exe on the machine, tracked or not.
+    fn running_games(&self) -> u32;
+
+    /// Launches and tracks one game, returning its pid.
+    fn launch(
+        &self,
+        game_path: PathBuf,
+        options: LaunchOptions,
+    ) -> impl Future<Output = Option<u32>> + Send;
+
+    /// Kills the tracked instances matching `selector` and returns their pids.
+    fn kill_selected(&self, selector: &Selector) -> impl Future<Output = Vec<u32>> + Send;
+
+    /// Starts tracking StarCraft.exe processes that were not launched through the backend.
+    fn adopt_running_games(&self) -> impl Future<Output = Result<Vec<u32>, String>> + Send;
+
+    fn guard_running(&self) -> bool;
+
+    /// Stops the background mutex guard. Returns `false` if it was not running.
+    fn stop_guard(&self) -> bool;
+
+    /// Receives every `GameEvent` published after this call.
+    fn subscribe(&self) -> broadcast::Receiver<GameEvent>;
+}
+
+#[cfg(windows)]
+impl SystemInfo for GameManager {
+    fn available_memory(&self) -> u64 {
+        WindowsSystemInfo.available_memory()
+    }
+}
+
+#[cfg(windows)]
+impl GameBackend for GameManager {
+    async fn instances(&self) -> Vec<GameInstance> {
+        GameManager::instances(self).await
+    }
+
+    fn running_games(&self) -> u32 {
+        super::game::find_starcraft_pids().map_or(0, |pids| pids.len() as u32)
+    }
+
+    async fn launch(&self, game_path: PathBuf, options: LaunchOptions) -> Option<u32> {
+        self.launch_game_with(game_path, options).await
+    }
+
+    async fn kill_selected(&self, selector: &Selector) -> Vec<u32> {
+        GameManager::kill_selected(self, selector).await
+    }
+
+    async fn adopt_running_games(&self) -> Result<Vec<u32>, String> {
+        GameManager::adopt_running_games(self)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn guard_running(&self) -> bool {
+        GameManager::guard_running(self)
+    }
+
+    fn stop_guard(&self) -> bool {
+        GameManager::stop_guard(self)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        GameManager::subscribe(self)
+    }
+}
+
+/// Long-lived owner of a [`GameBackend`], controlled by short-lived CLI calls over IPC.
+///
+/// Listens on a named pipe on Windows and a Unix socket elsewhere (see `ipc`).
+pub struct Daemon<B> {
+    backend: B,
+    game_path: Option<PathBuf>,
+    /// Checked before every launch, whichever client asks for it
+    limits: LaunchLimits,
+    shutdown: Notify,
+}
+
+impl<B: GameBackend> Daemon<B> {
+    pub fn new(backend: B, game_path: Option<PathBuf>, limits: LaunchLimits) -> Arc<Self> {
+        Arc::new(Daemon {
+            backend,
+            game_path,
+            limits,
+            shutdown: Notify::new(),
+        })
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Makes `serve` return after the connections in flight are answered.
+    pub fn shutdown(&self) {
+        self.shutdown.notify_one();
+    }
+
+    /// Accepts clients until a `Shutdown` request arrives or `shutdown` is called.
+    #[cfg(windows)]
+    pub async fn serve(self: Arc<Self>) -> io::Result<()> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        use super::ipc::PIPE_NAME;
+
+        // `first_pipe_instance` fails if another daemon already owns the pipe
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(PIPE_NAME)?;
+
+        loop {
+            tokio::select! {
+                connected = server.connect() => connected?,
+                _ = self.shutdown.notified() => return Ok(()),
+            }
+
+            // Create the next instance before handing this one off, so clients never see no pipe
+            let client = std::mem::replace(&mut server, ServerOptions::new().create(PIPE_NAME)?);
+            tokio::spawn(self.clone().serve_connection(client));
+        }
+    }
+
+    /// Accepts clients until a `Shutdown` request arrives or `shutdown` is called.
+    #[cfg(unix)]
+    pub async fn serve(self: Arc<Self>) -> io::Result<()> {
+        self.serve_at(&super::ipc::socket_path()).await
+    }
+
+    /// [`Daemon::serve`] on the Unix socket at `path`.
+    #[cfg(unix)]
+    async fn serve_at(self: Arc<Self>, path: &std::path::Path) -> io::Result<()> {
+        use tokio::net::UnixListener;
+
+        // A stale socket file is left behind when a daemon crashes
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+
+        let result = loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(self.clone().serve_connection(stream));
+                    }
+                    Err(e) => break Err(e),
+                },
+                _ = self.shutdown.notified() => break Ok(()),
+            }
+        };
+
+        let _ = std::fs::remove_file(path);
+        result
+    }
+
+    async fn serve_connection<S>(self: Arc<Self>, stream: S)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+
+        // Every connection starts with a version handshake
+        let hello = match read_message::<_, Request>(&mut reader).await {
+            Ok(Some(request)) => answer_hello(&request),
+            Ok(None) | Err(_) => return,
+        };
+        let hello = match hello {
+            Ok(hello) => hello,
+            Err(refusal) => {
+                let _ = write_message(&mut writer, &refusal).await;
+                return;
+            }
+        };
+        if write_message(&mut writer, &hello).await.is_err() {
+            return;
+        }
+
+        loop {
+            let request = match read_message::<_, Request>(&mut reader).await {
+                Ok(Some(request)) => request,
+                Ok(None) => return,
+                Err(e) => {
+                    let _ = write_message(
+                        &mut writer,
+                        &error(ErrorClass::Usage, t!("daemon-invalid-request", error = e)),
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+            let is_shutdown = matches!(request, Request::Shutdown);
+            let response = self.handle(request).await;
+            if write_message(&mut writer, &response).await.is_err() {
+                return;
+            }
+            if is_shutdown {
+                self.shutdown();
+                return;
+            }
+        }
+    }
+
+    /// Runs one request against the managed games.
+    pub async fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Hello { .. } => Response::Hello {
+                version: PROTOCOL_VERSION,
+                pid: std::process::id(),
+            },
+            Request::List => Response::Instances {
+                instances: self.backend.instances().await,
+            },
+            Request::Launch {
+                count,
+                profile,
+                labels,
+                game_path,
+            } => {
+                let Some(game_path) = game_path.or_else(|| self.game_path.clone()) else {
+                    return error(ErrorClass::InstallNotFound, t!("daemon-no-game-path"));
+                };
+                if let Err(violations) = self.check_limits(&profile, count).await {
+                    let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                    return error(
+                        ErrorClass::LimitExceeded,
+                        t!("limits-refuse", reasons = reasons.join(" ")),
+                    );
+                }
+
+                let mut pids = Vec::new();
+                for i in 0..count {
+                    if i > 0 {
+                        sleep(Duration::from_secs(1)).await;
+                    }
+                    let options = LaunchOptions {
+                        label: labels.get(i as usize).cloned(),
+                        profile: profile.clone(),
+                        ..LaunchOptions::default()
+                    };
+                    if let Some(pid) = self.backend.launch(game_path.clone(), options).await {
+                        pids.push(pid);
+                    }
+                }
+
+                if pids.is_empty() && count > 0 {
+                    error(ErrorClass::LaunchFailed, t!("launch-none"))
+                } else {
+                    Response::Launched { pids }
+                }
+            }
+            Request::Kill { selector } => {
+                let selector = match selector.parse::<Selector>() {
+                    Ok(selector) => selector,
+                    Err(e) => return error(ErrorClass::Usage, e),
+                };
+                let pids = self.backend.kill_selected(&selector).await;
+                if pids.is_empty() {
+                    error(ErrorClass::NoMatch, t!("no-match", selector = &selector))
+                } else {
+                    Response::Killed { pids }
+                }
+            }
+            Request::Status => Response::Status {
+                instances: self.backend.instances().await,
+                guard_running: self.backend.guard_running(),
+                game_path: self.game_path.clone(),
+            },
+            Request::Adopt => match self.backend.adopt_running_games().await {
+                Ok(pids) => Response::Adopted { pids },
+                Err(e) => error(
+                    ErrorClass::ProcessQuery,
+                    t!("process-list-failed", error = e),
+                ),
+            },
+            Request::Shutdown => {
+                self.backend.stop_guard();
+                Response::Ok
+            }
+        }
+    }
+
+    /// Checks `count` more games of `profile` against the limits, counting the daemon's own
+    /// games of that profile.
+    async fn check_limits(&self, profile: &str, count: u32) -> Result<(), Vec<LimitViolation>> {
+        let instances = self.backend.instances().await;
+        let running = Running {
+            // Games the daemon tracks may already have exited
+            total: self.backend.running_games().max(instances.len() as u32),
+            in_profile: Some(instances.iter().filter(|i| i.profile == profile).count() as u32),
+        };
+        self.limits
+            .check(&self.backend, profile, running, count)
+            .map(|_| ())
+    }
+}
+
+fn error(class: ErrorClass, message: impl Into<String>) -> Response {
+    Response::Error {
+        error: ErrorInfo::new(class, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use tokio::io::{duplex, split, AsyncWriteExt};
+
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+    use crate::util::ipc::IpcClient;
+    use crate::util::limits::GuardPolicy;
+    use crate::util::testing::{game, FakeBackend};
+
+    fn daemon(games: Vec<GameInstance>) -> Arc<Daemon<FakeBackend>> {
+        limited_daemon(games, LaunchLimits::default())
+    }
+
+    fn limited_daemon(games: Vec<GameInstance>, limits: LaunchLimits) -> Arc<Daemon<FakeBackend>> {
+        set_language(Lang::En);
+        Daemon::new(
+            FakeBackend::new(games),
+            Some(PathBuf::from("StarCraft.exe")),
+            limits,
+        )
+    }
+
+    fn launch(count: u32, profile: &str) -> Request {
+        Request::Launch {
+            count,
+            profile: profile.to_string(),
+            labels: Vec::new(),
+            game_path: None,
+        }
+    }
+
+    fn error_class(response: Response) -> ErrorClass {
+        match response {
+            Response::Error { error } => error.class,
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_and_daemon_agree_on_the_version() {
+        let daemon = daemon(vec![game(42, Some("main"), "default")]);
+        let (client, server) = duplex(4096);
+        let connection = tokio::spawn(daemon.clone().serve_connection(server));
+
+        let mut client = IpcClient::handshake(client).await.unwrap();
+        assert_eq!(client.daemon_pid, std::process::id());
+        match client.call(&Request::List).await.unwrap() {
+            Response::Instances { instances } => {
+                assert_eq!(instances.len(), 1);
+                assert_eq!(instances[0].pid, 42);
+                assert_eq!(instances[0].label.as_deref(), Some("main"));
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        drop(client);
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn daemon_refuses_another_version_and_hangs_up() {
+        let daemon = daemon(Vec::new());
+        let (client, server) = duplex(4096);
+        let connection = tokio::spawn(daemon.serve_connection(server));
+
+        let (reader, mut writer) = split(client);
+        let mut reader = BufReader::new(reader);
+        let hello = Request::Hello {
+            version: PROTOCOL_VERSION + 1,
+        };
+        write_message(&mut writer, &hello).await.unwrap();
+
+        match read_message::<_, Response>(&mut reader).await.unwrap() {
+            Some(Response::Error { error }) => assert_eq!(error.class, ErrorClass::Daemon),
+            other => panic!("unexpected response {:?}", other),
+        }
+        assert!(read_message::<_, Response>(&mut reader)
+            .await
+            .unwrap()
+            .is_none());
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_malformed_request_is_answered_and_the_connection_closed() {
+        let daemon = daemon(Vec::new());
+        let (client, server) = duplex(4096);
+        let connection = tokio::spawn(daemon.serve_connection(server));
+
+        let (reader, mut writer) = split(client);
+        let mut reader = BufReader::new(reader);
+        let hello = Request::Hello {
+            version: PROTOCOL_VERSION,
+        };
+        write_message(&mut writer, &hello).await.unwrap();
+        let hello = read_message::<_, Response>(&mut reader).await.unwrap();
+        assert!(matches!(hello, Some(Response::Hello { .. })));
+        writer.write_all(b"{\"type\":\"explode\"}\n").await.unwrap();
+
+        let response = read_message::<_, Response>(&mut reader).await.unwrap();
+        assert_eq!(error_class(response.unwrap()), ErrorClass::Usage);
+        connection.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_the_guard_and_ends_the_connection() {
+        let daemon = daemon(Vec::new());
+        let (client, server) = duplex(4096);
+        let connection = tokio::spawn(daemon.clone().serve_connection(server));
+
+        let mut client = IpcClient::handshake(client).await.unwrap();
+        assert!(matches!(
+            client.call(&Request::Shutdown).await.unwrap(),
+            Response::Ok
+        ));
+        connection.await.unwrap();
+        assert!(!daemon.backend().guard_running());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn launch_labels_games_in_order() {
+        let daemon = daemon(Vec::new());
+        let response = daemon
+            .handle(Request::Launch {
+                count: 2,
+                profile: "ladder".to_string(),
+                labels: vec!["left".to_string()],
+                game_path: None,
+            })
+            .await;
+
+        let Response::Launched { pids } = response else {
+            panic!("unexpected response {:?}", response);
+        };
+        assert_eq!(pids, [1000, 1001]);
+        let games = daemon.backend().games();
+        assert_eq!(games[0].label.as_deref(), Some("left"));
+        assert_eq!(games[1].label, None);
+        assert!(games.iter().all(|g| g.profile == "ladder"));
+        assert_eq!(games[0].game_path, Some(PathBuf::from("StarCraft.exe")));
+    }
+
+    #[tokio::test]
+    async fn launch_needs_a_game_path() {
+        set_language(Lang::En);
+        let daemon = Daemon::new(FakeBackend::new(Vec::new()), None, LaunchLimits::default());
+        let response = daemon
+            .handle(Request::Launch {
+                count: 1,
+                profile: "default".to_string(),
+                labels: Vec::new(),
+                game_path: None,
+            })
+            .await;
+        assert_eq!(error_class(response), ErrorClass::InstallNotFound);
+    }
+
+    #[tokio::test]
+    async fn launch_fails_when_no_game_starts() {
+        let daemon = daemon(Vec::new());
+        daemon.backend().fail_launches.store(true, Ordering::SeqCst);
+        let response = daemon
+            .handle(Request::Launch {
+                count: 1,
+                profile: "default".to_string(),
+                labels: Vec::new(),
+                game_path: Some(PathBuf::from("other.exe")),
+            })
+            .await;
+        assert_eq!(error_class(response), ErrorClass::LaunchFailed);
+    }
+
+    #[tokio::test]
+    async fn launch_refuses_to_go_over_the_limits() {
+        let limits = LaunchLimits {
+            max_instances: 3,
+            profile_max_instances: [("ladder".to_string(), 1)].into(),
+            ..LaunchLimits::default()
+        };
+        let daemon = limited_daemon(vec![game(1, None, "ladder")], limits);
+
+        // The profile already has its one game
+        let response = daemon.handle(launch(1, "ladder")).await;
+        assert_eq!(error_class(response), ErrorClass::LimitExceeded);
+
+        // Three games at most, whatever their profile
+        let response = daemon.handle(launch(3, "default")).await;
+        assert_eq!(error_class(response), ErrorClass::LimitExceeded);
+
+        // StarCraft.exe the daemon does not track count against the machine-wide maximum
+        daemon.backend().untracked.store(2, Ordering::SeqCst);
+        let response = daemon.handle(launch(1, "default")).await;
+        assert_eq!(error_class(response), ErrorClass::LimitExceeded);
+        assert_eq!(daemon.backend().games().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn launch_refuses_without_enough_memory() {
+        let daemon = daemon(Vec::new());
+        daemon.backend().available.store(0, Ordering::SeqCst);
+        let response = daemon.handle(launch(1, "default")).await;
+        assert_eq!(error_class(response), ErrorClass::LimitExceeded);
+    }
+
+    #[tokio::test]
+    async fn launch_only_warns_under_the_warn_policy() {
+        let limits = LaunchLimits {
+            max_instances: 0,
+            policy: GuardPolicy::Warn,
+            ..LaunchLimits::default()
+        };
+        let daemon = limited_daemon(Vec::new(), limits);
+        let response = daemon.handle(launch(1, "default")).await;
+        assert!(matches!(response, Response::Launched { pids } if pids.len() == 1));
+    }
+
+    #[tokio::test]
+    async fn kill_takes_a_selector() {
+        let daemon = daemon(vec![
+            game(1, Some("main"), "default"),
+            game(2, Some("smurf"), "ladder"),
+        ]);
+
+        let response = daemon
+            .handle(Request::Kill {
+                selector: "label:sm*".to_string(),
+            })
+            .await;
+        assert!(matches!(response, Response::Killed { pids } if pids == [2]));
+
+        let response = daemon
+            .handle(Request::Kill {
+                selector: "profile:ladder".to_string(),
+            })
+            .await;
+        assert_eq!(error_class(response), ErrorClass::NoMatch);
+
+        let response = daemon
+            .handle(Request::Kill {
+                selector: "pid:".to_string(),
+            })
+            .await;
+        assert_eq!(error_class(response), ErrorClass::Usage);
+        assert_eq!(daemon.backend().games().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn status_and_adopt_report_the_backend() {
+        let daemon = daemon(vec![game(1, None, "default")]);
+        *daemon.backend().running.lock().unwrap() =
+            Ok(vec![game(1, None, "default"), game(7, None, "default")]);
+
+        let response = daemon.handle(Request::Adopt).await;
+        assert!(matches!(response, Response::Adopted { pids } if pids == [7]));
+
+        match daemon.handle(Request::Status).await {
+            Response::Status {
+                instances,
+                guard_running,
+                game_path,
+            } => {
+                assert_eq!(instances.len(), 2);
+                assert!(instances[1].adopted);
+                assert!(guard_running);
+                assert_eq!(game_path, Some(PathBuf::from("StarCraft.exe")));
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+
+        *daemon.backend().running.lock().unwrap() = Err("access denied".to_string());
+        let response = daemon.handle(Request::Adopt).await;
+        assert_eq!(error_class(response), ErrorClass::ProcessQuery);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serve_answers_on_the_socket_until_shut_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let daemon = daemon(vec![game(42, None, "default")]);
+        let served = tokio::spawn({
+            let path = path.clone();
+            async move { daemon.serve_at(&path).await }
+        });
+
+        // The listener binds in the spawned task
+        let stream = loop {
+            match tokio::net::UnixStream::connect(&path).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::task::yield_now().await,
+            }
+        };
+        let mut client = IpcClient::handshake(stream).await.unwrap();
+        assert!(matches!(
+            client.call(&Request::List).await.unwrap(),
+            Response::Instances { instances } if instances.len() == 1
+        ));
+        client.call(&Request::Shutdown).await.unwrap();
+
+        served.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,651 @@
+use std::fmt;
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
+#[cfg(windows)]
+use std::time::Instant;
+
+use serde::Serialize;
+#[cfg(windows)]
+use windows::core::HSTRING;
+#[cfg(windows)]
+use windows::Win32::Foundation::CloseHandle;
+#[cfg(windows)]
+use windows::Win32::System::Threading::{CreateMutexW, GetCurrentProcess};
+
+#[cfg(windows)]
+use super::admin::{debug_privilege, is_admin};
+use super::config::Arch;
+#[cfg(windows)]
+use super::game::{find_process_pids, find_starcraft_pids};
+#[cfg(windows)]
+use super::layout::install_root;
+use super::layout::InstallLayout;
+#[cfg(windows)]
+use super::object_types::MUTANT_TYPE;
+use super::pe::read_pe_info;
+#[cfg(windows)]
+use super::portable;
+#[cfg(windows)]
+use super::reg::{read_install_location, UNINSTALL_KEY_PATHS};
+#[cfg(windows)]
+use super::scan::{object_types, query_object_name, query_process_handles, scan_instance_mutexes};
+use crate::t;
+
+/// Processes Battle.net runs while it is open.
+#[cfg(windows)]
+const BATTLE_NET_PROCESSES: [&str; 2] = ["Battle.net.exe", "Agent.exe"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// The architecture the checks were run for
+    pub arch: Arch,
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    fn push(&mut self, name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    /// The most severe status of all checks.
+    pub fn worst(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Pass)
+    }
+}
+
+/// One line per check with the names aligned, then the totals.
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .checks
+            .iter()
+            .map(|check| check.name.len())
+            .max()
+            .unwrap_or(0);
+        for check in &self.checks {
+            writeln!(
+                f,
+                "[{}] {:<width$}  {}",
+                check.status,
+                check.name,
+                check.detail,
+                width = width
+            )?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "{}",
+            t!(
+                "doctor-summary",
+                pass = self.count(CheckStatus::Pass),
+                warn = self.count(CheckStatus::Warn),
+                fail = self.count(CheckStatus::Fail),
+                arch = self.arch
+            )
+        )
+    }
+}
+
+/// Checks everything the launcher depends on, for pasting into a bug report.
+///
+/// `install` overrides the install root found in the registry (a folder or StarCraft.exe).
+#[cfg(windows)]
+pub fn run_diagnostics(install: Option<&Path>, arch: Arch) -> DoctorReport {
+    let mut report = DoctorReport {
+        arch,
+        checks: Vec::new(),
+    };
+
+    if is_admin() {
+        report.push("admin", CheckStatus::Pass, t!("doctor-admin-ok"));
+    } else {
+        report.push("admin", CheckStatus::Warn, t!("doctor-admin-missing"));
+    }
+
+    match debug_privilege() {
+        Some(true) => report.push(
+            "debug_privilege",
+            CheckStatus::Pass,
+            t!("doctor-debug-enabled"),
+        ),
+        Some(false) => report.push(
+            "debug_privilege",
+            CheckStatus::Pass,
+            t!("doctor-debug-available"),
+        ),
+        None => report.push(
+            "debug_privilege",
+            CheckStatus::Warn,
+            t!("doctor-debug-missing"),
+        ),
+    }
+
+    let mut registry_root = None;
+    // A portable copy does not even read the registry
+    let registry_keys: &[&str] = if portable::enabled() {
+        report.push("portable", CheckStatus::Pass, t!("doctor-portable"));
+        &[]
+    } else {
+        &UNINSTALL_KEY_PATHS
+    };
+    for key in registry_keys {
+        match read_install_location(key) {
+            Ok(location) => {
+                report.push(
+                    format!("registry HKLM\\{}", key),
+                    CheckStatus::Pass,
+                    t!("doctor-registry-ok", location = &location),
+                );
+                registry_root.get_or_insert_with(|| PathBuf::from(location));
+            }
+            Err(e) => report.push(
+                format!("registry HKLM\\{}", key),
+                CheckStatus::Warn,
+                t!("doctor-registry-missing", error = e),
+            ),
+        }
+    }
+
+    let root = install.map(install_root).or(registry_root);
+    check_install(&mut report, root.as_deref(), arch);
+
+    check_processes(&mut report);
+    check_handle_query(&mut report);
+
+    report
+}
+
+/// Adds the checks of the install at `root` and its StarCraft.exe for `arch`.
+pub fn check_install(report: &mut DoctorReport, root: Option<&Path>, arch: Arch) {
+    let Some(root) = root else {
+        report.push("install", CheckStatus::Fail, t!("doctor-install-none"));
+        return;
+    };
+    if !root.is_dir() {
+        report.push(
+            "install",
+            CheckStatus::Fail,
+            t!("doctor-install-missing", path = root.display()),
+        );
+        return;
+    }
+    // Without any StarCraft.exe, report the folders Battle.net would have put it in
+    let layout = InstallLayout::detect(root).unwrap_or(InstallLayout::PerArch);
+    report.push(
+        "install",
+        CheckStatus::Pass,
+        format!("{} ({})", root.display(), layout),
+    );
+
+    if layout.per_arch() {
+        for candidate in [Arch::X86, Arch::X86_64] {
+            let exe = layout.exe(root, candidate);
+            let status = match (exe.is_file(), candidate == arch) {
+                (true, _) => CheckStatus::Pass,
+                (false, true) => CheckStatus::Fail,
+                (false, false) => CheckStatus::Warn,
+            };
+            let detail = if exe.is_file() {
+                exe.display().to_string()
+            } else {
+                t!("doctor-exe-missing", path = exe.display())
+            };
+            report.push(format!("exe {}", candidate), status, detail);
+        }
+    } else {
+        // One StarCraft.exe in the root, whatever it is built for
+        let exe = layout.exe(root, arch);
+        report.push("exe", CheckStatus::Pass, exe.display().to_string());
+    }
+
+    let exe = layout.exe(root, arch);
+    let info = read_pe_info(&exe);
+    match info.as_ref().map(|info| info.machine) {
+        Ok(machine) if machine.arch() == Some(arch) => report.push(
+            "pe_arch",
+            CheckStatus::Pass,
+            t!("doctor-pe-ok", path = exe.display(), machine = machine),
+        ),
+        Ok(machine) => report.push(
+            "pe_arch",
+            CheckStatus::Fail,
+            t!(
+                "doctor-pe-mismatch",
+                path = exe.display(),
+                machine = machine,
+                arch = arch
+            ),
+        ),
+        Err(e) => report.push(
+            "pe_arch",
+            CheckStatus::Fail,
+            t!("doctor-pe-unreadable", path = exe.display(), error = e),
+        ),
+    }
+
+    if let Ok(info) = info {
+        match info.version.and_then(|version| {
+            let number = version.file_version.or(version.product_version)?;
+            Some((version.product_name.unwrap_or_default(), number))
+        }) {
+            Some((product, version)) => report.push(
+                "pe_version",
+                CheckStatus::Pass,
+                t!("doctor-version", product = product, version = version),
+            ),
+            None => report.push(
+                "pe_version",
+                CheckStatus::Warn,
+                t!("doctor-version-missing", path = exe.display()),
+            ),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn check_processes(report: &mut DoctorReport) {
+    let starcraft = match find_starcraft_pids() {
+        Ok(pids) => pids,
+        Err(e) => {
+            report.push(
+                "starcraft_processes",
+                CheckStatus::Fail,
+                t!("process-list-failed", error = e),
+            );
+            return;
+        }
+    };
+    report.push(
+        "starcraft_processes",
+        CheckStatus::Pass,
+        if starcraft.is_empty() {
+            t!("doctor-processes-none")
+        } else {
+            t!(
+                "doctor-processes",
+                count = starcraft.len(),
+                pids = format!("{:?}", starcraft)
+            )
+        },
+    );
+
+    let battle_net: Vec<String> = BATTLE_NET_PROCESSES
+        .iter()
+        .filter_map(|name| {
+            let pids = find_process_pids(name).ok()?;
+            (!pids.is_empty()).then(|| format!("{} {:?}", name, pids))
+        })
+        .collect();
+    report.push(
+        "battle_net",
+        CheckStatus::Pass,
+        if battle_net.is_empty() {
+            t!("doctor-battle-net-none")
+        } else {
+            t!("doctor-battle-net", processes = battle_net.join(", "))
+        },
+    );
+
+    if starcraft.is_empty() {
+        report.push(
+            "instance_mutex",
+            CheckStatus::Warn,
+            t!("doctor-mutex-no-game"),
+        );
+        return;
+    }
+    match scan_instance_mutexes(true) {
+        Ok(scan) => {
+            let found: Vec<u32> = scan
+                .processes
+                .iter()
+                .filter(|process| !process.matches.is_empty())
+                .map(|process| process.pid)
+                .collect();
+            let errors: Vec<String> = scan
+                .processes
+                .iter()
+                .filter_map(|process| {
+                    process
+                        .error
+                        .as_ref()
+                        .map(|error| format!("pid {}: {}", process.pid, error))
+                })
+                .collect();
+            if !errors.is_empty() {
+                report.push("instance_mutex", CheckStatus::Fail, errors.join("; "));
+            } else if found.is_empty() {
+                report.push(
+                    "instance_mutex",
+                    CheckStatus::Warn,
+                    t!("doctor-mutex-not-found"),
+                );
+            } else {
+                report.push(
+                    "instance_mutex",
+                    CheckStatus::Pass,
+                    t!("doctor-mutex-found", pids = format!("{:?}", found)),
+                );
+            }
+        }
+        Err(e) => report.push(
+            "instance_mutex",
+            CheckStatus::Fail,
+            t!("doctor-scan-failed", error = e),
+        ),
+    }
+}
+
+/// Opens a named mutex of our own and checks the handle query path finds it by name.
+#[cfg(windows)]
+fn check_handle_query(report: &mut DoctorReport) {
+    let name = format!("sclauncher-doctor-{}", std::process::id());
+    let started = Instant::now();
+
+    let mutex = match unsafe { CreateMutexW(None, false, &HSTRING::from(name.as_str())) } {
+        Ok(mutex) => mutex,
+        Err(e) => {
+            report.push(
+                "handle_query",
+                CheckStatus::Fail,
+                t!("doctor-mutex-create-failed", error = e),
+            );
+            return;
+        }
+    };
+
+    let process = unsafe { GetCurrentProcess() };
+    let result = match query_process_handles(process) {
+        Ok(handles) => {
+            let entry = handles.iter().find(|entry| entry.HandleValue == mutex);
+            check_object_types(report, entry.map(|entry| entry.ObjectTypeIndex));
+            let found = entry.and_then(|entry| {
+                query_object_name(std::process::id(), process, entry.HandleValue)
+                    .ok()
+                    .flatten()
+            });
+            match found {
+                Some(object_name) if object_name.ends_with(&name) => Ok(handles.len()),
+                Some(object_name) => Err(t!("doctor-handle-renamed", name = object_name)),
+                None => Err(t!("doctor-handle-missing")),
+            }
+        }
+        Err(status) => Err(t!(
+            "doctor-handle-query-failed",
+            status = format!("{:#x}", status.0)
+        )),
+    };
+    unsafe {
+        let _ = CloseHandle(mutex);
+    }
+
+    match result {
+        Ok(count) => report.push(
+            "handle_query",
+            CheckStatus::Pass,
+            t!(
+                "doctor-handle-ok",
+                count = count,
+                ms = started.elapsed().as_millis()
+            ),
+        ),
+        Err(detail) => report.push("handle_query", CheckStatus::Fail, detail),
+    }
+}
+
+/// Checks the type list the scanner filters by: the test mutex must come out as a Mutant.
+#[cfg(windows)]
+fn check_object_types(report: &mut DoctorReport, mutex_type: Option<u32>) {
+    let Some(types) = object_types() else {
+        report.push(
+            "object_types",
+            CheckStatus::Warn,
+            t!("doctor-types-unavailable"),
+        );
+        return;
+    };
+    match mutex_type.map(|index| (index, types.name(index))) {
+        Some((index, Some(name))) if name == MUTANT_TYPE => report.push(
+            "object_types",
+            CheckStatus::Pass,
+            t!("doctor-types-ok", count = types.len(), index = index),
+        ),
+        Some((index, name)) => report.push(
+            "object_types",
+            CheckStatus::Warn,
+            t!(
+                "doctor-types-mismatch",
+                index = index,
+                name = name.unwrap_or("?")
+            ),
+        ),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+    use crate::util::layout::EXE_NAME;
+    use crate::util::testing::{pe_image, version_resources, write_file, AMD64, I386};
+
+    fn report(statuses: &[(&str, CheckStatus)]) -> DoctorReport {
+        let mut report = DoctorReport {
+            arch: Arch::X86_64,
+            checks: Vec::new(),
+        };
+        for (name, status) in statuses {
+            report.push(*name, *status, format!("{} detail", name));
+        }
+        report
+    }
+
+    fn install_report(root: Option<&Path>, arch: Arch) -> DoctorReport {
+        set_language(Lang::En);
+        let mut report = DoctorReport {
+            arch,
+            checks: Vec::new(),
+        };
+        check_install(&mut report, root, arch);
+        report
+    }
+
+    fn statuses(report: &DoctorReport) -> Vec<(&str, CheckStatus)> {
+        report
+            .checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.status))
+            .collect()
+    }
+
+    fn write_versioned_exe(path: &Path, machine: u16) {
+        write_file(path, &pe_image(machine, Some(&version_resources())));
+    }
+
+    #[test]
+    fn worst_and_count() {
+        let empty = report(&[]);
+        assert_eq!(empty.worst(), CheckStatus::Pass);
+        assert_eq!(empty.count(CheckStatus::Pass), 0);
+
+        let mixed = report(&[
+            ("a", CheckStatus::Pass),
+            ("b", CheckStatus::Warn),
+            ("c", CheckStatus::Pass),
+        ]);
+        assert_eq!(mixed.worst(), CheckStatus::Warn);
+        assert_eq!(mixed.count(CheckStatus::Pass), 2);
+        assert_eq!(mixed.count(CheckStatus::Fail), 0);
+
+        let failed = report(&[("a", CheckStatus::Fail), ("b", CheckStatus::Warn)]);
+        assert_eq!(failed.worst(), CheckStatus::Fail);
+    }
+
+    #[test]
+    fn text_lines_up_the_names_and_ends_with_the_totals() {
+        set_language(Lang::En);
+        let report = report(&[
+            ("admin", CheckStatus::Pass),
+            ("instance_mutex", CheckStatus::Warn),
+            ("exe", CheckStatus::Fail),
+        ]);
+        assert_eq!(
+            report.to_string(),
+            "[PASS] admin           admin detail\n\
+             [WARN] instance_mutex  instance_mutex detail\n\
+             [FAIL] exe             exe detail\n\
+             \n\
+             1 passed, 1 warnings, 1 failed (checked for x86_64)."
+        );
+    }
+
+    #[test]
+    fn json_snapshot() {
+        let report = report(&[("admin", CheckStatus::Pass), ("exe", CheckStatus::Fail)]);
+        assert_eq!(
+            serde_json::to_string_pretty(&report).unwrap(),
+            r#"{
+  "arch": "x86_64",
+  "checks": [
+    {
+      "name": "admin",
+      "status": "pass",
+      "detail": "admin detail"
+    },
+    {
+      "name": "exe",
+      "status": "fail",
+      "detail": "exe detail"
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn a_missing_install_fails() {
+        let report = install_report(None, Arch::X86);
+        assert_eq!(statuses(&report), [("install", CheckStatus::Fail)]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let report = install_report(Some(&dir.path().join("gone")), Arch::X86);
+        assert_eq!(statuses(&report), [("install", CheckStatus::Fail)]);
+        assert!(report.checks[0].detail.ends_with("does not exist."));
+    }
+
+    #[test]
+    fn a_complete_remastered_install_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        write_versioned_exe(&dir.path().join("x86").join(EXE_NAME), I386);
+        write_versioned_exe(&dir.path().join("x86_64").join(EXE_NAME), AMD64);
+
+        let report = install_report(Some(dir.path()), Arch::X86_64);
+        assert_eq!(
+            statuses(&report),
+            [
+                ("install", CheckStatus::Pass),
+                ("exe x86", CheckStatus::Pass),
+                ("exe x86_64", CheckStatus::Pass),
+                ("pe_arch", CheckStatus::Pass),
+                ("pe_version", CheckStatus::Pass),
+            ]
+        );
+        assert!(report.checks[0].detail.ends_with("(per-arch)"));
+        assert_eq!(report.checks[4].detail, "StarCraft 1.23.10.13515");
+    }
+
+    #[test]
+    fn the_other_architecture_missing_only_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(
+            &dir.path().join("x86").join(EXE_NAME),
+            &pe_image(I386, None),
+        );
+
+        let report = install_report(Some(dir.path()), Arch::X86);
+        assert_eq!(
+            statuses(&report),
+            [
+                ("install", CheckStatus::Pass),
+                ("exe x86", CheckStatus::Pass),
+                ("exe x86_64", CheckStatus::Warn),
+                ("pe_arch", CheckStatus::Pass),
+                // No version resource
+                ("pe_version", CheckStatus::Warn),
+            ]
+        );
+
+        // The requested one missing fails, and so does reading it
+        let report = install_report(Some(dir.path()), Arch::X86_64);
+        assert_eq!(
+            statuses(&report),
+            [
+                ("install", CheckStatus::Pass),
+                ("exe x86", CheckStatus::Pass),
+                ("exe x86_64", CheckStatus::Fail),
+                ("pe_arch", CheckStatus::Fail),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_binary_built_for_the_other_architecture_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        write_versioned_exe(&dir.path().join(EXE_NAME), I386);
+
+        let report = install_report(Some(dir.path()), Arch::X86_64);
+        assert_eq!(
+            statuses(&report),
+            [
+                ("install", CheckStatus::Pass),
+                ("exe", CheckStatus::Pass),
+                ("pe_arch", CheckStatus::Fail),
+                ("pe_version", CheckStatus::Pass),
+            ]
+        );
+        assert!(report.checks[2].detail.contains("x86_64 was requested"));
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::t;
+
+/// Lifecycle events published by `GameManager`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    Launched {
+        pid: u32,
+        label: Option<String>,
+        profile: String,
+    },
+    LaunchFailed {
+        reason: String,
+    },
+    MutexClosed {
+        pid: u32,
+    },
+    /// A handle of `pid` whose name query did not return in time; the scans skip it
+    HandleHung {
+        pid: u32,
+        handle: usize,
+    },
+    /// The game runs, but a second copy cannot start until its instance mutex is closed
+    MutexCloseFailed {
+        pid: u32,
+        reason: String,
+    },
+    /// The game runs, but on every processor instead of the requested ones
+    AffinityFailed {
+        pid: u32,
+        reason: String,
+    },
+    Adopted {
+        pid: u32,
+    },
+    Killed {
+        pid: u32,
+    },
+    Relabeled {
+        pid: u32,
+        label: Option<String>,
+    },
+    /// The background mutex guard stopped; launches close the mutex themselves again
+    GuardFailed {
+        reason: String,
+    },
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEvent::Launched { pid, label, .. } => match label {
+                Some(label) => write!(
+                    f,
+                    " + {}",
+                    t!("event-launched-label", pid = pid, label = label)
+                ),
+                None => write!(f, " + {}", t!("event-launched", pid = pid)),
+            },
+            GameEvent::LaunchFailed { reason } => {
+                f.write_str(&t!("event-launch-failed", reason = reason))
+            }
+            GameEvent::MutexClosed { pid } => {
+                write!(f, "\t - {}", t!("event-mutex-closed", pid = pid))
+            }
+            GameEvent::HandleHung { pid, handle } => write!(
+                f,
+                "\t ! {}",
+                t!(
+                    "event-handle-hung",
+                    pid = pid,
+                    handle = format!("{:#06x}", handle)
+                )
+            ),
+            GameEvent::MutexCloseFailed { pid, reason } => write!(
+                f,
+                "\t ! {}",
+                t!("event-mutex-close-failed", pid = pid, reason = reason)
+            ),
+            GameEvent::AffinityFailed { pid, reason } => write!(
+                f,
+                "\t ! {}",
+                t!("event-affinity-failed", pid = pid, reason = reason)
+            ),
+            GameEvent::Adopted { pid } => f.write_str(&t!("event-adopted", pid = pid)),
+            GameEvent::Killed { pid } => f.write_str(&t!("event-killed", pid = pid)),
+            GameEvent::Relabeled { pid, label } => match label {
+                Some(label) => f.write_str(&t!("event-relabeled", pid = pid, label = label)),
+                None => f.write_str(&t!("event-unlabeled", pid = pid)),
+            },
+            GameEvent::GuardFailed { reason } => {
+                write!(f, "\t ! {}", t!("guard-failed", error = reason))
+            }
+        }
+    }
+}
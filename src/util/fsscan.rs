@@ -0,0 +1,410 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use super::layout::InstallLayout;
+use super::pe::read_machine;
+use super::portable;
+
+/// Folders a drive walk never enters, compared without case.
+pub const DEFAULT_IGNORES: [&str; 12] = [
+    "$Recycle.Bin",
+    "$WinREAgent",
+    "System Volume Information",
+    "Windows",
+    "WinSxS",
+    "ProgramData",
+    "AppData",
+    "Recovery",
+    "PerfLogs",
+    "node_modules",
+    ".git",
+    "target",
+];
+
+/// Folder names StarCraft is installed under, below a drive or a library folder.
+const INSTALL_FOLDERS: [&str; 3] = ["StarCraft", "StarCraft PTR", "Starcraft 1.16.1"];
+
+/// Parents of [`INSTALL_FOLDERS`] on every drive, as path components.
+const LIBRARY_FOLDERS: [&[&str]; 7] = [
+    &[],
+    &["Games"],
+    &["Program Files"],
+    &["Program Files (x86)"],
+    &["Battle.net"],
+    &["Blizzard"],
+    &["Games", "Battle.net"],
+];
+
+/// Whether `folder` holds a StarCraft.exe the PE reader accepts, in any layout.
+pub fn is_starcraft_folder(folder: &Path) -> bool {
+    let Some(layout) = InstallLayout::detect(folder) else {
+        return false;
+    };
+    layout.exes(folder).iter().any(|exe| {
+        exe.is_file()
+            && read_machine(exe)
+                .map(|machine| machine.arch().is_some())
+                .unwrap_or(false)
+    })
+}
+
+/// Install folders worth checking without walking anything: the usual StarCraft folders
+/// under the drive roots, Program Files and the common game library folders.
+pub fn well_known_locations(drives: &[PathBuf]) -> Vec<PathBuf> {
+    let mut parents: Vec<PathBuf> = ["ProgramFiles(x86)", "ProgramFiles", "ProgramW6432"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .collect();
+    for drive in drives {
+        parents.extend(LIBRARY_FOLDERS.iter().map(|library| {
+            library
+                .iter()
+                .fold(drive.clone(), |path, part| path.join(part))
+        }));
+    }
+
+    let mut seen = BTreeSet::new();
+    parents
+        .iter()
+        .flat_map(|parent| INSTALL_FOLDERS.iter().map(move |name| parent.join(name)))
+        .filter(|folder| seen.insert(folder.to_string_lossy().to_lowercase()))
+        .collect()
+}
+
+/// The roots of the fixed drives, e.g. `C:\` and `D:\`.
+#[cfg(windows)]
+pub fn fixed_drives() -> Vec<PathBuf> {
+    use windows::core::HSTRING;
+    use windows::Win32::Storage::FileSystem::{GetDriveTypeW, GetLogicalDrives};
+
+    // DRIVE_FIXED from winbase.h
+    const DRIVE_FIXED: u32 = 3;
+
+    let mask = unsafe { GetLogicalDrives() };
+    (0..26u8)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .map(|bit| PathBuf::from(format!("{}:\\", (b'A' + bit) as char)))
+        .filter(|root| {
+            let root = HSTRING::from(root.as_os_str());
+            unsafe { GetDriveTypeW(&root) == DRIVE_FIXED }
+        })
+        .collect()
+}
+
+/// The filesystem root, as there are no drive letters.
+#[cfg(not(windows))]
+pub fn fixed_drives() -> Vec<PathBuf> {
+    vec![PathBuf::from("/")]
+}
+
+/// A StarCraft folder found by a [`FsScan`].
+#[derive(Debug, Clone)]
+pub struct FoundFolder {
+    pub folder: PathBuf,
+    /// The root whose walk found it
+    pub root: PathBuf,
+}
+
+/// What a [`FsScan`] found.
+#[derive(Debug, Clone, Default)]
+pub struct FsScanResult {
+    pub found: Vec<FoundFolder>,
+    /// Folders looked into
+    pub visited: usize,
+    /// Whether the time budget ran out before every root was walked
+    pub timed_out: bool,
+}
+
+/// A walk over folder trees for StarCraft installs, one thread per root.
+///
+/// Symlinks and junctions are not followed, and a StarCraft folder is not walked into.
+#[derive(Debug, Clone)]
+pub struct FsScan {
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    ignore: Vec<String>,
+    budget: Duration,
+    cancel: CancellationToken,
+}
+
+impl Default for FsScan {
+    fn default() -> Self {
+        FsScan {
+            roots: Vec::new(),
+            // Deep enough for <drive>\Users\<name>\Games\StarCraft
+            max_depth: 5,
+            ignore: DEFAULT_IGNORES
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect(),
+            budget: Duration::from_secs(20),
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+impl FsScan {
+    pub fn new() -> Self {
+        FsScan::default()
+    }
+
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.roots.push(root.into());
+        self
+    }
+
+    pub fn roots(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.roots.extend(roots);
+        self
+    }
+
+    /// Descends at most this many folders below a root; 1 only looks at its subfolders.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Skips folders with this name, in addition to [`DEFAULT_IGNORES`].
+    pub fn ignore(mut self, name: &str) -> Self {
+        self.ignore.push(name.to_lowercase());
+        self
+    }
+
+    /// Gives up after this long, returning what was found so far.
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Walks every root in parallel and waits for all of them. This blocks.
+    pub fn run(&self) -> FsScanResult {
+        let deadline = Instant::now() + self.budget;
+        let results: Vec<FsScanResult> = thread::scope(|scope| {
+            let walks: Vec<_> = self
+                .roots
+                .iter()
+                .map(|root| scope.spawn(move || self.walk(root, deadline)))
+                .collect();
+            walks
+                .into_iter()
+                .filter_map(|walk| walk.join().ok())
+                .collect()
+        });
+
+        let mut total = FsScanResult::default();
+        for result in results {
+            total.found.extend(result.found);
+            total.visited += result.visited;
+            total.timed_out |= result.timed_out;
+        }
+        total
+    }
+
+    fn walk(&self, root: &Path, deadline: Instant) -> FsScanResult {
+        let mut result = FsScanResult::default();
+        let mut stack = vec![(root.to_path_buf(), 0)];
+
+        while let Some((folder, depth)) = stack.pop() {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            if Instant::now() >= deadline {
+                result.timed_out = true;
+                break;
+            }
+            result.visited += 1;
+            if is_starcraft_folder(&folder) {
+                result.found.push(FoundFolder {
+                    folder,
+                    root: root.to_path_buf(),
+                });
+                continue;
+            }
+            if depth >= self.max_depth {
+                continue;
+            }
+
+            let Ok(entries) = fs::read_dir(&folder) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                // file_type does not follow links, so junctions and symlinks are skipped
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if !file_type.is_dir() || file_type.is_symlink() {
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                if self.ignore.contains(&name) {
+                    continue;
+                }
+                stack.push((entry.path(), depth + 1));
+            }
+        }
+        result
+    }
+}
+
+/// Install folders found by earlier scans, so a drive walk only has to run once.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    /// Seconds since the Unix epoch of the last write
+    pub updated: u64,
+    pub folders: Vec<PathBuf>,
+}
+
+impl ScanCache {
+    /// Reads the cache, empty when it does not exist or cannot be read.
+    pub fn load(path: &Path) -> ScanCache {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Adds `folders` to the cache, keeping those that still hold StarCraft, and writes it.
+    pub fn update(path: &Path, folders: impl IntoIterator<Item = PathBuf>) -> std::io::Result<()> {
+        let mut cache = ScanCache::load(path);
+        cache.folders.retain(|folder| is_starcraft_folder(folder));
+        for folder in folders {
+            if !cache.folders.contains(&folder) {
+                cache.folders.push(folder);
+            }
+        }
+        cache.updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&cache)?)
+    }
+}
+
+/// `%LOCALAPPDATA%\sclauncher\installs.json` on Windows, `$XDG_CACHE_HOME/sclauncher/installs.json` elsewhere.
+/// A portable copy keeps it next to the executable.
+pub fn cache_path() -> Option<PathBuf> {
+    if let Some(dir) = portable::dir() {
+        return Some(dir.join(portable::CACHE_FILE));
+    }
+    #[cfg(windows)]
+    let dir = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")));
+
+    dir.map(|dir| dir.join("sclauncher").join("installs.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::util::testing::{write_exe, write_file, AMD64, I386};
+
+    fn found(scan: &FsScan) -> BTreeSet<PathBuf> {
+        scan.run()
+            .found
+            .into_iter()
+            .map(|found| found.folder)
+            .collect()
+    }
+
+    #[test]
+    fn installs_are_found_in_every_layout() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let remastered = root.join("Games").join("StarCraft");
+        write_exe(&remastered.join("x86_64").join("StarCraft.exe"), AMD64);
+        let legacy = root.join("Starcraft 1.16.1");
+        write_exe(&legacy.join("StarCraft.exe"), I386);
+        write_file(&legacy.join("storm.dll"), b"");
+        let flat = root.join("a").join("b").join("repack");
+        write_exe(&flat.join("StarCraft.exe"), I386);
+        // Not real executables, or nested inside an install already found
+        write_file(&root.join("fake").join("StarCraft.exe"), b"not a PE file");
+        write_exe(
+            &remastered.join("Maps").join("copy").join("StarCraft.exe"),
+            I386,
+        );
+
+        let scan = FsScan::new().root(root);
+        assert_eq!(found(&scan), BTreeSet::from([remastered, legacy, flat]));
+    }
+
+    #[test]
+    fn ignored_and_deep_folders_are_skipped() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write_exe(&root.join("Windows").join("StarCraft.exe"), I386);
+        write_exe(&root.join("Backup").join("StarCraft.exe"), I386);
+        let deep = root.join("1").join("2").join("3");
+        write_exe(&deep.join("StarCraft.exe"), I386);
+
+        let scan = FsScan::new().root(root).ignore("backup");
+        assert_eq!(found(&scan), BTreeSet::from([deep]));
+        assert!(found(&scan.max_depth(2)).is_empty());
+    }
+
+    #[test]
+    fn cancelled_and_timed_out_scans_stop() {
+        let dir = tempdir().unwrap();
+        write_exe(&dir.path().join("StarCraft").join("StarCraft.exe"), I386);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = FsScan::new().root(dir.path()).cancel_token(cancel).run();
+        assert!(result.found.is_empty());
+        assert_eq!(result.visited, 0);
+
+        let result = FsScan::new()
+            .root(dir.path())
+            .time_budget(Duration::ZERO)
+            .run();
+        assert!(result.found.is_empty());
+        assert!(result.timed_out);
+    }
+
+    #[test]
+    fn the_cache_keeps_only_folders_that_still_hold_starcraft() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let path = root.join("cache").join("installs.json");
+        assert!(ScanCache::load(&path).folders.is_empty());
+
+        let kept = root.join("kept");
+        write_exe(&kept.join("StarCraft.exe"), I386);
+        let removed = root.join("removed");
+        write_exe(&removed.join("StarCraft.exe"), I386);
+        ScanCache::update(&path, [kept.clone(), removed.clone(), kept.clone()]).unwrap();
+        let cache = ScanCache::load(&path);
+        assert_eq!(cache.folders, [kept.clone(), removed.clone()]);
+        assert!(cache.updated > 0);
+
+        fs::remove_dir_all(&removed).unwrap();
+        let added = root.join("added");
+        write_exe(&added.join("StarCraft.exe"), AMD64);
+        ScanCache::update(&path, [added.clone()]).unwrap();
+        assert_eq!(ScanCache::load(&path).folders, [kept, added]);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(ScanCache::load(&path).folders.is_empty());
+    }
+}
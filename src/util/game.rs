@@ -1,106 +1,438 @@
 #![allow(non_camel_case_types)]
 
-use std::ffi::{c_void, OsStr, OsString};
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, OsString};
 use std::mem::{self};
 use std::os::windows::ffi::{OsStrExt, OsStringExt};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex};
 
 use tokio::task;
 use tokio::time::sleep;
 
 use windows::{
     core::{Result, PWSTR},
-    Wdk::{
-        Foundation::{NtQueryObject, OBJECT_INFORMATION_CLASS, OBJECT_NAME_INFORMATION},
-        System::Threading::ProcessHandleInformation,
-    },
     Win32::{
-        Foundation::{
-            CloseHandle, DuplicateHandle, BOOL, DUPLICATE_CLOSE_SOURCE, DUPLICATE_SAME_ACCESS,
-            HANDLE, INVALID_HANDLE_VALUE, NTSTATUS, STATUS_INFO_LENGTH_MISMATCH,
-            STATUS_PIPE_DISCONNECTED, STATUS_PROCESS_IS_TERMINATING,
-        },
+        Foundation::{CloseHandle, BOOL, FILETIME, HANDLE},
         System::Diagnostics::ToolHelp::{
             CreateToolhelp32Snapshot, Process32First, Process32Next,
             CREATE_TOOLHELP_SNAPSHOT_FLAGS, PROCESSENTRY32, TH32CS_SNAPPROCESS,
         },
-        System::SystemServices::MAXIMUM_ALLOWED,
         System::Threading::{
-            CreateProcessW, GetCurrentProcess, OpenProcess, TerminateProcess, CREATE_NEW_CONSOLE,
-            CREATE_NO_WINDOW, PROCESS_ALL_ACCESS, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION,
+            CreateProcessW, GetProcessTimes, OpenProcess, QueryFullProcessImageNameW,
+            SetProcessAffinityMask, TerminateProcess, CREATE_NEW_CONSOLE, CREATE_NO_WINDOW,
+            PROCESS_ALL_ACCESS, PROCESS_CREATION_FLAGS, PROCESS_INFORMATION, PROCESS_NAME_WIN32,
             STARTUPINFOW,
         },
     },
 };
 
-use super::custom_windows::{self, PROCESS_HANDLE_SNAPSHOT_INFORMATION};
+use super::custom_windows;
+use super::events::GameEvent;
+use super::instance::{GameInstance, LaunchOptions, MutexScope, DEFAULT_PROFILE};
+use super::scan::{scan_instance_mutexes, scan_process, HandleAction, ScannedProcess};
+use super::selector::Selector;
+use super::window::focus_process_window;
+use crate::t;
 
-/// see https://www.geoffchappell.com/studies/windows/km/ntoskrnl/api/ex/sysinfo/handle.htm
-const OBJECT_NAME_INFORMATION: OBJECT_INFORMATION_CLASS = OBJECT_INFORMATION_CLASS(0x1);
+/// How long a launch waits for its game to create the instance mutex.
+pub const MUTEX_WAIT: Duration = Duration::from_secs(30);
 
 pub struct GameManager {
-    children: Arc<Mutex<Vec<(u32, HANDLE)>>>, // Store PID alongside HANDLE
+    children: Arc<Mutex<Vec<GameInstance>>>, // kept in launch order
+    events: broadcast::Sender<GameEvent>,
+    guard: std::sync::Mutex<Option<task::JoinHandle<()>>>, // continuous `modify_processes` task
+}
+
+impl Default for GameManager {
+    fn default() -> Self {
+        GameManager::new()
+    }
 }
 
 impl GameManager {
     pub fn new() -> Self {
+        let (events, _) = broadcast::channel(256);
         GameManager {
             children: Arc::new(Mutex::new(Vec::new())),
+            events,
+            guard: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Starts closing the instance mutex of every StarCraft.exe in the background.
+    ///
+    /// While the guard runs, launches skip their own one-shot scan. Returns `false` if it was already running.
+    pub fn start_guard(&self) -> bool {
+        let mut guard = self.guard.lock().unwrap();
+        if guard.as_ref().is_some_and(|task| !task.is_finished()) {
+            return false;
+        }
+        let events = self.events.clone();
+        *guard = Some(tokio::spawn(async move {
+            if let Err(e) = modify_processes(&events).await {
+                // No subscribers is fine
+                let _ = events.send(GameEvent::GuardFailed {
+                    reason: e.to_string(),
+                });
+            }
+        }));
+        true
+    }
+
+    /// Stops the background guard. Returns `false` if it was not running.
+    pub fn stop_guard(&self) -> bool {
+        match self.guard.lock().unwrap().take() {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
         }
     }
 
-    // Modify `launch_game` and other related methods to handle `HANDLE` directly
+    pub fn guard_running(&self) -> bool {
+        self.guard
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    /// Receives every `GameEvent` published after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: GameEvent) {
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+
     pub async fn launch_game(&self, game_path: PathBuf) -> bool {
+        self.launch_game_with(game_path, LaunchOptions::default())
+            .await
+            .is_some()
+    }
+
+    /// Launches a game and tracks it with the given label and profile, returning its pid.
+    pub async fn launch_game_with(
+        &self,
+        game_path: PathBuf,
+        options: LaunchOptions,
+    ) -> Option<u32> {
+        let exe_path = game_path.clone();
+        let spawn_options = options.clone();
         let game_launch = task::spawn_blocking(move || {
-            let args = vec!["-launch"];
-            let pi = spawn_console_process(game_path.to_str().unwrap_or_default(), args);
-            println!(" + Launched game with pid: {}", pi.dwProcessId);
-            pi
+            spawn_console_process(
+                exe_path.to_str().unwrap_or_default(),
+                spawn_options.command_args(),
+            )
         });
 
         match game_launch.await {
-            Ok(pi) if pi.hProcess.0 != 0 => {
+            Ok(Ok(pi)) if pi.hProcess.0 != 0 => {
+                let affinity_error = options
+                    .affinity
+                    .and_then(|mask| unsafe { SetProcessAffinityMask(pi.hProcess, mask) }.err());
+
                 let mut children = self.children.lock().await;
-                children.push((pi.dwProcessId, HANDLE(pi.hProcess.0)));
+                children.push(GameInstance {
+                    pid: pi.dwProcessId,
+                    handle: HANDLE(pi.hProcess.0),
+                    label: options.label.clone(),
+                    profile: options.profile.clone(),
+                    adopted: false,
+                    game_path: Some(game_path),
+                });
+                drop(children);
+                self.emit(GameEvent::Launched {
+                    pid: pi.dwProcessId,
+                    label: options.label,
+                    profile: options.profile,
+                });
+                if let Some(e) = affinity_error {
+                    self.emit(GameEvent::AffinityFailed {
+                        pid: pi.dwProcessId,
+                        reason: e.to_string(),
+                    });
+                }
 
-                unsafe { modify_processes_once() }
-                    .await
-                    .expect("should close");
+                // The guard closes the mutex by itself, and would race a one-shot scan forever
+                if !self.guard_running() {
+                    let closed = match options.mutex_scope {
+                        MutexScope::Launched => {
+                            close_launched_mutex(pi.dwProcessId, &self.events).await
+                        }
+                        MutexScope::System => modify_processes_once(&self.events).await,
+                    };
+                    match closed {
+                        Ok(pid) => self.emit(GameEvent::MutexClosed { pid }),
+                        Err(reason) => self.emit(GameEvent::MutexCloseFailed {
+                            pid: pi.dwProcessId,
+                            reason,
+                        }),
+                    }
+                }
 
-                true
+                Some(pi.dwProcessId)
             }
-            _ => {
-                eprintln!("Failed to launch game in background thread");
-                false
+            Ok(Ok(_)) => {
+                self.emit(GameEvent::LaunchFailed {
+                    reason: t!("launch-no-handle"),
+                });
+                None
+            }
+            Ok(Err(e)) => {
+                self.emit(GameEvent::LaunchFailed {
+                    reason: t!("launch-create-failed", error = e),
+                });
+                None
+            }
+            Err(e) => {
+                self.emit(GameEvent::LaunchFailed {
+                    reason: t!("background-thread-failed", error = e),
+                });
+                None
             }
         }
     }
 
-    pub async fn kill_a_game(&self, target_pid: u32) {
+    /// Starts tracking StarCraft processes that are running but were not launched by us.
+    pub async fn adopt_running_games(&self) -> Result<Vec<u32>> {
+        let pids = find_starcraft_pids()?;
         let mut children = self.children.lock().await;
-        if let Some(index) = children.iter().position(|(pid, _)| *pid == target_pid) {
-            let (_, handle) = children[index];
-            unsafe {
-                let _ = TerminateProcess(handle, 0);
+        let mut adopted = Vec::new();
+
+        for pid in pids {
+            if children.iter().any(|child| child.pid == pid) {
+                continue;
             }
-            println!("Killing game with pid: {}", target_pid);
-            children.remove(index);
+            let handle = match unsafe { OpenProcess(PROCESS_ALL_ACCESS, false, pid) } {
+                Ok(handle) => handle,
+                Err(_) => continue,
+            };
+            children.push(GameInstance {
+                pid,
+                handle,
+                label: None,
+                profile: DEFAULT_PROFILE.to_string(),
+                adopted: true,
+                game_path: process_image_path(handle),
+            });
+            self.emit(GameEvent::Adopted { pid });
+            adopted.push(pid);
+        }
+
+        // Keep launch order meaningful for adopted games (oldest/newest selectors)
+        children.sort_by_key(|child| process_creation_time(child.handle));
+
+        Ok(adopted)
+    }
+
+    /// Returns a copy of the tracked instances in launch order.
+    pub async fn instances(&self) -> Vec<GameInstance> {
+        self.children.lock().await.clone()
+    }
+
+    /// Returns the tracked instances matching `selector`.
+    pub async fn select(&self, selector: &Selector) -> Vec<GameInstance> {
+        let children = self.children.lock().await;
+        selector
+            .matches(&children)
+            .into_iter()
+            .map(|index| children[index].clone())
+            .collect()
+    }
+
+    /// Changes the label of a tracked instance; `None` removes it. Returns `false` if the pid is not tracked.
+    pub async fn relabel(&self, target_pid: u32, label: Option<String>) -> bool {
+        let mut children = self.children.lock().await;
+        let Some(child) = children.iter_mut().find(|child| child.pid == target_pid) else {
+            return false;
+        };
+        child.label = label.clone();
+        drop(children);
+        self.emit(GameEvent::Relabeled {
+            pid: target_pid,
+            label,
+        });
+        true
+    }
+
+    pub async fn kill_a_game(&self, target_pid: u32) {
+        let mut children = self.children.lock().await;
+        if let Some(index) = children.iter().position(|child| child.pid == target_pid) {
+            let child = children.remove(index);
+            self.emit(GameEvent::Killed { pid: target_pid });
+            terminate(&child);
         }
     }
 
+    /// Kills every instance matching `selector` and returns their pids.
+    pub async fn kill_selected(&self, selector: &Selector) -> Vec<u32> {
+        let mut children = self.children.lock().await;
+        let mut indices = selector.matches(&children);
+        indices.sort_unstable();
+
+        let mut killed = Vec::new();
+        // Remove from the back so the remaining indices stay valid
+        for index in indices.into_iter().rev() {
+            let child = children.remove(index);
+            self.emit(GameEvent::Killed { pid: child.pid });
+            terminate(&child);
+            killed.push(child.pid);
+        }
+        killed.reverse();
+        killed
+    }
+
     pub async fn kill_all_games(&self) {
         let mut children = self.children.lock().await;
-        for (pid, handle) in children.iter() {
-            println!("Killing game with pid: {}", pid);
-            unsafe {
-                let _ = TerminateProcess(*handle, 0);
-            }
+        for child in children.iter() {
+            self.emit(GameEvent::Killed { pid: child.pid });
+            terminate(child);
         }
         children.clear(); // Clear all children after killing them
     }
+
+    /// Suspends every thread of the matching instances and returns their pids.
+    pub async fn suspend_selected(&self, selector: &Selector) -> Vec<u32> {
+        self.select(selector)
+            .await
+            .into_iter()
+            .filter(|child| unsafe { custom_windows::NtSuspendProcess(child.handle) }.is_ok())
+            .map(|child| child.pid)
+            .collect()
+    }
+
+    /// Resumes the matching instances and returns their pids.
+    pub async fn resume_selected(&self, selector: &Selector) -> Vec<u32> {
+        self.select(selector)
+            .await
+            .into_iter()
+            .filter(|child| unsafe { custom_windows::NtResumeProcess(child.handle) }.is_ok())
+            .map(|child| child.pid)
+            .collect()
+    }
+
+    /// Brings the window of the first matching instance to the foreground.
+    pub async fn focus_selected(&self, selector: &Selector) -> Option<u32> {
+        self.select(selector)
+            .await
+            .into_iter()
+            .find(|child| focus_process_window(child.pid))
+            .map(|child| child.pid)
+    }
+
+    /// Kills the matching instances and launches them again with the same label and profile.
+    ///
+    /// Returns `(old pid, new pid)` pairs for the instances that came back up.
+    pub async fn relaunch_selected(&self, selector: &Selector) -> Vec<(u32, u32)> {
+        let targets = self.select(selector).await;
+        let mut relaunched = Vec::new();
+
+        for target in targets {
+            let Some(game_path) = target.game_path.clone() else {
+                self.emit(GameEvent::LaunchFailed {
+                    reason: t!("relaunch-no-path", pid = target.pid),
+                });
+                continue;
+            };
+            self.kill_a_game(target.pid).await;
+
+            let options = LaunchOptions {
+                label: target.label.clone(),
+                profile: target.profile.clone(),
+                ..LaunchOptions::default()
+            };
+            if let Some(new_pid) = self.launch_game_with(game_path, options).await {
+                relaunched.push((target.pid, new_pid));
+            }
+        }
+
+        relaunched
+    }
+}
+
+fn terminate(child: &GameInstance) {
+    unsafe {
+        let _ = TerminateProcess(child.handle, 0);
+        let _ = CloseHandle(child.handle);
+    }
+}
+
+/// Returns the full executable path of a process.
+fn process_image_path(process_handle: HANDLE) -> Option<PathBuf> {
+    let mut buffer = vec![0u16; 1024];
+    let mut size = buffer.len() as u32;
+    unsafe {
+        QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        )
+        .ok()?;
+    }
+    Some(PathBuf::from(OsString::from_wide(&buffer[..size as usize])))
+}
+
+/// Returns when the process was created, as a FILETIME tick count.
+fn process_creation_time(process_handle: HANDLE) -> u64 {
+    let mut creation = FILETIME::default();
+    let mut exit = FILETIME::default();
+    let mut kernel = FILETIME::default();
+    let mut user = FILETIME::default();
+    unsafe {
+        if GetProcessTimes(
+            process_handle,
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+        .is_err()
+        {
+            return 0;
+        }
+    }
+    ((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64
+}
+
+/// Lists the pids of every running StarCraft.exe.
+pub fn find_starcraft_pids() -> Result<Vec<u32>> {
+    find_process_pids("StarCraft.exe")
+}
+
+/// Lists the pids of every running process whose executable is named `exe_name` (any case).
+pub fn find_process_pids(exe_name: &str) -> Result<Vec<u32>> {
+    let h_snapshot = HandleWrapper::new(TH32CS_SNAPPROCESS)?;
+    let mut entry = PROCESSENTRY32 {
+        dwSize: std::mem::size_of::<PROCESSENTRY32>() as u32,
+        ..Default::default()
+    };
+
+    let mut pids = Vec::new();
+    unsafe {
+        if Process32First(h_snapshot.get_handle(), &mut entry).is_ok() {
+            loop {
+                if std::ffi::CStr::from_ptr(entry.szExeFile.as_ptr())
+                    .to_bytes()
+                    .eq_ignore_ascii_case(exe_name.as_bytes())
+                {
+                    pids.push(entry.th32ProcessID);
+                }
+                if Process32Next(h_snapshot.get_handle(), &mut entry).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(pids)
 }
 
 struct HandleWrapper(HANDLE);
@@ -125,392 +457,159 @@ impl Drop for HandleWrapper {
     }
 }
 
-/// check and close
-pub async unsafe fn modify_processes_once() -> Result<()> {
-    let starcraft_name = b"StarCraft.exe\0"; // ANSI string with null termination
-    let mut is_handle_found = false;
-
-    while !is_handle_found {
-        let h_snapshot = HandleWrapper::new(TH32CS_SNAPPROCESS)?;
-        // Check if handle is invalid
-        if h_snapshot.get_handle() == INVALID_HANDLE_VALUE {
-            return Err(
-                std::io::Error::new(std::io::ErrorKind::Other, "Invalid handle value").into(),
-            );
+/// Waits for the game `pid` to create its instance mutex and closes it, returning `pid`.
+///
+/// Fails when the game exits, the handle cannot be closed or no mutex shows up within
+/// [`MUTEX_WAIT`]. Handles whose name query hangs are published on `events`.
+pub async fn close_launched_mutex(
+    pid: u32,
+    events: &broadcast::Sender<GameEvent>,
+) -> std::result::Result<u32, String> {
+    let deadline = Instant::now() + MUTEX_WAIT;
+    let mut reported = BTreeSet::new();
+    loop {
+        let scanned = task::spawn_blocking(move || scan_process(pid, false))
+            .await
+            .map_err(|e| t!("background-thread-failed", error = e))?;
+        report_hung(events, &scanned, &mut reported);
+        if let Some(error) = scanned.error {
+            return Err(error);
         }
-        let mut entry = PROCESSENTRY32::default();
-        entry.dwSize = std::mem::size_of::<PROCESSENTRY32>() as u32;
-
-        if Process32First(h_snapshot.get_handle(), &mut entry as *mut PROCESSENTRY32).is_ok() {
-            'outer: loop {
-                // Check for StarCraft.exe
-                if unsafe {
-                    std::ffi::CStr::from_ptr(entry.szExeFile.as_ptr()).to_bytes_with_nul()
-                        == starcraft_name
-                } {
-                    let process_handle: HANDLE =
-                        unsafe { OpenProcess(PROCESS_ALL_ACCESS, false, entry.th32ProcessID)? };
-
-                    if process_handle == INVALID_HANDLE_VALUE {
-                        println!("Failed to open process with ID: {}", entry.th32ProcessID);
-                        continue; // skip to the next process
-                    }
-
-                    let mut buffer: Vec<u8> = Vec::new();
-                    let mut dw_length: u32 = 0;
-
-                    let mut status: NTSTATUS = custom_windows::ZwQueryInformationProcess(
-                        process_handle,
-                        ProcessHandleInformation,
-                        std::ptr::null_mut(), // initially pass a null pointer
-                        0,                    // and a length of 0
-                        &mut dw_length,
-                    );
-
-                    while status == STATUS_INFO_LENGTH_MISMATCH {
-                        buffer.resize(dw_length as usize, 0);
-
-                        status = custom_windows::ZwQueryInformationProcess(
-                            process_handle,
-                            ProcessHandleInformation,
-                            buffer.as_mut_ptr() as *mut c_void,
-                            buffer.len() as u32,
-                            &mut dw_length,
-                        );
-                    }
-
-                    if status == STATUS_PROCESS_IS_TERMINATING {
-                        continue;
-                    }
-
-                    if status.is_err() {
-                        println!("Error: NTSTATUS({})", status.0);
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Failed to query process information",
-                        )
-                        .into());
-                    }
-
-                    // in cpp, pInformation
-                    let handles_slice = unsafe {
-                        let base_ptr =
-                            buffer.as_ptr() as *const PROCESS_HANDLE_SNAPSHOT_INFORMATION;
-                        // Calculate the pointer to the handles array. The offset should take into account the size of the struct.
-                        let handles_ptr = (base_ptr.add(1) as *const u8)
-                            .cast::<custom_windows::PROCESS_HANDLE_TABLE_ENTRY_INFO>(
-                        );
-                        // Create a slice from the handles array using the number of handles
-                        std::slice::from_raw_parts(handles_ptr, (*base_ptr).NumberOfHandles)
-                    };
-
-                    for handle_info in handles_slice {
-                        let handle = handle_info.HandleValue;
-
-                        let mut copy_handle: HANDLE = HANDLE::default(); // zeroed()
-                        let status = DuplicateHandle(
-                            process_handle,
-                            handle,
-                            GetCurrentProcess(),
-                            &mut copy_handle as *mut HANDLE,
-                            MAXIMUM_ALLOWED,
-                            false,
-                            DUPLICATE_SAME_ACCESS,
-                        );
-
-                        if status.is_err() {
-                            continue;
-                        }
-
-                        // 핸들정보 조회
-                        let mut object_buf: Vec<u8> = Vec::new();
-                        let mut dw_object_result: u32 = 0; // DWORD
-
-                        let mut status = unsafe {
-                            NtQueryObject(
-                                copy_handle,
-                                OBJECT_NAME_INFORMATION,
-                                None,                        // pass None to get size
-                                0,                           // Length is 0 for initial call
-                                Some(&mut dw_object_result), // pointer to receive required size
-                            )
-                        };
-
-                        while status == STATUS_INFO_LENGTH_MISMATCH {
-                            object_buf.resize(dw_object_result as usize, 0);
-                            status = unsafe {
-                                NtQueryObject(
-                                    copy_handle,
-                                    OBJECT_NAME_INFORMATION, // Same class as before
-                                    Some(object_buf.as_mut_ptr() as *mut c_void), // Now passing the actual buffer
-                                    dw_object_result, // The length is now the correct size
-                                    None,             // No need to pass size pointer again
-                                )
-                            };
-                        }
-
-                        if status == STATUS_PIPE_DISCONNECTED {
-                            continue;
-                        }
-
-                        let _ = CloseHandle(copy_handle);
-
-                        let p_object_info = unsafe {
-                            // Transmute the buffer
-                            (object_buf.as_ptr() as *const OBJECT_NAME_INFORMATION)
-                                .as_ref()
-                                .unwrap()
-                        };
-
-                        // Check if the Name length is non-zero
-                        if p_object_info.Name.Length > 0 {
-                            let name_slice = unsafe {
-                                std::slice::from_raw_parts(
-                                    p_object_info.Name.Buffer.as_ptr(), // Correctly access the raw pointer
-                                    p_object_info.Name.Length as usize / 2, // Divide by 2 because it's likely u16 units
-                                )
-                            };
-
-                            let name: OsString = OsStringExt::from_wide(name_slice);
-
-                            // Searching for the specific substring
-                            if name
-                                .to_string_lossy()
-                                .contains("Starcraft Check For Other Instances")
-                            {
-                                // let mut copy_handle: HANDLE = HANDLE(0); // Equivalent to nullptr in C++
-                                let status = DuplicateHandle(
-                                    process_handle,
-                                    handle,
-                                    GetCurrentProcess(),
-                                    &mut copy_handle,
-                                    MAXIMUM_ALLOWED,
-                                    false,
-                                    DUPLICATE_CLOSE_SOURCE,
-                                );
-
-                                // Check status if necessary and then close the handle
-                                if status.is_ok() {
-                                    println!(
-                                        "\t - Closed proc_handle for StarCraft.exe.{}",
-                                        process_handle.0
-                                    );
-                                    let _ = CloseHandle(copy_handle);
-                                    is_handle_found = true;
-                                    break 'outer;
-                                }
-                            }
-                        }
-                    }
-                }
+        if scanned
+            .matches
+            .iter()
+            .any(|handle| handle.action == HandleAction::Closed)
+        {
+            return Ok(pid);
+        }
+        if !scanned.matches.is_empty() {
+            return Err(t!("mutex-close-refused"));
+        }
+        if Instant::now() >= deadline {
+            return Err(t!("mutex-wait-timeout", seconds = MUTEX_WAIT.as_secs()));
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+}
 
-                if Process32Next(h_snapshot.get_handle(), &mut entry).is_err() {
-                    // let _ = CloseHandle(h_snapshot.get_handle()); // auto drop
-                    break;
-                }
+/// check and close, returning the pid of the StarCraft.exe whose mutex was closed
+///
+/// Takes whichever StarCraft.exe comes first, which may be one the user started; launches
+/// use [`close_launched_mutex`] unless asked for [`MutexScope::System`]. Gives up when no
+/// mutex could be closed within [`MUTEX_WAIT`].
+pub async fn modify_processes_once(
+    events: &broadcast::Sender<GameEvent>,
+) -> std::result::Result<u32, String> {
+    let deadline = Instant::now() + MUTEX_WAIT;
+    let mut reported = BTreeSet::new();
+    loop {
+        let pids = find_starcraft_pids().map_err(|e| t!("process-list-failed", error = e))?;
+        for pid in pids {
+            let scanned = task::spawn_blocking(move || scan_process(pid, false))
+                .await
+                .map_err(|e| t!("background-thread-failed", error = e))?;
+            report_hung(events, &scanned, &mut reported);
+            if scanned
+                .matches
+                .iter()
+                .any(|handle| handle.action == HandleAction::Closed)
+            {
+                return Ok(pid);
             }
         }
+        if Instant::now() >= deadline {
+            return Err(t!("mutex-wait-timeout", seconds = MUTEX_WAIT.as_secs()));
+        }
+        sleep(Duration::from_millis(100)).await;
     }
-
-    Ok(())
 }
 
 /// Continuously check and close
-pub async unsafe fn modify_processes() -> Result<()> {
-    let starcraft_name = b"StarCraft.exe\0"; // ANSI string with null termination
-
-    // process_name.push(0); // null-terminate
+pub async fn modify_processes(events: &broadcast::Sender<GameEvent>) -> Result<()> {
+    let mut reported = BTreeSet::new();
     loop {
         sleep(Duration::from_millis(100)).await;
-
-        let h_snapshot = HandleWrapper::new(TH32CS_SNAPPROCESS)?;
-        // Check if handle is invalid
-        if h_snapshot.get_handle() == INVALID_HANDLE_VALUE {
-            return Err(
-                std::io::Error::new(std::io::ErrorKind::Other, "Invalid handle value").into(),
-            );
+        let scan = task::spawn_blocking(|| scan_instance_mutexes(false))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
+        for scanned in &scan.processes {
+            report_hung(events, scanned, &mut reported);
         }
-        let mut entry = PROCESSENTRY32::default();
-        entry.dwSize = std::mem::size_of::<PROCESSENTRY32>() as u32;
-
-        if Process32First(h_snapshot.get_handle(), &mut entry as *mut PROCESSENTRY32).is_ok() {
-            loop {
-                // Check for StarCraft.exe
-                if unsafe {
-                    std::ffi::CStr::from_ptr(entry.szExeFile.as_ptr()).to_bytes_with_nul()
-                        == starcraft_name
-                } {
-                    let process_handle: HANDLE =
-                        unsafe { OpenProcess(PROCESS_ALL_ACCESS, false, entry.th32ProcessID)? };
-
-                    if process_handle == INVALID_HANDLE_VALUE {
-                        println!("Failed to open process with ID: {}", entry.th32ProcessID);
-                        continue; // skip to the next process
-                    }
-
-                    let mut buffer: Vec<u8> = Vec::new();
-                    let mut dw_length: u32 = 0;
-
-                    let mut status: NTSTATUS = custom_windows::ZwQueryInformationProcess(
-                        process_handle,
-                        ProcessHandleInformation,
-                        std::ptr::null_mut(), // initially pass a null pointer
-                        0,                    // and a length of 0
-                        &mut dw_length,
-                    );
-
-                    while status == STATUS_INFO_LENGTH_MISMATCH {
-                        buffer.resize(dw_length as usize, 0);
-
-                        status = custom_windows::ZwQueryInformationProcess(
-                            process_handle,
-                            ProcessHandleInformation,
-                            buffer.as_mut_ptr() as *mut c_void,
-                            buffer.len() as u32,
-                            &mut dw_length,
-                        );
-                    }
-
-                    if status == STATUS_PROCESS_IS_TERMINATING {
-                        continue;
-                    }
-
-                    if status.is_err() {
-                        println!("Error: NTSTATUS({})", status.0);
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::Other,
-                            "Failed to query process information",
-                        )
-                        .into());
-                    }
-
-                    let handles_slice = unsafe {
-                        let base_ptr =
-                            buffer.as_ptr() as *const PROCESS_HANDLE_SNAPSHOT_INFORMATION;
-                        // Calculate the pointer to the handles array. The offset should take into account the size of the struct.
-                        let handles_ptr = (base_ptr.add(1) as *const u8)
-                            .cast::<custom_windows::PROCESS_HANDLE_TABLE_ENTRY_INFO>(
-                        );
-                        // Create a slice from the handles array using the number of handles
-                        std::slice::from_raw_parts(handles_ptr, (*base_ptr).NumberOfHandles)
-                    };
-
-                    for handle_info in handles_slice {
-                        let handle = handle_info.HandleValue;
-
-                        let mut copy_handle: HANDLE = HANDLE::default(); // zeroed()
-                        let status = DuplicateHandle(
-                            process_handle,
-                            handle,
-                            GetCurrentProcess(),
-                            &mut copy_handle as *mut HANDLE,
-                            MAXIMUM_ALLOWED,
-                            false,
-                            DUPLICATE_SAME_ACCESS,
-                        );
-
-                        if status.is_err() {
-                            continue;
-                        }
+    }
+}
 
-                        // 핸들정보 조회
-                        let mut object_buf: Vec<u8> = Vec::new();
-                        let mut dw_object_result: u32 = 0; // DWORD
-
-                        let mut status = unsafe {
-                            NtQueryObject(
-                                copy_handle,
-                                OBJECT_NAME_INFORMATION,
-                                None,                        // pass None to get size
-                                0,                           // Length is 0 for initial call
-                                Some(&mut dw_object_result), // pointer to receive required size
-                            )
-                        };
-
-                        while status == STATUS_INFO_LENGTH_MISMATCH {
-                            object_buf.resize(dw_object_result as usize, 0);
-                            status = unsafe {
-                                NtQueryObject(
-                                    copy_handle,
-                                    OBJECT_NAME_INFORMATION, // Same class as before
-                                    Some(object_buf.as_mut_ptr() as *mut c_void), // Now passing the actual buffer
-                                    dw_object_result, // The length is now the correct size
-                                    None,             // No need to pass size pointer again
-                                )
-                            };
-                        }
+/// Publishes the handles of `scanned` whose name query hung, each one once.
+fn report_hung(
+    events: &broadcast::Sender<GameEvent>,
+    scanned: &ScannedProcess,
+    reported: &mut BTreeSet<(u32, usize)>,
+) {
+    for &handle in &scanned.hung_handles {
+        if reported.insert((scanned.pid, handle)) {
+            // No subscribers is fine
+            let _ = events.send(GameEvent::HandleHung {
+                pid: scanned.pid,
+                handle,
+            });
+        }
+    }
+}
 
-                        if status == STATUS_PIPE_DISCONNECTED {
-                            continue;
-                        }
+/// Builds the command line `spawn_console_process` passes to `CreateProcessW`.
+///
+/// Arguments with spaces or quotes are quoted the way `CommandLineToArgvW` splits them.
+pub fn command_line(application: &str, args: &[&str]) -> String {
+    std::iter::once(application)
+        .chain(args.iter().copied())
+        .map(quote_arg)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-                        let _ = CloseHandle(copy_handle);
-
-                        let p_object_info = unsafe {
-                            // Transmute the buffer
-                            (object_buf.as_ptr() as *const OBJECT_NAME_INFORMATION)
-                                .as_ref()
-                                .unwrap()
-                        };
-
-                        // Check if the Name length is non-zero
-                        if p_object_info.Name.Length > 0 {
-                            let name_slice = unsafe {
-                                std::slice::from_raw_parts(
-                                    p_object_info.Name.Buffer.as_ptr(), // Correctly access the raw pointer
-                                    p_object_info.Name.Length as usize / 2, // Divide by 2 because it's likely u16 units
-                                )
-                            };
-
-                            let name: OsString = OsStringExt::from_wide(name_slice);
-
-                            // Searching for the specific substring
-                            if name
-                                .to_string_lossy()
-                                .contains("Starcraft Check For Other Instances")
-                            {
-                                // let mut copy_handle: HANDLE = HANDLE(0); // Equivalent to nullptr in C++
-                                let status = DuplicateHandle(
-                                    process_handle,
-                                    handle,
-                                    GetCurrentProcess(),
-                                    &mut copy_handle,
-                                    MAXIMUM_ALLOWED,
-                                    false,
-                                    DUPLICATE_CLOSE_SOURCE,
-                                );
-
-                                // Check status if necessary and then close the handle
-                                if status.is_ok() {
-                                    let _ = CloseHandle(copy_handle);
-                                }
-                            }
-                        }
-                    }
-                }
+/// Quotes one argument so `CommandLineToArgvW` and the C runtime split it back unchanged.
+pub(crate) fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
 
-                if Process32Next(h_snapshot.get_handle(), &mut entry).is_err() {
-                    // let _ = CloseHandle(h_snapshot.get_handle()); // auto drop
-                    break;
-                }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are doubled, plus one to escape the quote
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                backslashes = 0;
             }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
         }
     }
+    // Backslashes before the closing quote are doubled too
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
 }
 
 /// Function to spawn a console process with no handle inheritance.
-pub fn spawn_console_process(application: &str, args: Vec<&str>) -> PROCESS_INFORMATION {
+pub fn spawn_console_process(application: &str, args: Vec<&str>) -> Result<PROCESS_INFORMATION> {
     // Create command line string
-    let mut cmd: Vec<u16> = OsStr::new(application).encode_wide().collect::<Vec<_>>();
-    for arg in args {
-        cmd.push(' ' as u16);
-        cmd.extend(OsStr::new(arg).encode_wide());
-    }
+    let mut cmd: Vec<u16> = OsStr::new(&command_line(application, &args))
+        .encode_wide()
+        .collect();
     cmd.push(0); // Null-terminate the entire command line
 
     let mut process_info = PROCESS_INFORMATION::default();
-    let mut startup_info = STARTUPINFOW::default();
-    startup_info.cb = mem::size_of::<STARTUPINFOW>() as u32;
+    let startup_info = STARTUPINFOW {
+        cb: mem::size_of::<STARTUPINFOW>() as u32,
+        ..Default::default()
+    };
 
     unsafe {
         CreateProcessW(
@@ -522,13 +621,40 @@ pub fn spawn_console_process(application: &str, args: Vec<&str>) -> PROCESS_INFO
             PROCESS_CREATION_FLAGS(CREATE_NEW_CONSOLE.0 | CREATE_NO_WINDOW.0), // CREATE_NO_WINDOW | CREATE_NO_INHERIT_HANDLES
             None, // Use parent's environment block
             None, // Use parent's starting directory
-            &mut startup_info,
+            &startup_info,
             &mut process_info,
-        )
-        .expect("Failed to create process");
+        )?;
     }
 
-    process_info
+    Ok(process_info)
 }
 
 // WINDOWS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_arguments_are_left_alone() {
+        assert_eq!(quote_arg("-launch"), "-launch");
+        assert_eq!(quote_arg(r"C:\Games\StarCraft"), r"C:\Games\StarCraft");
+    }
+
+    #[test]
+    fn spaces_quotes_and_trailing_backslashes_are_escaped() {
+        assert_eq!(quote_arg(""), r#""""#);
+        assert_eq!(quote_arg("bot 1"), r#""bot 1""#);
+        assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_arg(r"C:\Program Files\"), r#""C:\Program Files\\""#);
+        assert_eq!(quote_arg(r#"a\"b"#), r#""a\\\"b""#);
+    }
+
+    #[test]
+    fn command_lines_quote_every_part() {
+        assert_eq!(
+            command_line(r"C:\Program Files\StarCraft.exe", &["-launch", "bot 1"]),
+            r#""C:\Program Files\StarCraft.exe" -launch "bot 1""#
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use regex::Regex;
+use serde::Serialize;
+use windows::Win32::{
+    Foundation::CloseHandle,
+    System::Threading::{OpenProcess, PROCESS_DUP_HANDLE, PROCESS_QUERY_INFORMATION},
+};
+
+use super::name_query::NameQueryHung;
+use super::scan::{object_types, query_object_name, query_object_type, query_process_handles};
+use crate::t;
+
+/// One handle open in a process, as Sysinternals' handle.exe shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HandleInfo {
+    /// Handle value inside the process
+    pub handle: usize,
+    /// Access mask the handle was opened with
+    pub granted_access: u32,
+    /// `Mutant`, `Event`, `File`, ...; `None` when the handle could not be duplicated
+    pub type_name: Option<String>,
+    /// Most handles have no name
+    pub name: Option<String>,
+}
+
+/// Which handles [`inspect_handles`] reports.
+#[derive(Debug, Clone, Default)]
+pub struct HandleFilter {
+    /// Object type, compared without case
+    pub type_name: Option<String>,
+    /// Pattern the object name must match; unnamed handles never do
+    pub name: Option<Regex>,
+}
+
+impl HandleFilter {
+    fn type_matches(&self, type_name: Option<&str>) -> bool {
+        self.type_name.as_deref().is_none_or(|wanted| {
+            type_name.is_some_and(|type_name| type_name.eq_ignore_ascii_case(wanted))
+        })
+    }
+
+    fn name_matches(&self, name: Option<&str>) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| name.is_some_and(|name| pattern.is_match(name)))
+    }
+}
+
+/// The handles of one process that passed the filter.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessHandles {
+    pub pid: u32,
+    /// Number of handles the process has open, filtered or not
+    pub handle_count: usize,
+    pub handles: Vec<HandleInfo>,
+    /// Handles whose name query did not return in time; they are left out of `handles`
+    pub hung_handles: Vec<usize>,
+    /// Set when the process could not be opened or queried
+    pub error: Option<String>,
+}
+
+/// Lists the handles of `pid` that pass `filter`, with their access, type and name.
+///
+/// The type comes from the cached type list where it can, so filtering by type spares
+/// duplicating and naming the other handles.
+pub fn inspect_handles(pid: u32, filter: &HandleFilter) -> ProcessHandles {
+    let mut inspected = ProcessHandles {
+        pid,
+        handle_count: 0,
+        handles: Vec::new(),
+        hung_handles: Vec::new(),
+        error: None,
+    };
+
+    let process =
+        match unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_DUP_HANDLE, false, pid) } {
+            Ok(process) => process,
+            Err(e) => {
+                inspected.error = Some(t!("process-open-failed", error = e));
+                return inspected;
+            }
+        };
+
+    match query_process_handles(process) {
+        Ok(entries) => {
+            inspected.handle_count = entries.len();
+            let types = object_types();
+            for entry in entries {
+                let type_name = match types.and_then(|types| types.name(entry.ObjectTypeIndex)) {
+                    Some(type_name) => Some(type_name.to_string()),
+                    None => query_object_type(process, entry.HandleValue),
+                };
+                if !filter.type_matches(type_name.as_deref()) {
+                    continue;
+                }
+                let name = match query_object_name(pid, process, entry.HandleValue) {
+                    Ok(name) => name,
+                    Err(NameQueryHung) => {
+                        inspected.hung_handles.push(entry.HandleValue.0 as usize);
+                        continue;
+                    }
+                };
+                if !filter.name_matches(name.as_deref()) {
+                    continue;
+                }
+                inspected.handles.push(HandleInfo {
+                    handle: entry.HandleValue.0 as usize,
+                    granted_access: entry.GrantedAccess,
+                    type_name,
+                    name,
+                });
+            }
+        }
+        Err(status) => {
+            inspected.error = Some(t!(
+                "handle-query-failed",
+                status = format!("{:#x}", status.0)
+            ))
+        }
+    }
+
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    inspected
+}
@@ -0,0 +1,424 @@
+use std::convert::Infallible;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, Request as HttpRequest, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response as HttpResponse,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use super::daemon::{Daemon, GameBackend};
+use super::instance::DEFAULT_PROFILE;
+use super::ipc::{Request, Response};
+use super::output::{Document, ErrorClass, ErrorInfo};
+use crate::t;
+
+/// Localhost only; binding to the LAN has to be asked for explicitly.
+pub const DEFAULT_BIND: &str = "127.0.0.1:7878";
+
+struct HttpState<B> {
+    daemon: Arc<Daemon<B>>,
+    token: String,
+}
+
+/// Serves the daemon's games over HTTP/JSON until the future is dropped.
+///
+/// Clients send `token` as `Authorization: Bearer <token>`, or as `?token=` where headers
+/// cannot be set (`EventSource`).
+///
+/// * `GET /v1/status`
+/// * `GET /v1/instances`
+/// * `POST /v1/instances` with a `LaunchSpec` body
+/// * `DELETE /v1/instances?select=<selector>`
+/// * `POST /v1/adopt`
+/// * `GET /v1/events` - lifecycle events as Server-Sent Events
+pub async fn serve_http<B: GameBackend>(
+    daemon: Arc<Daemon<B>>,
+    listener: TcpListener,
+    token: String,
+) -> io::Result<()> {
+    axum::serve(listener, router(daemon, token)).await
+}
+
+fn router<B: GameBackend>(daemon: Arc<Daemon<B>>, token: String) -> Router {
+    let state = Arc::new(HttpState { daemon, token });
+
+    Router::new()
+        .route("/v1/status", get(status::<B>))
+        .route(
+            "/v1/instances",
+            get(list::<B>).post(launch::<B>).delete(kill::<B>),
+        )
+        .route("/v1/adopt", post(adopt::<B>))
+        .route("/v1/events", get(events::<B>))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            authorize::<B>,
+        ))
+        .with_state(state)
+}
+
+/// Makes a random token for when none is configured.
+pub fn generate_token() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    // RandomState is seeded from the OS, which is plenty for a LAN control token
+    (0..2)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos(),
+            );
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+async fn authorize<B>(
+    State(state): State<Arc<HttpState<B>>>,
+    request: HttpRequest,
+    next: Next,
+) -> HttpResponse {
+    if is_authorized(&request, &state.token) {
+        next.run(request).await
+    } else {
+        failure(
+            "auth",
+            ErrorInfo::new(ErrorClass::Usage, t!("http-unauthorized")),
+            StatusCode::UNAUTHORIZED,
+        )
+    }
+}
+
+/// Whether the request carries `token` as a bearer token or a `token=` query parameter,
+/// the latter for `EventSource`, which cannot set headers.
+fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value)
+    });
+
+    bearer.is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+        || query.is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Body of `POST /v1/instances`.
+#[derive(Debug, Deserialize)]
+pub struct LaunchSpec {
+    #[serde(default = "one")]
+    pub count: u32,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub game_path: Option<PathBuf>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct KillQuery {
+    select: Option<String>,
+}
+
+async fn status<B: GameBackend>(State(state): State<Arc<HttpState<B>>>) -> HttpResponse {
+    forward(&state, "status", Request::Status).await
+}
+
+async fn list<B: GameBackend>(State(state): State<Arc<HttpState<B>>>) -> HttpResponse {
+    forward(&state, "list", Request::List).await
+}
+
+async fn launch<B: GameBackend>(
+    State(state): State<Arc<HttpState<B>>>,
+    Json(spec): Json<LaunchSpec>,
+) -> HttpResponse {
+    let request = Request::Launch {
+        count: spec.count,
+        profile: spec.profile.unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+        labels: spec.labels,
+        game_path: spec.game_path,
+    };
+    forward(&state, "launch", request).await
+}
+
+async fn kill<B: GameBackend>(
+    State(state): State<Arc<HttpState<B>>>,
+    Query(query): Query<KillQuery>,
+) -> HttpResponse {
+    // Killing everything by accident is too easy without an explicit selector
+    let Some(selector) = query.select else {
+        return failure(
+            "kill",
+            ErrorInfo::new(ErrorClass::Usage, t!("http-select-required")),
+            StatusCode::BAD_REQUEST,
+        );
+    };
+    forward(&state, "kill", Request::Kill { selector }).await
+}
+
+async fn adopt<B: GameBackend>(State(state): State<Arc<HttpState<B>>>) -> HttpResponse {
+    forward(&state, "adopt", Request::Adopt).await
+}
+
+async fn events<B: GameBackend>(
+    State(state): State<Arc<HttpState<B>>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.daemon.backend().subscribe())
+        // A lagging client just misses events
+        .filter_map(|event| event.ok())
+        .filter_map(|event| Event::default().json_data(event).ok())
+        .map(Ok);
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Runs a request through the daemon and wraps the result in the usual JSON `Document`.
+async fn forward<B: GameBackend>(
+    state: &HttpState<B>,
+    command: &str,
+    request: Request,
+) -> HttpResponse {
+    match state.daemon.handle(request).await {
+        Response::Error { error } => {
+            let status = match error.class {
+                ErrorClass::Usage => StatusCode::BAD_REQUEST,
+                ErrorClass::NotElevated => StatusCode::FORBIDDEN,
+                ErrorClass::NoMatch => StatusCode::NOT_FOUND,
+                ErrorClass::LimitExceeded => StatusCode::CONFLICT,
+                ErrorClass::InstallNotFound => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            failure(command, error, status)
+        }
+        response => (StatusCode::OK, Json(Document::success(command, response))).into_response(),
+    }
+}
+
+fn failure(command: &str, error: ErrorInfo, status: StatusCode) -> HttpResponse {
+    (status, Json(Document::<()>::failure(command, error))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+    use crate::util::instance::GameInstance;
+    use crate::util::limits::LaunchLimits;
+    use crate::util::testing::{game, FakeBackend};
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn app(games: Vec<GameInstance>, limits: LaunchLimits) -> Router {
+        set_language(Lang::En);
+        let daemon = Daemon::new(
+            FakeBackend::new(games),
+            Some(PathBuf::from("StarCraft.exe")),
+            limits,
+        );
+        router(daemon, TOKEN.to_string())
+    }
+
+    /// Sends one request through the router and returns the status and the JSON document.
+    async fn send(
+        app: Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut builder = HttpRequest::builder()
+            .method(method)
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", TOKEN));
+        let body = match body {
+            Some(body) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = app.oneshot(builder.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn request(uri: &str, authorization: Option<&str>) -> HttpRequest {
+        let mut builder = HttpRequest::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn the_token_is_taken_from_the_header_or_the_query() {
+        let token = "0123456789abcdef";
+        assert!(is_authorized(
+            &request("/v1/status", Some("Bearer 0123456789abcdef")),
+            token
+        ));
+        assert!(is_authorized(
+            &request("/v1/events?since=0&token=0123456789abcdef", None),
+            token
+        ));
+        assert!(is_authorized(
+            &request("/v1/events?token=0123456789abcdef", Some("Bearer wrong")),
+            token
+        ));
+    }
+
+    #[test]
+    fn missing_or_wrong_tokens_are_refused() {
+        let token = "0123456789abcdef";
+        assert!(!is_authorized(&request("/v1/status", None), token));
+        assert!(!is_authorized(
+            &request("/v1/status", Some("Bearer 0123456789abcdeF")),
+            token
+        ));
+        assert!(!is_authorized(
+            &request("/v1/status", Some("0123456789abcdef")),
+            token
+        ));
+        assert!(!is_authorized(
+            &request("/v1/status?token=0123456789abcde", None),
+            token
+        ));
+    }
+
+    #[test]
+    fn the_query_token_is_percent_decoded() {
+        assert!(is_authorized(
+            &request("/v1/events?token=a%2Bb%20c", None),
+            "a+b c"
+        ));
+        assert!(is_authorized(&request("/v1/events?token=a+b", None), "a b"));
+        assert!(!is_authorized(
+            &request("/v1/events?token=a%2Bb%20c", None),
+            "a%2Bb%20c"
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_get_401() {
+        let app = app(Vec::new(), LaunchLimits::default());
+        let response = app.oneshot(request("/v1/status", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn list_returns_the_tracked_games() {
+        let app = app(
+            vec![game(42, Some("main"), "default")],
+            LaunchLimits::default(),
+        );
+        let (status, document) = send(app, "GET", "/v1/instances", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(document["ok"], true);
+        assert_eq!(document["data"]["instances"][0]["pid"], 42);
+        assert_eq!(document["data"]["instances"][0]["label"], "main");
+    }
+
+    #[tokio::test]
+    async fn launch_starts_games() {
+        let app = app(Vec::new(), LaunchLimits::default());
+        let spec = json!({ "profile": "ladder", "labels": ["left"] });
+        let (status, document) = send(app, "POST", "/v1/instances", Some(spec)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(document["command"], "launch");
+        assert_eq!(document["data"]["pids"], json!([1000]));
+    }
+
+    #[tokio::test]
+    async fn launch_over_the_limit_is_409() {
+        let limits = LaunchLimits {
+            max_instances: 1,
+            ..LaunchLimits::default()
+        };
+        let app = app(vec![game(1, None, "default")], limits);
+        let (status, document) = send(app, "POST", "/v1/instances", Some(json!({}))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(document["ok"], false);
+        assert_eq!(document["error"]["class"], "limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn kill_needs_a_selector() {
+        let app = app(vec![game(1, None, "default")], LaunchLimits::default());
+        let (status, document) = send(app, "DELETE", "/v1/instances", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(document["error"]["class"], "usage");
+        assert_eq!(
+            document["error"]["message"],
+            "Pass ?select=<selector>, e.g. ?select=all."
+        );
+    }
+
+    #[tokio::test]
+    async fn kill_without_a_match_is_404() {
+        let app = app(
+            vec![game(1, Some("main"), "default")],
+            LaunchLimits::default(),
+        );
+        let (status, document) = send(
+            app.clone(),
+            "DELETE",
+            "/v1/instances?select=label:smurf",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(document["error"]["class"], "no_match");
+
+        let (status, document) = send(app, "DELETE", "/v1/instances?select=label:main", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(document["data"]["pids"], json!([1]));
+    }
+
+    #[test]
+    fn generated_tokens_are_32_hex_digits() {
+        let token = generate_token();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn launch_specs_default_to_one_game() {
+        let spec: LaunchSpec = serde_json::from_str("{}").unwrap();
+        assert_eq!(spec.count, 1);
+        assert!(spec.profile.is_none() && spec.labels.is_empty() && spec.game_path.is_none());
+    }
+}
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use fluent_bundle::{concurrent::FluentBundle, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+pub use fluent_bundle::FluentArgs;
+
+const EN_MESSAGES: &str = include_str!("../../locales/en.ftl");
+const KO_MESSAGES: &str = include_str!("../../locales/ko.ftl");
+
+/// Language of the user-facing text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Lang {
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "ko")]
+    Ko,
+}
+
+impl Lang {
+    /// The language of the OS user locale, English unless it is Korean.
+    pub fn detect() -> Lang {
+        sys_locale::get_locale()
+            .and_then(|locale| locale.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Ko => "ko",
+        }
+    }
+
+    fn messages(self) -> &'static str {
+        match self {
+            Lang::En => EN_MESSAGES,
+            Lang::Ko => KO_MESSAGES,
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    /// Accepts a language code or a locale such as `ko-KR` or `en_US.UTF-8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match code.as_str() {
+            "en" | "english" => Ok(Lang::En),
+            "ko" | "kor" | "korean" => Ok(Lang::Ko),
+            _ => Err(crate::t!("lang-unknown", lang = s)),
+        }
+    }
+}
+
+/// The messages of one language, falling back to English for missing keys.
+struct Catalog {
+    lang: Lang,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Catalog {
+    fn new(lang: Lang) -> Self {
+        let mut languages = vec![lang];
+        if lang != Lang::En {
+            languages.push(Lang::En);
+        }
+        Catalog {
+            lang,
+            bundles: languages.into_iter().map(bundle).collect(),
+        }
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(key).and_then(|message| message.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned();
+        }
+        // A missing key still shows something useful
+        key.to_string()
+    }
+}
+
+fn bundle(lang: Lang) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = lang.code().parse().expect("valid language code");
+    let resource = FluentResource::try_new(lang.messages().to_string())
+        .unwrap_or_else(|(_, errors)| panic!("locales/{}.ftl: {:?}", lang, errors));
+
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // Isolation marks around placeables show up as garbage in consoles
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("locales/{}.ftl: {:?}", lang, errors));
+    bundle
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new(Lang::detect()))
+}
+
+/// Chooses the language of every later message. Only the first call has an effect;
+/// messages printed before it use the OS locale.
+pub fn set_language(lang: Lang) -> bool {
+    CATALOG.set(Catalog::new(lang)).is_ok()
+}
+
+/// The language messages are printed in.
+pub fn language() -> Lang {
+    catalog().lang
+}
+
+/// Formats the message `key` of the current language. Prefer the `t!` macro.
+pub fn message(key: &str, args: Option<&FluentArgs>) -> String {
+    catalog().format(key, args)
+}
+
+/// A message argument on its way to Fluent. `t!` wraps every value in one, and the two traits
+/// below pick the conversion by type: integers become numbers so plural forms work, anything
+/// else is formatted as text, so a label such as `007` stays `007`.
+#[doc(hidden)]
+pub struct Arg<T>(pub T);
+
+#[doc(hidden)]
+pub trait NumberArg {
+    fn fluent_value(&self) -> FluentValue<'static>;
+}
+
+#[doc(hidden)]
+pub trait TextArg {
+    fn fluent_value(&self) -> FluentValue<'static>;
+}
+
+macro_rules! number_args {
+    ($($number:ty),+) => {$(
+        impl NumberArg for Arg<$number> {
+            fn fluent_value(&self) -> FluentValue<'static> {
+                FluentValue::from(self.0)
+            }
+        }
+
+        impl NumberArg for Arg<&$number> {
+            fn fluent_value(&self) -> FluentValue<'static> {
+                FluentValue::from(*self.0)
+            }
+        }
+    )+};
+}
+
+number_args!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// Implemented for `&Arg` so method lookup only falls back to it when no `NumberArg` applies
+impl<T: fmt::Display> TextArg for &Arg<T> {
+    fn fluent_value(&self) -> FluentValue<'static> {
+        FluentValue::from(self.0.to_string())
+    }
+}
+
+/// Formats a message of the catalog in `locales/`, e.g. `t!("no-match", selector = selector)`.
+#[macro_export]
+macro_rules! t {
+    ($key:literal) => {
+        $crate::util::i18n::message($key, None)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::util::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), {
+            #[allow(unused_imports)]
+            use $crate::util::i18n::{NumberArg as _, TextArg as _};
+            (&$crate::util::i18n::Arg($value)).fluent_value()
+        });)+
+        $crate::util::i18n::message($key, Some(&args))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use fluent_syntax::ast::{Entry, Expression, InlineExpression, Pattern, PatternElement};
+    use fluent_syntax::parser;
+
+    use super::*;
+
+    /// What `t!` hands Fluent for `value`.
+    macro_rules! fluent {
+        ($value:expr) => {{
+            #[allow(unused_imports)]
+            use super::{NumberArg as _, TextArg as _};
+            (&Arg($value)).fluent_value()
+        }};
+    }
+
+    /// Message keys of a catalog with the `$variables` each one uses.
+    fn variables(lang: Lang) -> BTreeMap<String, BTreeSet<String>> {
+        let resource = parser::parse(lang.messages())
+            .unwrap_or_else(|(_, errors)| panic!("locales/{}.ftl: {:?}", lang, errors));
+        resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message),
+                _ => None,
+            })
+            .map(|message| {
+                let mut names = BTreeSet::new();
+                if let Some(pattern) = &message.value {
+                    pattern_variables(pattern, &mut names);
+                }
+                (message.id.name.to_string(), names)
+            })
+            .collect()
+    }
+
+    fn pattern_variables(pattern: &Pattern<&str>, names: &mut BTreeSet<String>) {
+        for element in &pattern.elements {
+            if let PatternElement::Placeable { expression } = element {
+                expression_variables(expression, names);
+            }
+        }
+    }
+
+    fn expression_variables(expression: &Expression<&str>, names: &mut BTreeSet<String>) {
+        match expression {
+            Expression::Select { selector, variants } => {
+                inline_variables(selector, names);
+                for variant in variants {
+                    pattern_variables(&variant.value, names);
+                }
+            }
+            Expression::Inline(inline) => inline_variables(inline, names),
+        }
+    }
+
+    fn inline_variables(inline: &InlineExpression<&str>, names: &mut BTreeSet<String>) {
+        match inline {
+            InlineExpression::VariableReference { id } => {
+                names.insert(id.name.to_string());
+            }
+            InlineExpression::FunctionReference { arguments, .. } => {
+                for argument in &arguments.positional {
+                    inline_variables(argument, names);
+                }
+                for argument in &arguments.named {
+                    inline_variables(&argument.value, names);
+                }
+            }
+            InlineExpression::Placeable { expression } => expression_variables(expression, names),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn every_key_exists_in_both_languages_with_the_same_variables() {
+        let en = variables(Lang::En);
+        let ko = variables(Lang::Ko);
+        assert!(!en.is_empty());
+
+        for (key, names) in &en {
+            assert_eq!(ko.get(key), Some(names), "'{}' differs in ko.ftl", key);
+        }
+        for key in ko.keys() {
+            assert!(en.contains_key(key), "'{}' is missing from en.ftl", key);
+        }
+    }
+
+    #[test]
+    fn variables_after_a_select_expression_are_found() {
+        let en = variables(Lang::En);
+        let names: Vec<&str> = en["install-no-arch"].iter().map(String::as_str).collect();
+        assert_eq!(names, ["arch", "count"]);
+    }
+
+    #[test]
+    fn plural_forms_follow_the_count() {
+        let catalog = Catalog::new(Lang::En);
+        let mut args = FluentArgs::new();
+        args.set("count", fluent!(1u32));
+        args.set("path", fluent!("out.reg"));
+        assert_eq!(
+            catalog.format("registry-exported", Some(&args)),
+            "Wrote one change to out.reg."
+        );
+    }
+
+    #[test]
+    fn only_integers_become_numbers() {
+        let count = 3usize;
+        assert_eq!(fluent!(count), FluentValue::from(3));
+        assert_eq!(fluent!(&count), FluentValue::from(3));
+        assert_eq!(fluent!("007"), FluentValue::from("007".to_string()));
+        assert_eq!(
+            fluent!(String::from("+5")),
+            FluentValue::from("+5".to_string())
+        );
+    }
+
+    #[test]
+    fn labels_that_look_like_numbers_are_kept() {
+        set_language(Lang::En);
+        assert_eq!(
+            crate::t!("event-launched-label", pid = 7u32, label = "007"),
+            "Launched game with pid: 7 (007)"
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Serialize;
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::{
+    GetNativeSystemInfo, PROCESSOR_ARCHITECTURE_AMD64, PROCESSOR_ARCHITECTURE_ARM64, SYSTEM_INFO,
+};
+
+use super::battlenet;
+use super::config::Arch;
+use super::fsscan::{cache_path, fixed_drives, well_known_locations, ScanCache};
+use super::layout::{InstallLayout, EXE_NAME};
+use super::pe::{read_machine, read_pe_info, PeInfo};
+#[cfg(windows)]
+use super::portable;
+#[cfg(windows)]
+use super::reg::{legacy_install_paths, starcraft_uninstall_entries};
+use crate::t;
+
+/// Which StarCraft an install folder holds, in the order installs are offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edition {
+    /// The Battle.net build with `x86` and `x86_64` folders
+    Remastered,
+    /// 1.16.1 with StarCraft.exe next to storm.dll
+    Legacy,
+    /// The public test realm build
+    Ptr,
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Edition::Remastered => "Remastered",
+            Edition::Legacy => "1.16.1",
+            Edition::Ptr => "PTR",
+        })
+    }
+}
+
+impl FromStr for Edition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "remastered" | "scr" => Ok(Edition::Remastered),
+            "legacy" | "1.16.1" | "1161" => Ok(Edition::Legacy),
+            "ptr" => Ok(Edition::Ptr),
+            _ => Err(t!("edition-unknown", edition = s)),
+        }
+    }
+}
+
+/// Where an install was found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "key", rename_all = "snake_case")]
+pub enum DiscoverySource {
+    /// An `Uninstall` entry written by Battle.net
+    UninstallKey(String),
+    /// `InstallPath` of the 1.16.1 installer
+    LegacyKey(String),
+    /// A match of the full registry search
+    RegistrySearch(String),
+    /// `product.db` or `Battle.net.config` of the Battle.net app, at this path
+    BattleNet(String),
+    /// A usual install folder that turned out to hold StarCraft
+    WellKnown(String),
+    /// Found by an earlier disk scan, from the scan cache at this path
+    ScanCache(String),
+    /// Found by walking the folder tree under this root
+    DriveScan(String),
+}
+
+impl fmt::Display for DiscoverySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&match self {
+            DiscoverySource::UninstallKey(key) => t!("source-uninstall-key", key = key),
+            DiscoverySource::LegacyKey(key) => t!("source-legacy-key", key = key),
+            DiscoverySource::RegistrySearch(key) => t!("source-registry-search", key = key),
+            DiscoverySource::BattleNet(path) => t!("source-battle-net", path = path),
+            DiscoverySource::WellKnown(path) => t!("source-well-known", path = path),
+            DiscoverySource::ScanCache(path) => t!("source-scan-cache", path = path),
+            DiscoverySource::DriveScan(path) => t!("source-drive-scan", path = path),
+        })
+    }
+}
+
+/// One StarCraft folder on this machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct Installation {
+    pub root: PathBuf,
+    pub edition: Edition,
+    pub layout: InstallLayout,
+    /// File version of StarCraft.exe, or the `DisplayVersion` of the uninstall entry
+    pub version: Option<String>,
+    /// Battle.net version branch, e.g. `ptr`, when Battle.net knows the install
+    pub branch: Option<String>,
+    /// Architectures with a StarCraft.exe in this install
+    pub architectures: Vec<Arch>,
+    pub sources: Vec<DiscoverySource>,
+}
+
+/// The headers and version of `exe`, keeping the machine type alone when the version
+/// resource cannot be read.
+fn read_build(exe: &Path) -> Option<PeInfo> {
+    read_pe_info(exe).ok().or_else(|| {
+        Some(PeInfo {
+            machine: read_machine(exe).ok()?,
+            version: None,
+        })
+    })
+}
+
+impl Installation {
+    /// Looks at `root` and describes the install there, `None` when it holds no StarCraft.exe.
+    ///
+    /// Architectures come from the PE headers, so a binary in the wrong folder is not offered.
+    pub fn detect(
+        root: impl Into<PathBuf>,
+        source: DiscoverySource,
+        version: Option<String>,
+        display_name: Option<&str>,
+    ) -> Option<Installation> {
+        let root = root.into();
+        let layout = InstallLayout::detect(&root)?;
+
+        let (edition, builds) = if layout.per_arch() {
+            let builds: Vec<(Arch, PeInfo)> = [Arch::X86, Arch::X86_64]
+                .into_iter()
+                .filter_map(|arch| {
+                    let info = read_build(&layout.exe(&root, arch))?;
+                    (info.machine.arch() == Some(arch)).then_some((arch, info))
+                })
+                .collect();
+            if builds.is_empty() {
+                return None;
+            }
+            let label = format!("{} {}", root.display(), display_name.unwrap_or_default());
+            (Self::remastered_or_ptr(&label), builds)
+        } else {
+            // 1.16.1 and repacks of it have one StarCraft.exe in the root
+            let info = read_build(&root.join(EXE_NAME))?;
+            (Edition::Legacy, vec![(info.machine.arch()?, info)])
+        };
+
+        let file_version = builds
+            .iter()
+            .find_map(|(_, info)| info.version.as_ref()?.file_version.clone());
+        Some(Installation {
+            root,
+            edition,
+            layout,
+            version: file_version.or(version.filter(|version| !version.trim().is_empty())),
+            branch: None,
+            architectures: builds.into_iter().map(|(arch, _)| arch).collect(),
+            sources: vec![source],
+        })
+    }
+
+    /// PTR when `label` (the folder, display name or Battle.net uid and branch) has `ptr`
+    /// or `public test` as whole words, so `s1_ptr` counts but `Captr` does not.
+    fn remastered_or_ptr(label: &str) -> Edition {
+        let label = label.to_lowercase();
+        let words: Vec<&str> = label
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let ptr = words.contains(&"ptr") || words.windows(2).any(|pair| pair == ["public", "test"]);
+        if ptr {
+            Edition::Ptr
+        } else {
+            Edition::Remastered
+        }
+    }
+
+    pub fn supports(&self, arch: Arch) -> bool {
+        self.architectures.contains(&arch)
+    }
+
+    /// The architecture to launch when none was asked for: the OS's own if this install has it.
+    pub fn default_arch(&self) -> Option<Arch> {
+        let native = native_arch();
+        if self.supports(native) {
+            Some(native)
+        } else {
+            self.architectures.first().copied()
+        }
+    }
+
+    /// The StarCraft.exe to launch for `arch`, if this install has one.
+    pub fn exe(&self, arch: Arch) -> Option<PathBuf> {
+        if !self.supports(arch) {
+            return None;
+        }
+        Some(self.layout.exe(&self.root, arch))
+    }
+
+    /// Whether only the full registry search knows about this install.
+    pub fn search_only(&self) -> bool {
+        self.sources
+            .iter()
+            .all(|source| matches!(source, DiscoverySource::RegistrySearch(_)))
+    }
+
+    /// Folders are the same install when they resolve to the same path, ignoring case.
+    fn identity(&self) -> String {
+        let path = std::fs::canonicalize(&self.root).unwrap_or_else(|_| self.root.clone());
+        path.to_string_lossy()
+            .trim_end_matches(['\\', '/'])
+            .to_lowercase()
+    }
+}
+
+impl fmt::Display for Installation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}", self.root.display(), self.edition)?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        if let Some(branch) = &self.branch {
+            write!(f, " ({})", branch)?;
+        }
+        let architectures: Vec<&str> = self
+            .architectures
+            .iter()
+            .map(|arch| arch.folder())
+            .collect();
+        write!(f, ", {}]", architectures.join(" "))
+    }
+}
+
+/// `x86_64` on 64-bit Windows (ARM64 emulates it), `x86` otherwise.
+#[cfg(windows)]
+pub fn native_arch() -> Arch {
+    let mut info = SYSTEM_INFO::default();
+    unsafe { GetNativeSystemInfo(&mut info) };
+    match unsafe { info.Anonymous.Anonymous.wProcessorArchitecture } {
+        PROCESSOR_ARCHITECTURE_AMD64 | PROCESSOR_ARCHITECTURE_ARM64 => Arch::X86_64,
+        _ => Arch::X86,
+    }
+}
+
+/// The architecture of this build, as there is no WoW64 to look through.
+#[cfg(not(windows))]
+pub fn native_arch() -> Arch {
+    if cfg!(target_pointer_width = "64") {
+        Arch::X86_64
+    } else {
+        Arch::X86
+    }
+}
+
+/// Finds every StarCraft install the registry, Battle.net, the scan cache and the usual
+/// folders know about, best candidate first.
+///
+/// Only reads the keys StarCraft installers write, and none in portable mode; the full
+/// registry search and the drive walk are slow and left to the caller, whose finds can be
+/// folded in with [`rank_installations`].
+pub fn discover_installations() -> Vec<Installation> {
+    let mut candidates = Vec::new();
+
+    // A portable copy leaves the registry alone, reads included
+    #[cfg(windows)]
+    if !portable::enabled() {
+        for entry in starcraft_uninstall_entries() {
+            candidates.extend(Installation::detect(
+                &entry.install_location,
+                DiscoverySource::UninstallKey(entry.key_path),
+                entry.display_version,
+                entry.display_name.as_deref(),
+            ));
+        }
+        for (key, install_path) in legacy_install_paths() {
+            candidates.extend(Installation::detect(
+                &install_path,
+                DiscoverySource::LegacyKey(key),
+                None,
+                None,
+            ));
+        }
+    }
+    // Battle.net knows installs the registry lost after a drive move
+    for found in battlenet::starcraft_installs() {
+        let label = format!(
+            "{} {}",
+            found.uid.as_deref().unwrap_or_default(),
+            found.branch.as_deref().unwrap_or_default()
+        );
+        let source = DiscoverySource::BattleNet(found.file.display().to_string());
+        if let Some(mut install) = Installation::detect(found.path, source, None, Some(&label)) {
+            install.branch = found.branch;
+            candidates.push(install);
+        }
+    }
+    if let Some(cache) = cache_path() {
+        for folder in ScanCache::load(&cache).folders {
+            let source = DiscoverySource::ScanCache(cache.display().to_string());
+            candidates.extend(Installation::detect(folder, source, None, None));
+        }
+    }
+    for folder in well_known_locations(&fixed_drives()) {
+        let source = DiscoverySource::WellKnown(folder.display().to_string());
+        candidates.extend(Installation::detect(folder, source, None, None));
+    }
+
+    rank_installations(candidates)
+}
+
+/// Merges candidates for the same folder and sorts them: Remastered before 1.16.1 before
+/// PTR, then installs more sources agree on, then by path.
+pub fn rank_installations(candidates: Vec<Installation>) -> Vec<Installation> {
+    let mut installs: Vec<(String, Installation)> = Vec::new();
+
+    for candidate in candidates {
+        let identity = candidate.identity();
+        match installs.iter_mut().find(|(other, _)| *other == identity) {
+            Some((_, install)) => {
+                for source in candidate.sources {
+                    if !install.sources.contains(&source) {
+                        install.sources.push(source);
+                    }
+                }
+                if install.version.is_none() {
+                    install.version = candidate.version;
+                }
+                if install.branch.is_none() {
+                    install.branch = candidate.branch;
+                }
+            }
+            None => installs.push((identity, candidate)),
+        }
+    }
+
+    let mut installs: Vec<Installation> =
+        installs.into_iter().map(|(_, install)| install).collect();
+    installs.sort_by(|a, b| {
+        a.edition
+            .cmp(&b.edition)
+            .then(b.sources.len().cmp(&a.sources.len()))
+            .then_with(|| a.root.cmp(&b.root))
+    });
+    installs
+}
+
+/// Picks an install by its 1-based position in `installs` or by edition.
+pub fn select_installation<'a>(
+    installs: &'a [Installation],
+    choice: &str,
+) -> Result<&'a Installation, String> {
+    if let Ok(number) = choice.trim().parse::<usize>() {
+        return number
+            .checked_sub(1)
+            .and_then(|index| installs.get(index))
+            .ok_or_else(|| {
+                t!(
+                    "install-choice-out-of-range",
+                    number = number,
+                    count = installs.len()
+                )
+            });
+    }
+    let edition: Edition = choice.trim().parse()?;
+    installs
+        .iter()
+        .find(|install| install.edition == edition)
+        .ok_or_else(|| t!("install-edition-missing", edition = edition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+    use crate::util::testing::{write_exe, AMD64, I386};
+
+    fn install(root: &str, edition: Edition, sources: &[&str]) -> Installation {
+        Installation {
+            root: PathBuf::from(root),
+            edition,
+            layout: InstallLayout::PerArch,
+            version: None,
+            branch: None,
+            architectures: vec![Arch::X86, Arch::X86_64],
+            sources: sources
+                .iter()
+                .map(|key| DiscoverySource::UninstallKey(key.to_string()))
+                .collect(),
+        }
+    }
+
+    fn roots(installs: &[Installation]) -> Vec<&str> {
+        installs
+            .iter()
+            .map(|install| install.root.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ptr_is_a_whole_word_or_the_battle_net_uid() {
+        for label in [
+            "C:\\Games\\StarCraft PTR",
+            "D:\\StarCraft-ptr ",
+            "s1_ptr ptr",
+            "C:\\StarCraft StarCraft Public Test",
+        ] {
+            assert_eq!(
+                Installation::remastered_or_ptr(label),
+                Edition::Ptr,
+                "{}",
+                label
+            );
+        }
+        for label in [
+            "C:\\Program Files (x86)\\StarCraft StarCraft",
+            "D:\\Captr\\StarCraft",
+            "E:\\Games\\ptrace StarCraft",
+            "s1 ",
+            "C:\\Public\\Testing\\StarCraft",
+        ] {
+            assert_eq!(
+                Installation::remastered_or_ptr(label),
+                Edition::Remastered,
+                "{}",
+                label
+            );
+        }
+    }
+
+    #[test]
+    fn detect_reads_the_edition_from_the_folder_and_display_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("StarCraft");
+        write_exe(&live.join("x86").join(EXE_NAME), I386);
+        write_exe(&live.join("x86_64").join(EXE_NAME), AMD64);
+        let source = DiscoverySource::WellKnown(live.display().to_string());
+
+        let found = Installation::detect(&live, source.clone(), None, None).unwrap();
+        assert_eq!(found.edition, Edition::Remastered);
+        assert_eq!(found.architectures, [Arch::X86, Arch::X86_64]);
+
+        let found = Installation::detect(&live, source, Some("1.23".into()), Some("StarCraft PTR"))
+            .unwrap();
+        assert_eq!(found.edition, Edition::Ptr);
+        assert_eq!(found.version.as_deref(), Some("1.23"));
+    }
+
+    #[test]
+    fn the_same_folder_is_merged() {
+        let mut first = install("C:\\Games\\StarCraft", Edition::Remastered, &["a"]);
+        first.branch = Some("live".to_string());
+        let mut second = install("c:\\games\\starcraft\\", Edition::Remastered, &["a", "b"]);
+        second.version = Some("1.23.10".to_string());
+        second.branch = Some("ptr".to_string());
+        let other = install("D:\\StarCraft", Edition::Remastered, &["c"]);
+
+        let installs = rank_installations(vec![first, second, other]);
+        assert_eq!(installs.len(), 2);
+        let merged = &installs[0];
+        assert_eq!(merged.root, PathBuf::from("C:\\Games\\StarCraft"));
+        assert_eq!(merged.sources.len(), 2);
+        // The first candidate's details win, gaps are filled from the others
+        assert_eq!(merged.branch.as_deref(), Some("live"));
+        assert_eq!(merged.version.as_deref(), Some("1.23.10"));
+    }
+
+    #[test]
+    fn installs_are_ranked_by_edition_agreement_and_path() {
+        let installs = rank_installations(vec![
+            install("A:\\ptr", Edition::Ptr, &["a", "b", "c"]),
+            install("B:\\legacy", Edition::Legacy, &["a"]),
+            install("C:\\remastered", Edition::Remastered, &["a"]),
+            install("D:\\remastered", Edition::Remastered, &["a", "b"]),
+            install("B:\\remastered", Edition::Remastered, &["a"]),
+        ]);
+        assert_eq!(
+            roots(&installs),
+            [
+                "D:\\remastered",
+                "B:\\remastered",
+                "C:\\remastered",
+                "B:\\legacy",
+                "A:\\ptr"
+            ]
+        );
+    }
+
+    #[test]
+    fn installs_are_selected_by_number_or_edition() {
+        set_language(Lang::En);
+        let installs = vec![
+            install("C:\\remastered", Edition::Remastered, &["a"]),
+            install("C:\\legacy", Edition::Legacy, &["a"]),
+        ];
+
+        let root = |choice: &str| {
+            select_installation(&installs, choice).map(|install| install.root.to_str().unwrap())
+        };
+        assert_eq!(root("1"), Ok("C:\\remastered"));
+        assert_eq!(root(" 2 "), Ok("C:\\legacy"));
+        assert_eq!(root("1.16.1"), Ok("C:\\legacy"));
+        assert_eq!(root("SCR"), Ok("C:\\remastered"));
+        assert!(root("0").is_err());
+        assert!(root("3").is_err());
+        assert!(root("ptr").is_err());
+        assert!(root("beta").is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use windows::Win32::Foundation::HANDLE;
+
+/// A StarCraft process tracked by `GameManager`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameInstance {
+    pub pid: u32,
+    #[cfg(windows)]
+    #[serde(skip)]
+    pub handle: HANDLE,
+    pub label: Option<String>,
+    pub profile: String,
+    /// `true` when the process was already running and got adopted instead of launched
+    pub adopted: bool,
+    pub game_path: Option<PathBuf>,
+}
+
+/// Per-launch settings for `GameManager::launch_game_with`.
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    pub label: Option<String>,
+    pub profile: String,
+    /// Extra command line arguments, passed after `-launch`
+    pub args: Vec<String>,
+    /// Mask of the logical processors the game may run on
+    pub affinity: Option<usize>,
+    /// Which StarCraft.exe may have its instance mutex closed for this launch
+    pub mutex_scope: MutexScope,
+}
+
+/// Where a launch looks for the instance mutex to close.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MutexScope {
+    /// Only the process just launched, so games started by hand are left alone
+    #[default]
+    Launched,
+    /// The first StarCraft.exe on the system whose mutex can be closed
+    System,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            label: None,
+            profile: DEFAULT_PROFILE.to_string(),
+            args: Vec::new(),
+            affinity: None,
+            mutex_scope: MutexScope::default(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Arguments StarCraft.exe is started with.
+    pub fn command_args(&self) -> Vec<&str> {
+        std::iter::once("-launch")
+            .chain(self.args.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+pub const DEFAULT_PROFILE: &str = "default";
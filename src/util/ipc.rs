@@ -0,0 +1,223 @@
+use std::io;
+use std::path::PathBuf;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::instance::GameInstance;
+use super::output::{ErrorClass, ErrorInfo};
+use crate::t;
+
+/// Bumped whenever a request or response changes shape.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Messages a client sends to the daemon, one JSON object per line.
+///
+/// Every connection starts with `Hello`; the daemon answers with its own version and
+/// closes the connection if they differ.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Hello {
+        version: u32,
+    },
+    List,
+    Launch {
+        count: u32,
+        profile: String,
+        labels: Vec<String>,
+        /// Falls back to the path the daemon was started with
+        game_path: Option<PathBuf>,
+    },
+    Kill {
+        selector: String,
+    },
+    Status,
+    /// Starts tracking StarCraft.exe processes the daemon did not launch
+    Adopt,
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Hello {
+        version: u32,
+        pid: u32,
+    },
+    Instances {
+        instances: Vec<GameInstance>,
+    },
+    Launched {
+        pids: Vec<u32>,
+    },
+    Killed {
+        pids: Vec<u32>,
+    },
+    Adopted {
+        pids: Vec<u32>,
+    },
+    Status {
+        instances: Vec<GameInstance>,
+        guard_running: bool,
+        game_path: Option<PathBuf>,
+    },
+    Ok,
+    Error {
+        error: ErrorInfo,
+    },
+}
+
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\sclauncher";
+
+/// Where the daemon listens on Unix-like systems.
+#[cfg(unix)]
+pub fn socket_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("sclauncher.sock")
+}
+
+/// The daemon's answer to the first request of a connection: its own `Hello`, or the error
+/// to send before closing the connection.
+pub fn answer_hello(request: &Request) -> Result<Response, Response> {
+    match request {
+        Request::Hello { version } if *version == PROTOCOL_VERSION => Ok(Response::Hello {
+            version: PROTOCOL_VERSION,
+            pid: std::process::id(),
+        }),
+        Request::Hello { version } => Err(Response::Error {
+            error: ErrorInfo::new(
+                ErrorClass::Daemon,
+                t!(
+                    "daemon-protocol",
+                    requested = *version,
+                    supported = PROTOCOL_VERSION
+                ),
+            ),
+        }),
+        _ => Err(Response::Error {
+            error: ErrorInfo::new(ErrorClass::Daemon, t!("daemon-hello-first")),
+        }),
+    }
+}
+
+/// Writes one message as a single JSON line.
+pub async fn write_message<W, T>(writer: &mut W, message: &T) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await
+}
+
+/// Reads one JSON line, returning `None` when the peer closed the connection.
+pub async fn read_message<R, T>(reader: &mut R) -> io::Result<Option<T>>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(windows)]
+type ClientStream = tokio::net::windows::named_pipe::NamedPipeClient;
+#[cfg(unix)]
+type ClientStream = tokio::net::UnixStream;
+
+/// A connection to a running daemon.
+pub struct IpcClient<S = ClientStream> {
+    stream: BufReader<S>,
+    /// Process id of the daemon
+    pub daemon_pid: u32,
+}
+
+impl IpcClient {
+    /// Connects and performs the version handshake.
+    ///
+    /// Fails with `io::ErrorKind::NotFound` when no daemon is running.
+    pub async fn connect() -> io::Result<Self> {
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(PIPE_NAME)?;
+        #[cfg(unix)]
+        let stream = tokio::net::UnixStream::connect(socket_path()).await?;
+        IpcClient::handshake(stream).await
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> IpcClient<S> {
+    /// Performs the version handshake over an open connection.
+    pub async fn handshake(stream: S) -> io::Result<Self> {
+        let mut client = IpcClient {
+            stream: BufReader::new(stream),
+            daemon_pid: 0,
+        };
+        match client
+            .call(&Request::Hello {
+                version: PROTOCOL_VERSION,
+            })
+            .await?
+        {
+            Response::Hello { version, pid } if version == PROTOCOL_VERSION => {
+                client.daemon_pid = pid;
+                Ok(client)
+            }
+            Response::Error { error } => Err(io::Error::other(error.message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected handshake response: {:?}", other),
+            )),
+        }
+    }
+
+    /// Sends one request and waits for its response.
+    pub async fn call(&mut self, request: &Request) -> io::Result<Response> {
+        write_message(self.stream.get_mut(), request).await?;
+        read_message(&mut self.stream)
+            .await?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, t!("daemon-closed")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{duplex, split};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn client_reports_a_refused_handshake() {
+        let (client, daemon) = duplex(4096);
+        tokio::spawn(async move {
+            let (reader, mut writer) = split(daemon);
+            let mut reader = BufReader::new(reader);
+            let _ = read_message::<_, Request>(&mut reader).await;
+            let refusal = answer_hello(&Request::Hello { version: 0 }).unwrap_err();
+            write_message(&mut writer, &refusal).await.unwrap();
+        });
+
+        assert!(IpcClient::handshake(client).await.is_err());
+    }
+
+    #[test]
+    fn hello_must_come_first() {
+        assert!(answer_hello(&Request::List).is_err());
+        assert!(matches!(
+            answer_hello(&Request::Hello {
+                version: PROTOCOL_VERSION
+            }),
+            Ok(Response::Hello { .. })
+        ));
+    }
+}
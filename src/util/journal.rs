@@ -0,0 +1,296 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use winreg::{enums::*, RegKey};
+
+/// Hive every journaled change is made in; the launcher only writes machine-wide keys.
+const HIVE_NAME: &str = "HKEY_LOCAL_MACHINE";
+
+/// One registry write and what was there before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryChange {
+    pub id: u64,
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    /// Key below HKEY_LOCAL_MACHINE, e.g. `SOFTWARE\...\Uninstall\StarCraft`
+    pub key_path: String,
+    pub value_name: String,
+    /// `None` when the value did not exist
+    pub previous: Option<String>,
+    pub value: String,
+    /// Whether the key itself was created by this change
+    pub key_created: bool,
+    /// Whether `registry restore` already rolled this change back
+    pub restored: bool,
+}
+
+/// Every registry write the launcher made, so each one can be rolled back.
+///
+/// The journal is written before the registry is, so a crash in between leaves an entry
+/// whose restore is a no-op rather than a change nobody knows about.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    path: PathBuf,
+    changes: Vec<RegistryChange>,
+}
+
+impl Journal {
+    /// Reads the journal at `path`, empty when it does not exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Journal> {
+        let path = path.into();
+        let changes = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Journal { path, changes })
+    }
+
+    /// The journal at [`journal_path`].
+    pub fn open_default() -> io::Result<Journal> {
+        let path = journal_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "%ProgramData% is not set"))?;
+        Journal::open(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn changes(&self) -> &[RegistryChange] {
+        &self.changes
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.changes)?)
+    }
+
+    /// Writes a string value under HKLM, creating the key if needed, and records the change.
+    pub fn set_value(
+        &mut self,
+        key_path: &str,
+        value_name: &str,
+        value: &str,
+    ) -> io::Result<RegistryChange> {
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+        let existing = hklm.open_subkey_with_flags(key_path, KEY_READ).ok();
+        let previous = existing
+            .as_ref()
+            .and_then(|key| key.get_value::<String, _>(value_name).ok());
+
+        let change = RegistryChange {
+            id: self
+                .changes
+                .iter()
+                .map(|change| change.id)
+                .max()
+                .unwrap_or(0)
+                + 1,
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            key_path: key_path.to_string(),
+            value_name: value_name.to_string(),
+            previous,
+            value: value.to_string(),
+            key_created: existing.is_none(),
+            restored: false,
+        };
+        self.changes.push(change.clone());
+        self.save()?;
+
+        let written = hklm
+            .create_subkey_with_flags(key_path, KEY_ALL_ACCESS)
+            .and_then(|(key, _)| key.set_value(value_name, &value));
+        if let Err(e) = written {
+            // Nothing changed, so nothing to restore
+            self.changes.pop();
+            self.save()?;
+            return Err(e);
+        }
+        Ok(change)
+    }
+
+    /// Rolls back one change: puts the previous value back, or removes the value and the key
+    /// when the change created them.
+    pub fn restore(&mut self, id: u64) -> io::Result<RegistryChange> {
+        let index = self
+            .changes
+            .iter()
+            .position(|change| change.id == id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No change {}", id)))?;
+        let change = self.changes[index].clone();
+
+        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+        match &change.previous {
+            Some(previous) => {
+                let (key, _) = hklm.create_subkey_with_flags(&change.key_path, KEY_ALL_ACCESS)?;
+                key.set_value(&change.value_name, previous)?;
+            }
+            None => {
+                if let Ok(key) = hklm.open_subkey_with_flags(&change.key_path, KEY_ALL_ACCESS) {
+                    match key.delete_value(&change.value_name) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                        _ => {}
+                    }
+                    // Only remove a key we created and nothing else has filled since
+                    let info = key.query_info()?;
+                    if change.key_created && info.sub_keys == 0 && info.values == 0 {
+                        drop(key);
+                        hklm.delete_subkey(&change.key_path)?;
+                    }
+                }
+            }
+        }
+
+        self.changes[index].restored = true;
+        self.save()?;
+        Ok(self.changes[index].clone())
+    }
+
+    /// Rolls back every change not restored yet, newest first.
+    pub fn restore_all(&mut self) -> io::Result<Vec<RegistryChange>> {
+        let mut ids: Vec<u64> = self
+            .changes
+            .iter()
+            .filter(|change| !change.restored)
+            .map(|change| change.id)
+            .collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        ids.into_iter().map(|id| self.restore(id)).collect()
+    }
+
+    /// The journal as a `.reg` file: the values as written, or with `undo` the state
+    /// before the changes, for importing with `reg import` or regedit.
+    ///
+    /// Unlike [`Journal::restore`], the undo file cannot check whether a key the launcher
+    /// created is empty again, so it removes the values and keeps the keys.
+    pub fn to_reg(&self, undo: bool) -> String {
+        let mut lines = vec!["Windows Registry Editor Version 5.00".to_string()];
+        // Undoing goes newest first and skips what was already restored
+        let changes: Vec<&RegistryChange> = if undo {
+            self.changes
+                .iter()
+                .rev()
+                .filter(|change| !change.restored)
+                .collect()
+        } else {
+            self.changes.iter().collect()
+        };
+
+        for change in changes {
+            let key = format!("{}\\{}", HIVE_NAME, change.key_path);
+            lines.push(String::new());
+            lines.push(format!(
+                "; {} #{}",
+                if undo { "undo" } else { "change" },
+                change.id
+            ));
+            // A created key is left in place: `[-key]` would take everything else stored
+            // there since with it, so only the value is removed
+            lines.push(format!("[{}]", key));
+            let value = match (undo, &change.previous) {
+                (false, _) => reg_string(&change.value),
+                (true, Some(previous)) => reg_string(previous),
+                (true, None) => "-".to_string(),
+            };
+            lines.push(format!("{}={}", reg_string(&change.value_name), value));
+        }
+        lines.push(String::new());
+        lines.join("\r\n")
+    }
+
+    /// Writes [`Journal::to_reg`] as regedit does: UTF-16 with a byte order mark.
+    pub fn export_reg(&self, path: &Path, undo: bool) -> io::Result<()> {
+        let mut bytes = vec![0xff, 0xfe];
+        for unit in self.to_reg(undo).encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+}
+
+/// A quoted `.reg` string with backslashes and quotes escaped.
+fn reg_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `%ProgramData%\sclauncher\registry-journal.json`, next to the machine-wide config, as the
+/// changes are machine-wide too.
+pub fn journal_path() -> Option<PathBuf> {
+    std::env::var_os("ProgramData").map(|dir| {
+        PathBuf::from(dir)
+            .join("sclauncher")
+            .join("registry-journal.json")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(id: u64, previous: Option<&str>, key_created: bool) -> RegistryChange {
+        RegistryChange {
+            id,
+            time: 0,
+            key_path: r"SOFTWARE\Blizzard Entertainment\StarCraft".to_string(),
+            value_name: "InstallPath".to_string(),
+            previous: previous.map(str::to_string),
+            value: r"D:\Games\StarCraft".to_string(),
+            key_created,
+            restored: false,
+        }
+    }
+
+    fn journal(changes: Vec<RegistryChange>) -> Journal {
+        Journal {
+            path: PathBuf::new(),
+            changes,
+        }
+    }
+
+    #[test]
+    fn changes_are_exported_as_written() {
+        let reg = journal(vec![change(1, None, true)]).to_reg(false);
+        assert_eq!(
+            reg,
+            "Windows Registry Editor Version 5.00\r\n\r\n; change #1\r\n\
+             [HKEY_LOCAL_MACHINE\\SOFTWARE\\Blizzard Entertainment\\StarCraft]\r\n\
+             \"InstallPath\"=\"D:\\\\Games\\\\StarCraft\"\r\n"
+        );
+    }
+
+    #[test]
+    fn undo_removes_created_values_without_deleting_the_key() {
+        let reg = journal(vec![change(1, None, true)]).to_reg(true);
+        assert!(reg
+            .contains("\r\n[HKEY_LOCAL_MACHINE\\SOFTWARE\\Blizzard Entertainment\\StarCraft]\r\n"));
+        assert!(reg.contains("\r\n\"InstallPath\"=-\r\n"));
+        assert!(!reg.contains("[-"));
+    }
+
+    #[test]
+    fn undo_puts_previous_values_back_newest_first() {
+        let mut restored = change(3, Some("ignored"), false);
+        restored.restored = true;
+        let reg = journal(vec![
+            change(1, Some(r"C:\Old"), false),
+            change(2, Some(r"C:\Older"), false),
+            restored,
+        ])
+        .to_reg(true);
+
+        let second = reg.find("; undo #2").unwrap();
+        let first = reg.find("; undo #1").unwrap();
+        assert!(second < first);
+        assert!(!reg.contains("; undo #3"));
+        assert!(reg.contains("\"InstallPath\"=\"C:\\\\Older\""));
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::config::Arch;
+
+pub const EXE_NAME: &str = "StarCraft.exe";
+
+/// The 1.16.1 client keeps Blizzard's runtime next to StarCraft.exe.
+const LEGACY_MARKER: &str = "storm.dll";
+
+const ARCHES: [Arch; 2] = [Arch::X86, Arch::X86_64];
+
+/// How the files of a StarCraft install are arranged on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallLayout {
+    /// `x86\StarCraft.exe` and `x86_64\StarCraft.exe`, as Battle.net installs Remastered
+    PerArch,
+    /// `StarCraft.exe` next to `storm.dll` in the root, the classic 1.16.1 client
+    Legacy,
+    /// `StarCraft.exe` alone in the root, as some repacks ship it
+    Flat,
+}
+
+impl InstallLayout {
+    /// Recognises the layout of `root` by its files, `None` when it holds no StarCraft.exe.
+    pub fn detect(root: &Path) -> Option<InstallLayout> {
+        if ARCHES
+            .iter()
+            .any(|&arch| InstallLayout::PerArch.exe(root, arch).is_file())
+        {
+            Some(InstallLayout::PerArch)
+        } else if !root.join(EXE_NAME).is_file() {
+            None
+        } else if root.join(LEGACY_MARKER).is_file() {
+            Some(InstallLayout::Legacy)
+        } else {
+            Some(InstallLayout::Flat)
+        }
+    }
+
+    /// Whether each architecture has its own StarCraft.exe. Otherwise the one in the root is
+    /// built for a single architecture, which only its PE header tells.
+    pub fn per_arch(self) -> bool {
+        self == InstallLayout::PerArch
+    }
+
+    /// Where this layout keeps the StarCraft.exe for `arch`.
+    pub fn exe(self, root: &Path, arch: Arch) -> PathBuf {
+        match self {
+            InstallLayout::PerArch => root.join(arch.folder()).join(EXE_NAME),
+            InstallLayout::Legacy | InstallLayout::Flat => root.join(EXE_NAME),
+        }
+    }
+
+    /// Every StarCraft.exe this layout can have under `root`, whether it exists or not.
+    pub fn exes(self, root: &Path) -> Vec<PathBuf> {
+        match self {
+            InstallLayout::PerArch => ARCHES.iter().map(|&arch| self.exe(root, arch)).collect(),
+            InstallLayout::Legacy | InstallLayout::Flat => vec![root.join(EXE_NAME)],
+        }
+    }
+}
+
+impl fmt::Display for InstallLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InstallLayout::PerArch => "per-arch",
+            InstallLayout::Legacy => "legacy",
+            InstallLayout::Flat => "flat",
+        })
+    }
+}
+
+/// The install folder `path` points into: the folder itself, or the one holding it when it
+/// is a StarCraft.exe, and one level up from an `x86` or `x86_64` folder.
+pub fn install_root(path: &Path) -> PathBuf {
+    let is_exe = path
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case(EXE_NAME));
+    let folder = match path.parent() {
+        Some(parent) if is_exe => parent,
+        _ => path,
+    };
+
+    let is_arch_folder = folder.file_name().is_some_and(|name| {
+        ARCHES
+            .iter()
+            .any(|arch| name.eq_ignore_ascii_case(arch.folder()))
+    });
+    match folder.parent() {
+        Some(root) if is_arch_folder => root.to_path_buf(),
+        _ => folder.to_path_buf(),
+    }
+}
+
+/// The install root and layout of a folder or StarCraft.exe, e.g. `D:\Games\StarCraft`,
+/// `D:\Games\StarCraft\x86_64\StarCraft.exe` or `D:\Starcraft 1.16.1\StarCraft.exe`.
+pub fn resolve(path: &Path) -> Option<(PathBuf, InstallLayout)> {
+    let root = install_root(path);
+    InstallLayout::detect(&root).map(|layout| (root, layout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::testing::install_tree;
+
+    #[test]
+    fn layouts_are_told_apart_by_their_files() {
+        let per_arch = install_tree(&["x86_64/StarCraft.exe", "x86/StarCraft.exe"]);
+        let legacy = install_tree(&["StarCraft.exe", "storm.dll"]);
+        let flat = install_tree(&["StarCraft.exe"]);
+        let empty = install_tree(&["x86/storm.dll"]);
+        let (per_arch, legacy, flat) = (per_arch.path(), legacy.path(), flat.path());
+
+        assert_eq!(
+            InstallLayout::detect(per_arch),
+            Some(InstallLayout::PerArch)
+        );
+        assert_eq!(InstallLayout::detect(legacy), Some(InstallLayout::Legacy));
+        assert_eq!(InstallLayout::detect(flat), Some(InstallLayout::Flat));
+        assert_eq!(InstallLayout::detect(empty.path()), None);
+
+        assert_eq!(
+            InstallLayout::PerArch.exe(per_arch, Arch::X86_64),
+            per_arch.join("x86_64").join(EXE_NAME)
+        );
+        assert_eq!(
+            InstallLayout::Legacy.exe(legacy, Arch::X86_64),
+            legacy.join(EXE_NAME)
+        );
+        assert_eq!(InstallLayout::Flat.exes(flat), [flat.join(EXE_NAME)]);
+    }
+
+    #[test]
+    fn one_architecture_is_enough_for_per_arch() {
+        let root = install_tree(&["x86_64/StarCraft.exe"]);
+        assert_eq!(
+            InstallLayout::detect(root.path()),
+            Some(InstallLayout::PerArch)
+        );
+        assert_eq!(InstallLayout::PerArch.exes(root.path()).len(), 2);
+    }
+
+    #[test]
+    fn paths_into_an_install_lead_to_its_root() {
+        let root = PathBuf::from("Games").join("StarCraft");
+        assert_eq!(install_root(&root), root);
+        assert_eq!(install_root(&root.join("StarCraft.exe")), root);
+        assert_eq!(install_root(&root.join("starcraft.EXE")), root);
+        assert_eq!(install_root(&root.join("x86_64")), root);
+        assert_eq!(install_root(&root.join("X86").join("StarCraft.exe")), root);
+        // Only an exe or an architecture folder is stepped out of
+        let maps = root.join("Maps");
+        assert_eq!(install_root(&maps), maps);
+    }
+
+    #[test]
+    fn resolve_finds_root_and_layout_from_any_path() {
+        let per_arch = install_tree(&["x86/StarCraft.exe"]);
+        let legacy = install_tree(&["StarCraft.exe", "storm.dll"]);
+        let (per_arch, legacy) = (per_arch.path(), legacy.path());
+
+        assert_eq!(
+            resolve(&per_arch.join("x86").join(EXE_NAME)),
+            Some((per_arch.to_path_buf(), InstallLayout::PerArch))
+        );
+        assert_eq!(
+            resolve(&legacy.join(EXE_NAME)),
+            Some((legacy.to_path_buf(), InstallLayout::Legacy))
+        );
+        assert_eq!(resolve(&legacy.join("missing")), None);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+#[cfg(windows)]
+use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+use crate::t;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Maximum number of running games when nothing else is configured.
+pub const DEFAULT_MAX_INSTANCES: u32 = 8;
+
+/// Source of machine numbers used by the launch guard, so it can be fed fake values.
+pub trait SystemInfo {
+    /// Physical memory currently available, in bytes.
+    fn available_memory(&self) -> u64;
+}
+
+/// `SystemInfo` backed by `GlobalMemoryStatusEx`.
+#[cfg(windows)]
+pub struct WindowsSystemInfo;
+
+#[cfg(windows)]
+impl SystemInfo for WindowsSystemInfo {
+    fn available_memory(&self) -> u64 {
+        let mut status = MEMORYSTATUSEX {
+            dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+            ..Default::default()
+        };
+        match unsafe { GlobalMemoryStatusEx(&mut status) } {
+            Ok(()) => status.ullAvailPhys,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// What to do when a launch would go over a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardPolicy {
+    Refuse,
+    Warn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LimitViolation {
+    TooManyGames {
+        running: u32,
+        requested: u32,
+        max: u32,
+    },
+    TooManyInstances {
+        profile: String,
+        running: u32,
+        requested: u32,
+        max: u32,
+    },
+    LowMemory {
+        available: u64,
+        required: u64,
+    },
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitViolation::TooManyGames {
+                running,
+                requested,
+                max,
+            } => f.write_str(&t!(
+                "limit-too-many-games",
+                max = max,
+                running = running,
+                requested = requested
+            )),
+            LimitViolation::TooManyInstances {
+                profile,
+                running,
+                requested,
+                max,
+            } => f.write_str(&t!(
+                "limit-too-many",
+                profile = profile,
+                max = max,
+                running = running,
+                requested = requested
+            )),
+            LimitViolation::LowMemory {
+                available,
+                required,
+            } => f.write_str(&t!(
+                "limit-low-memory",
+                available = available / MIB,
+                required = required / MIB
+            )),
+        }
+    }
+}
+
+/// How many games are running when a launch is checked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Running {
+    /// Every StarCraft.exe on the machine
+    pub total: u32,
+    /// Games of the profile being launched, `None` when no daemon or manager tracks profiles
+    pub in_profile: Option<u32>,
+}
+
+/// Guardrails checked before launching games.
+#[derive(Debug, Clone)]
+pub struct LaunchLimits {
+    /// Maximum number of StarCraft.exe running at once, whatever their profile
+    pub max_instances: u32,
+    /// Maximum number of games of one profile, for the profiles listed
+    pub profile_max_instances: HashMap<String, u32>,
+    /// Estimated memory one StarCraft instance needs, in bytes
+    pub memory_per_instance: u64,
+    /// Memory that must stay free after all requested instances are up, in bytes
+    pub min_free_memory: u64,
+    pub policy: GuardPolicy,
+}
+
+impl Default for LaunchLimits {
+    fn default() -> Self {
+        LaunchLimits {
+            max_instances: DEFAULT_MAX_INSTANCES,
+            profile_max_instances: HashMap::new(),
+            memory_per_instance: 512 * MIB,
+            min_free_memory: 1024 * MIB,
+            policy: GuardPolicy::Refuse,
+        }
+    }
+}
+
+impl LaunchLimits {
+    /// Checks whether `requested` more instances of `profile` may be launched.
+    ///
+    /// A per-profile maximum is only checked when `running.in_profile` is known; StarCraft.exe
+    /// itself does not say which profile it was launched with.
+    ///
+    /// # Returns
+    /// * `Ok(warnings)` when the launch may go ahead (violations are only warnings under `GuardPolicy::Warn`).
+    /// * `Err(violations)` when the launch must be refused.
+    pub fn check(
+        &self,
+        system: &dyn SystemInfo,
+        profile: &str,
+        running: Running,
+        requested: u32,
+    ) -> Result<Vec<LimitViolation>, Vec<LimitViolation>> {
+        let mut violations = Vec::new();
+
+        if running.total.saturating_add(requested) > self.max_instances {
+            violations.push(LimitViolation::TooManyGames {
+                running: running.total,
+                requested,
+                max: self.max_instances,
+            });
+        }
+        let profile_max = self.profile_max_instances.get(profile).copied();
+        if let (Some(max), Some(in_profile)) = (profile_max, running.in_profile) {
+            if in_profile.saturating_add(requested) > max {
+                violations.push(LimitViolation::TooManyInstances {
+                    profile: profile.to_string(),
+                    running: in_profile,
+                    requested,
+                    max,
+                });
+            }
+        }
+
+        let required = self
+            .memory_per_instance
+            .saturating_mul(requested as u64)
+            .saturating_add(self.min_free_memory);
+        let available = system.available_memory();
+        if available < required {
+            violations.push(LimitViolation::LowMemory {
+                available,
+                required,
+            });
+        }
+
+        if violations.is_empty() || self.policy == GuardPolicy::Warn {
+            Ok(violations)
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeSystem {
+        available: u64,
+    }
+
+    impl SystemInfo for FakeSystem {
+        fn available_memory(&self) -> u64 {
+            self.available
+        }
+    }
+
+    const PLENTY: FakeSystem = FakeSystem {
+        available: 64 * 1024 * MIB,
+    };
+
+    fn running(total: u32, in_profile: Option<u32>) -> Running {
+        Running { total, in_profile }
+    }
+
+    #[test]
+    fn launches_within_the_limits_go_ahead() {
+        let limits = LaunchLimits::default();
+        assert_eq!(
+            limits.check(&PLENTY, "default", running(2, Some(2)), 3),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn too_many_games_are_refused() {
+        let limits = LaunchLimits::default();
+        assert_eq!(
+            limits.check(&PLENTY, "default", running(6, None), 3),
+            Err(vec![LimitViolation::TooManyGames {
+                running: 6,
+                requested: 3,
+                max: 8,
+            }])
+        );
+    }
+
+    #[test]
+    fn profiles_count_only_their_own_games() {
+        let limits = LaunchLimits {
+            profile_max_instances: HashMap::from([("ladder".to_string(), 2)]),
+            ..LaunchLimits::default()
+        };
+        // Five games of other profiles do not use up the ladder's two
+        assert!(limits
+            .check(&PLENTY, "ladder", running(5, Some(0)), 2)
+            .is_ok());
+        assert_eq!(
+            limits.check(&PLENTY, "ladder", running(5, Some(1)), 2),
+            Err(vec![LimitViolation::TooManyInstances {
+                profile: "ladder".to_string(),
+                running: 1,
+                requested: 2,
+                max: 2,
+            }])
+        );
+        // Profiles without an entry only have the machine-wide cap
+        assert!(limits
+            .check(&PLENTY, "practice", running(5, Some(5)), 2)
+            .is_ok());
+    }
+
+    #[test]
+    fn unknown_profile_counts_only_check_the_total() {
+        let limits = LaunchLimits {
+            profile_max_instances: HashMap::from([("ladder".to_string(), 1)]),
+            ..LaunchLimits::default()
+        };
+        assert!(limits.check(&PLENTY, "ladder", running(1, None), 2).is_ok());
+        assert!(limits
+            .check(&PLENTY, "ladder", running(7, None), 2)
+            .is_err());
+    }
+
+    #[test]
+    fn low_memory_counts_every_requested_instance() {
+        let limits = LaunchLimits::default();
+        // Two games need 2 * 512 MiB plus the 1 GiB that must stay free
+        let system = FakeSystem {
+            available: 2048 * MIB - 1,
+        };
+        assert_eq!(
+            limits.check(&system, "default", Running::default(), 2),
+            Err(vec![LimitViolation::LowMemory {
+                available: 2048 * MIB - 1,
+                required: 2048 * MIB,
+            }])
+        );
+        assert!(limits
+            .check(&system, "default", Running::default(), 1)
+            .is_ok());
+    }
+
+    #[test]
+    fn warn_policy_reports_violations_without_refusing() {
+        let limits = LaunchLimits {
+            policy: GuardPolicy::Warn,
+            ..LaunchLimits::default()
+        };
+        let system = FakeSystem { available: 0 };
+        let warnings = limits
+            .check(&system, "default", running(8, None), u32::MAX)
+            .unwrap();
+        assert_eq!(warnings.len(), 2);
+    }
+}
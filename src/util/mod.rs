@@ -1,4 +1,40 @@
+#[cfg(windows)]
 pub mod admin;
+pub mod battlenet;
+pub mod config;
+pub mod console;
+#[cfg(windows)]
 pub mod custom_windows;
+pub mod daemon;
+pub mod doctor;
+pub mod events;
+pub mod fsscan;
+#[cfg(windows)]
 pub mod game;
+#[cfg(windows)]
+pub mod handles;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod i18n;
+pub mod install;
+pub mod instance;
+pub mod ipc;
+#[cfg(windows)]
+pub mod journal;
+pub mod layout;
+pub mod limits;
+pub mod name_query;
+pub mod object_types;
+pub mod output;
+pub mod pattern;
+pub mod pe;
+pub mod plan;
+pub mod portable;
+#[cfg(windows)]
 pub mod reg;
+#[cfg(windows)]
+pub mod scan;
+pub mod selector;
+#[cfg(test)]
+mod testing;
+pub mod window;
@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How long one object name query may take before its worker is given up on.
+pub const NAME_QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A name query that did not return within the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameQueryHung;
+
+/// Runs object name queries on a worker thread.
+///
+/// `NtQueryObject` blocks for good on some synchronous pipes, so a query that does not
+/// return in time is left behind with its thread and the next one gets a fresh thread.
+/// A handle that hung once is not asked again.
+pub struct NameQueries {
+    /// Reads the name behind a handle of this process
+    query: fn(isize) -> Option<String>,
+    timeout: Duration,
+    worker: Option<NameWorker>,
+    /// Handles whose query hung, by pid and handle value
+    hung: BTreeSet<(u32, usize)>,
+}
+
+impl NameQueries {
+    pub const fn new(query: fn(isize) -> Option<String>, timeout: Duration) -> Self {
+        NameQueries {
+            query,
+            timeout,
+            worker: None,
+            hung: BTreeSet::new(),
+        }
+    }
+
+    /// Whether the query for `handle` of process `pid` hung before.
+    pub fn is_hung(&self, pid: u32, handle: usize) -> bool {
+        self.hung.contains(&(pid, handle))
+    }
+
+    /// Asks for the name behind `local`, this process's copy of `handle` of process `pid`.
+    ///
+    /// After `NameQueryHung` the stuck thread still uses `local`, so it must not be closed.
+    pub fn query(
+        &mut self,
+        pid: u32,
+        handle: usize,
+        local: isize,
+    ) -> Result<Option<String>, NameQueryHung> {
+        if self.is_hung(pid, handle) {
+            return Err(NameQueryHung);
+        }
+        if self.worker.is_none() {
+            self.worker = NameWorker::spawn(self.query);
+        }
+        let Some(worker) = self.worker.as_ref() else {
+            // Without a thread to spare, ask directly
+            return Ok((self.query)(local));
+        };
+
+        match worker.query(local, self.timeout) {
+            Ok(name) => Ok(name),
+            Err(NameQueryHung) => {
+                self.worker = None;
+                self.hung.insert((pid, handle));
+                Err(NameQueryHung)
+            }
+        }
+    }
+}
+
+/// A thread that answers name queries until its channel is dropped.
+struct NameWorker {
+    requests: mpsc::Sender<isize>,
+    replies: mpsc::Receiver<Option<String>>,
+}
+
+impl NameWorker {
+    fn spawn(query: fn(isize) -> Option<String>) -> Option<NameWorker> {
+        let (requests, handles) = mpsc::channel::<isize>();
+        let (names, replies) = mpsc::channel();
+        thread::Builder::new()
+            .name("object-name-query".to_string())
+            .spawn(move || {
+                for handle in handles {
+                    if names.send(query(handle)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .ok()?;
+        Some(NameWorker { requests, replies })
+    }
+
+    fn query(&self, handle: isize, timeout: Duration) -> Result<Option<String>, NameQueryHung> {
+        self.requests.send(handle).map_err(|_| NameQueryHung)?;
+        self.replies
+            .recv_timeout(timeout)
+            .map_err(|_| NameQueryHung)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use super::*;
+
+    /// The handle [`blocking_query`] never returns for.
+    const STUCK: isize = 13;
+
+    /// Queries [`blocking_query`] was asked for [`STUCK`].
+    static STUCK_QUERIES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Names handles `\Sessions\<handle>`, except [`STUCK`], which blocks like a pipe.
+    fn blocking_query(handle: isize) -> Option<String> {
+        if handle == STUCK {
+            STUCK_QUERIES.fetch_add(1, Ordering::SeqCst);
+            loop {
+                thread::park();
+            }
+        }
+        (handle != 0).then(|| format!("\\Sessions\\{}", handle))
+    }
+
+    #[test]
+    fn names_come_from_the_worker() {
+        let mut queries = NameQueries::new(blocking_query, NAME_QUERY_TIMEOUT);
+        assert_eq!(
+            queries.query(1, 4, 4),
+            Ok(Some("\\Sessions\\4".to_string()))
+        );
+        assert_eq!(queries.query(1, 8, 0), Ok(None));
+        assert!(!queries.is_hung(1, 4));
+    }
+
+    #[test]
+    fn a_hung_query_is_abandoned_and_never_repeated() {
+        let mut queries = NameQueries::new(blocking_query, NAME_QUERY_TIMEOUT);
+        assert!(queries.query(7, 0x10, 1).is_ok());
+
+        let started = Instant::now();
+        assert_eq!(queries.query(7, 0x20, STUCK), Err(NameQueryHung));
+        let waited = started.elapsed();
+        assert!(waited >= NAME_QUERY_TIMEOUT, "gave up after {:?}", waited);
+        assert!(
+            waited < NAME_QUERY_TIMEOUT * 4,
+            "gave up after {:?}",
+            waited
+        );
+        assert!(queries.is_hung(7, 0x20));
+        assert_eq!(STUCK_QUERIES.load(Ordering::SeqCst), 1);
+
+        // The next scan skips the handle without asking or waiting
+        let started = Instant::now();
+        assert_eq!(queries.query(7, 0x20, STUCK), Err(NameQueryHung));
+        assert!(started.elapsed() < NAME_QUERY_TIMEOUT);
+        assert_eq!(STUCK_QUERIES.load(Ordering::SeqCst), 1);
+
+        // A fresh worker answers the other handles, including the same value in another process
+        assert_eq!(
+            queries.query(7, 0x30, 2),
+            Ok(Some("\\Sessions\\2".to_string()))
+        );
+        assert!(!queries.is_hung(8, 0x20));
+    }
+}
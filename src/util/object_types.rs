@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+/// Type of the handles StarCraft's instance mutex is reached through.
+pub const MUTANT_TYPE: &str = "Mutant";
+
+/// Object type names by the index handle tables refer to them with, as listed by
+/// `NtQueryObject(ObjectTypesInformation)`. Indexes only change with a reboot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectTypeMap {
+    names: BTreeMap<u32, String>,
+}
+
+impl ObjectTypeMap {
+    pub fn name(&self, index: u32) -> Option<&str> {
+        self.names.get(&index).map(String::as_str)
+    }
+
+    /// The index of a type, compared without case.
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .find(|(_, type_name)| type_name.eq_ignore_ascii_case(name))
+            .map(|(&index, _)| index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Parses an `OBJECT_TYPES_INFORMATION` buffer as written by a process whose pointers are
+    /// `pointer_size` bytes: a `u32` count, then one `OBJECT_TYPE_INFORMATION` per type, each
+    /// followed by its name and aligned to a pointer.
+    ///
+    /// Before Windows 8.1 `TypeIndex` is zero, and the index is the position plus two. Only
+    /// 4 and 8 byte pointers exist on Windows; any other `pointer_size` is refused.
+    pub fn parse(buffer: &[u8], pointer_size: usize) -> Result<ObjectTypeMap, String> {
+        if !matches!(pointer_size, 4 | 8) {
+            return Err(format!("Unsupported pointer size {}", pointer_size));
+        }
+        // UNICODE_STRING is two u16 lengths and a pointer, padded to pointer alignment
+        let unicode_string = pointer_size * 2;
+        // TypeName, 12 counters, InvalidAttributes, GENERIC_MAPPING, ValidAccessMask, then
+        // SecurityRequired, MaintainHandleCount and TypeIndex as bytes
+        let type_index_offset = unicode_string + 12 * 4 + 4 + 16 + 4 + 2;
+        // ...ReservedByte, PoolType and the two default charges
+        let entry_size = (type_index_offset + 2 + 3 * 4).next_multiple_of(pointer_size);
+
+        let count = read_u32(buffer, 0).ok_or("Truncated type count")?;
+        let mut names = BTreeMap::new();
+        let mut offset = 4usize.next_multiple_of(pointer_size);
+        for position in 0..count {
+            let length = read_u16(buffer, offset).ok_or("Truncated type entry")? as usize;
+            let maximum_length = read_u16(buffer, offset + 2).ok_or("Truncated type entry")?;
+            let type_index = *buffer
+                .get(offset + type_index_offset)
+                .ok_or("Truncated type entry")?;
+
+            let name_start = offset + entry_size;
+            let name = buffer
+                .get(name_start..name_start + length)
+                .ok_or("Truncated type name")?;
+            let name: Vec<u16> = name
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+
+            let index = match type_index {
+                0 => position + 2,
+                index => u32::from(index),
+            };
+            names.insert(index, String::from_utf16_lossy(&name));
+            offset = (name_start + maximum_length as usize).next_multiple_of(pointer_size);
+        }
+        Ok(ObjectTypeMap { names })
+    }
+}
+
+fn read_u16(buffer: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buffer.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buffer.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `OBJECT_TYPE_INFORMATION` is 0x68 bytes on x64 and 0x60 on x86, with `TypeIndex` at
+    /// 0x5a and 0x52.
+    fn layout(pointer_size: usize) -> (usize, usize) {
+        match pointer_size {
+            8 => (0x68, 0x5a),
+            4 => (0x60, 0x52),
+            _ => unreachable!(),
+        }
+    }
+
+    /// An `ObjectTypesInformation` buffer as Windows writes it for the given types.
+    fn fixture(pointer_size: usize, types: &[(&str, u8)]) -> Vec<u8> {
+        let (entry_size, type_index_offset) = layout(pointer_size);
+        let mut buffer = (types.len() as u32).to_le_bytes().to_vec();
+        buffer.resize(pointer_size, 0);
+
+        for (name, type_index) in types {
+            let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            let mut entry = vec![0; entry_size];
+            entry[0..2].copy_from_slice(&(name.len() as u16).to_le_bytes());
+            entry[2..4].copy_from_slice(&(name.len() as u16 + 2).to_le_bytes());
+            entry[type_index_offset] = *type_index;
+            buffer.extend_from_slice(&entry);
+            buffer.extend_from_slice(&name);
+            buffer.extend_from_slice(&[0, 0]);
+            buffer.resize(buffer.len().next_multiple_of(pointer_size), 0);
+        }
+        buffer
+    }
+
+    const TYPES: [(&str, u8); 4] = [("Type", 2), ("Directory", 3), ("File", 37), ("Mutant", 17)];
+
+    #[test]
+    fn parses_x64_buffers() {
+        let types = ObjectTypeMap::parse(&fixture(8, &TYPES), 8).unwrap();
+        assert_eq!(types.len(), 4);
+        assert_eq!(types.name(17), Some(MUTANT_TYPE));
+        assert_eq!(types.name(37), Some("File"));
+        assert_eq!(types.index_of("mutant"), Some(17));
+    }
+
+    #[test]
+    fn parses_x86_buffers() {
+        let types = ObjectTypeMap::parse(&fixture(4, &TYPES), 4).unwrap();
+        assert_eq!(types.len(), 4);
+        assert_eq!(types.name(3), Some("Directory"));
+        assert_eq!(types.index_of(MUTANT_TYPE), Some(17));
+    }
+
+    #[test]
+    fn numbers_types_by_position_before_windows_8_1() {
+        let types = [("Type", 0), ("Directory", 0), ("Mutant", 0)];
+        let types = ObjectTypeMap::parse(&fixture(8, &types), 8).unwrap();
+        assert_eq!(types.name(2), Some("Type"));
+        assert_eq!(types.name(3), Some("Directory"));
+        assert_eq!(types.index_of(MUTANT_TYPE), Some(4));
+    }
+
+    #[test]
+    fn refuses_truncated_buffers() {
+        let buffer = fixture(8, &TYPES);
+        assert!(ObjectTypeMap::parse(&[], 8).is_err());
+        assert!(ObjectTypeMap::parse(&buffer[..2], 8).is_err());
+        // Inside the first entry, then inside the last name
+        assert!(ObjectTypeMap::parse(&buffer[..40], 8).is_err());
+        assert!(ObjectTypeMap::parse(&buffer[..buffer.len() - 8], 8).is_err());
+    }
+
+    #[test]
+    fn refuses_pointer_sizes_windows_does_not_have() {
+        let buffer = fixture(8, &TYPES);
+        assert!(ObjectTypeMap::parse(&buffer, 0).is_err());
+        assert!(ObjectTypeMap::parse(&buffer, 3).is_err());
+    }
+}
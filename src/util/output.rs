@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+/// Version of the JSON documents and events printed with `--output json`.
+///
+/// Bump it whenever a field is renamed or removed; adding fields is not a breaking change.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Failure classes, each with its own process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Anything not covered below
+    Failure,
+    /// Bad arguments or selector
+    Usage,
+    /// Admin rights are required and could not be obtained
+    NotElevated,
+    InstallNotFound,
+    LaunchFailed,
+    /// The selector matched no instance
+    NoMatch,
+    /// The instance or memory guard refused the launch
+    LimitExceeded,
+    /// Listing or opening processes failed
+    ProcessQuery,
+    /// Talking to the background daemon failed
+    Daemon,
+    /// Writing or restoring a registry value failed
+    Registry,
+}
+
+impl ErrorClass {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorClass::Failure => 1,
+            ErrorClass::Usage => 2,
+            ErrorClass::NotElevated => 3,
+            ErrorClass::InstallNotFound => 4,
+            ErrorClass::LaunchFailed => 5,
+            ErrorClass::NoMatch => 6,
+            ErrorClass::LimitExceeded => 7,
+            ErrorClass::ProcessQuery => 8,
+            ErrorClass::Daemon => 9,
+            ErrorClass::Registry => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    pub class: ErrorClass,
+    pub exit_code: u8,
+    pub message: String,
+}
+
+impl ErrorInfo {
+    pub fn new(class: ErrorClass, message: impl Into<String>) -> Self {
+        ErrorInfo {
+            class,
+            exit_code: class.exit_code(),
+            message: message.into(),
+        }
+    }
+}
+
+/// The single JSON document a command prints when it finishes.
+#[derive(Debug, Serialize)]
+pub struct Document<'a, T: Serialize> {
+    pub version: u32,
+    pub command: &'a str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorInfo>,
+}
+
+impl<'a, T: Serialize> Document<'a, T> {
+    pub fn success(command: &'a str, data: T) -> Self {
+        Document {
+            version: SCHEMA_VERSION,
+            command,
+            ok: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    pub fn failure(command: &'a str, error: ErrorInfo) -> Self {
+        Document {
+            version: SCHEMA_VERSION,
+            command,
+            ok: false,
+            data: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// One newline-delimited JSON event emitted while a command is still running.
+#[derive(Debug, Serialize)]
+pub struct EventLine<'a, E: Serialize> {
+    pub version: u32,
+    pub command: &'a str,
+    #[serde(flatten)]
+    pub event: &'a E,
+}
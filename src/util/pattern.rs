@@ -0,0 +1,96 @@
+use regex::{Regex, RegexBuilder};
+
+/// Which registry keys a `reg::RegistrySearch` reports, matched against the key name
+/// without case, as the registry itself compares names.
+#[derive(Debug, Clone)]
+pub struct KeyPattern(Regex);
+
+impl KeyPattern {
+    /// Matches one key name.
+    pub fn exact(name: &str) -> Self {
+        KeyPattern::compile(&format!("^{}$", regex::escape(name))).expect("escaped name")
+    }
+
+    /// Matches names with `*` standing for any run of characters and `?` for one.
+    pub fn glob(pattern: &str) -> Self {
+        let mut expression = String::from("^");
+        for c in pattern.chars() {
+            match c {
+                '*' => expression.push_str(".*"),
+                '?' => expression.push('.'),
+                c => expression.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        expression.push('$');
+        KeyPattern::compile(&expression).expect("escaped glob")
+    }
+
+    /// Matches names the regular expression finds a match in.
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        KeyPattern::compile(pattern)
+    }
+
+    fn compile(expression: &str) -> Result<Self, regex::Error> {
+        RegexBuilder::new(expression)
+            .case_insensitive(true)
+            .build()
+            .map(KeyPattern)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.is_match(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_names_ignore_case_but_nothing_else() {
+        let pattern = KeyPattern::exact("StarCraft");
+        assert!(pattern.matches("StarCraft"));
+        assert!(pattern.matches("starcraft"));
+        assert!(!pattern.matches("StarCraft II"));
+        assert!(!pattern.matches("My StarCraft"));
+    }
+
+    #[test]
+    fn globs_match_the_whole_name() {
+        let pattern = KeyPattern::glob("Star*");
+        assert!(pattern.matches("StarCraft"));
+        assert!(pattern.matches("STAR"));
+        assert!(!pattern.matches("Old StarCraft"));
+
+        let pattern = KeyPattern::glob("Game?");
+        assert!(pattern.matches("Game1"));
+        assert!(!pattern.matches("Game"));
+        assert!(!pattern.matches("Game12"));
+    }
+
+    #[test]
+    fn globs_escape_regex_metacharacters() {
+        let pattern = KeyPattern::glob("StarCraft (x86)*");
+        assert!(pattern.matches("StarCraft (x86)"));
+        assert!(pattern.matches("starcraft (X86) PTR"));
+        assert!(!pattern.matches("StarCraft x86"));
+
+        let pattern = KeyPattern::glob("a.b+[c]");
+        assert!(pattern.matches("a.b+[c]"));
+        assert!(!pattern.matches("axb+c"));
+
+        let pattern = KeyPattern::exact("C++ (1.16.1)");
+        assert!(pattern.matches("c++ (1.16.1)"));
+        assert!(!pattern.matches("C (1x16x1)"));
+    }
+
+    #[test]
+    fn regexes_find_a_match_anywhere() {
+        let pattern = KeyPattern::regex(r"starcraft\s*(remastered)?$").unwrap();
+        assert!(pattern.matches("Blizzard StarCraft"));
+        assert!(pattern.matches("StarCraft Remastered"));
+        assert!(!pattern.matches("StarCraft II Editor"));
+
+        assert!(KeyPattern::regex("(unclosed").is_err());
+    }
+}
@@ -0,0 +1,464 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::config::Arch;
+use crate::t;
+
+/// `IMAGE_FILE_HEADER.Machine` of a PE file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Machine {
+    I386,
+    Amd64,
+    Arm64,
+    Other(u16),
+}
+
+impl Machine {
+    fn from_raw(raw: u16) -> Self {
+        match raw {
+            0x014c => Machine::I386,
+            0x8664 => Machine::Amd64,
+            0xaa64 => Machine::Arm64,
+            other => Machine::Other(other),
+        }
+    }
+
+    /// The StarCraft build this machine type belongs to, if any.
+    pub fn arch(self) -> Option<Arch> {
+        match self {
+            Machine::I386 => Some(Arch::X86),
+            Machine::Amd64 => Some(Arch::X86_64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Machine::I386 => write!(f, "x86 (I386)"),
+            Machine::Amd64 => write!(f, "x86_64 (AMD64)"),
+            Machine::Arm64 => write!(f, "ARM64"),
+            Machine::Other(raw) => write!(f, "unknown ({:#06x})", raw),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// `IMAGE_DIRECTORY_ENTRY_RESOURCE`, index of the resource table in the data directories.
+const RESOURCE_DIRECTORY: usize = 2;
+/// `RT_VERSION`, the resource type of `VS_VERSIONINFO`.
+const RT_VERSION: u32 = 16;
+/// `VS_FIXEDFILEINFO.dwSignature`.
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xfeef_04bd;
+/// Resource sections are a few MB at most; anything larger is a corrupt header.
+const MAX_RESOURCE_SIZE: u32 = 64 << 20;
+
+/// What the version resource of an executable says about it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VersionInfo {
+    /// `dwFileVersionMS`/`LS` of `VS_FIXEDFILEINFO`, e.g. `1.23.10.13515`
+    pub file_version: Option<String>,
+    pub product_version: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+}
+
+/// The headers and version of a PE file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeInfo {
+    pub machine: Machine,
+    /// `None` when the file has no `VS_VERSIONINFO` resource
+    pub version: Option<VersionInfo>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+impl Section {
+    fn contains(&self, rva: u32) -> bool {
+        let size = self.virtual_size.max(self.raw_size);
+        rva >= self.virtual_address && rva - self.virtual_address < size
+    }
+}
+
+struct Headers {
+    machine: Machine,
+    /// RVA and size of the resource table, if the file has one
+    resources: Option<(u32, u32)>,
+    sections: Vec<Section>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_headers<R: Read + Seek>(file: &mut R) -> io::Result<Headers> {
+    let mut dos_header = [0u8; 0x40];
+    file.read_exact(&mut dos_header)?;
+    if &dos_header[..2] != b"MZ" {
+        return Err(invalid("Not an executable (no MZ header)"));
+    }
+    // e_lfanew: offset of the PE signature
+    let pe_offset = u32::from_le_bytes(dos_header[0x3c..0x40].try_into().unwrap());
+
+    // Signature followed by IMAGE_FILE_HEADER
+    let mut file_header = [0u8; 24];
+    file.seek(SeekFrom::Start(pe_offset as u64))?;
+    file.read_exact(&mut file_header)?;
+    if &file_header[..4] != b"PE\0\0" {
+        return Err(invalid("Not a PE file (no PE signature)"));
+    }
+    let machine = Machine::from_raw(u16::from_le_bytes([file_header[4], file_header[5]]));
+    let section_count = u16::from_le_bytes([file_header[6], file_header[7]]) as usize;
+    let optional_size = u16::from_le_bytes([file_header[20], file_header[21]]) as usize;
+
+    let mut optional_header = vec![0u8; optional_size];
+    file.read_exact(&mut optional_header)?;
+    // PE32 and PE32+ differ in where the data directories start
+    let directories = match u16_at(&optional_header, 0) {
+        Some(0x10b) => 96,
+        Some(0x20b) => 112,
+        _ => return Err(invalid("Unknown optional header")),
+    };
+    let directory_count = u32_at(&optional_header, directories - 4).unwrap_or(0) as usize;
+    let resources = (RESOURCE_DIRECTORY < directory_count)
+        .then(|| {
+            let entry = directories + RESOURCE_DIRECTORY * 8;
+            Some((
+                u32_at(&optional_header, entry)?,
+                u32_at(&optional_header, entry + 4)?,
+            ))
+        })
+        .flatten()
+        .filter(|&(rva, size)| rva != 0 && size != 0);
+
+    let mut section_table = vec![0u8; section_count * 40];
+    file.read_exact(&mut section_table)?;
+    let sections = section_table
+        .chunks_exact(40)
+        .map(|header| Section {
+            virtual_size: u32_at(header, 8).unwrap(),
+            virtual_address: u32_at(header, 12).unwrap(),
+            raw_size: u32_at(header, 16).unwrap(),
+            raw_offset: u32_at(header, 20).unwrap(),
+        })
+        .collect();
+
+    Ok(Headers {
+        machine,
+        resources,
+        sections,
+    })
+}
+
+/// Reads the machine type from the headers of a PE file without loading it.
+pub fn read_machine(path: &Path) -> io::Result<Machine> {
+    Ok(read_headers(&mut File::open(path)?)?.machine)
+}
+
+/// Fails with a message for the user unless `path` is a PE file built for `arch`.
+pub fn check_arch(path: &Path, arch: Arch) -> Result<Machine, String> {
+    let machine =
+        read_machine(path).map_err(|e| t!("exe-unreadable", path = path.display(), error = e))?;
+    if machine.arch() != Some(arch) {
+        return Err(t!(
+            "exe-arch-mismatch",
+            path = path.display(),
+            machine = machine,
+            arch = arch
+        ));
+    }
+    Ok(machine)
+}
+
+/// Reads the machine type and the version resource of a PE file without loading it.
+pub fn read_pe_info(path: &Path) -> io::Result<PeInfo> {
+    read_pe(&mut File::open(path)?)
+}
+
+/// [`read_pe_info`] on any reader, e.g. a `Cursor` over the bytes of a file.
+pub fn read_pe<R: Read + Seek>(file: &mut R) -> io::Result<PeInfo> {
+    let headers = read_headers(file)?;
+    let Some((rva, size)) = headers.resources else {
+        return Ok(PeInfo {
+            machine: headers.machine,
+            version: None,
+        });
+    };
+    let section = headers
+        .sections
+        .iter()
+        .find(|section| section.contains(rva))
+        .ok_or_else(|| invalid("Resource table outside of every section"))?;
+    if size > MAX_RESOURCE_SIZE {
+        return Err(invalid("Resource table too large"));
+    }
+
+    let mut resources = vec![0u8; size as usize];
+    let offset = section.raw_offset as u64 + (rva - section.virtual_address) as u64;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut resources)?;
+
+    let version = version_resource(&resources, rva).map(parse_version_info);
+    Ok(PeInfo {
+        machine: headers.machine,
+        version,
+    })
+}
+
+/// Entries of the `IMAGE_RESOURCE_DIRECTORY` at `offset` as `(name or id, offset to data)`.
+fn directory_entries(resources: &[u8], offset: usize) -> Vec<(u32, u32)> {
+    let named = u16_at(resources, offset + 12).unwrap_or(0) as usize;
+    let ids = u16_at(resources, offset + 14).unwrap_or(0) as usize;
+    (0..named + ids)
+        .map_while(|i| {
+            let entry = offset + 16 + i * 8;
+            Some((u32_at(resources, entry)?, u32_at(resources, entry + 4)?))
+        })
+        .collect()
+}
+
+/// Follows type → name → language to the first `VS_VERSIONINFO` in the resource table.
+fn version_resource(resources: &[u8], base_rva: u32) -> Option<&[u8]> {
+    const SUBDIRECTORY: u32 = 0x8000_0000;
+
+    let (_, types) = directory_entries(resources, 0)
+        .into_iter()
+        .find(|&(id, target)| id == RT_VERSION && target & SUBDIRECTORY != 0)?;
+    let (_, names) = *directory_entries(resources, (types & !SUBDIRECTORY) as usize).first()?;
+    if names & SUBDIRECTORY == 0 {
+        return None;
+    }
+    let (_, data_entry) =
+        *directory_entries(resources, (names & !SUBDIRECTORY) as usize).first()?;
+    if data_entry & SUBDIRECTORY != 0 {
+        return None;
+    }
+
+    // IMAGE_RESOURCE_DATA_ENTRY holds an RVA, not an offset into the table
+    let data_rva = u32_at(resources, data_entry as usize)?;
+    let data_size = u32_at(resources, data_entry as usize + 4)? as usize;
+    let start = data_rva.checked_sub(base_rva)? as usize;
+    resources.get(start..start.checked_add(data_size)?)
+}
+
+/// One node of the `VS_VERSIONINFO` tree: `wLength`, `wValueLength`, `wType`, key, value
+/// and children, each part starting on a 32-bit boundary.
+struct Block {
+    key: String,
+    /// `(start, end)` of the value in the resource
+    value: (usize, usize),
+    /// `(start, end)` of the children in the resource
+    children: (usize, usize),
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// Reads the block at `offset`, returning it and the offset of the next sibling.
+fn read_block(data: &[u8], offset: usize) -> Option<(Block, usize)> {
+    let length = u16_at(data, offset)? as usize;
+    let value_length = u16_at(data, offset + 2)? as usize;
+    let is_text = u16_at(data, offset + 4)? == 1;
+    if length < 6 {
+        return None;
+    }
+    let end = (offset + length).min(data.len());
+
+    let mut key = Vec::new();
+    let mut position = offset + 6;
+    while position + 2 <= end {
+        let unit = u16_at(data, position)?;
+        position += 2;
+        if unit == 0 {
+            break;
+        }
+        key.push(unit);
+    }
+
+    let value_start = align4(position).min(end);
+    // Text values count UTF-16 units, binary ones bytes
+    let value_size = if is_text {
+        value_length * 2
+    } else {
+        value_length
+    };
+    let value_end = (value_start + value_size).min(end);
+    let block = Block {
+        key: String::from_utf16_lossy(&key),
+        value: (value_start, value_end),
+        children: (align4(value_end).min(end), end),
+    };
+    Some((block, align4(end)))
+}
+
+fn children(data: &[u8], block: &Block) -> Vec<Block> {
+    let (mut offset, end) = block.children;
+    let mut children = Vec::new();
+    while offset < end {
+        let Some((child, next)) = read_block(&data[..end], offset) else {
+            break;
+        };
+        children.push(child);
+        offset = next;
+    }
+    children
+}
+
+fn text(value: &[u8]) -> Option<String> {
+    let units: Vec<u16> = value
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    let text = String::from_utf16_lossy(&units).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn parse_version_info(data: &[u8]) -> VersionInfo {
+    let mut info = VersionInfo::default();
+    let Some((root, _)) = read_block(data, 0) else {
+        return info;
+    };
+
+    let fixed = &data[root.value.0..root.value.1];
+    if u32_at(fixed, 0) == Some(FIXED_FILE_INFO_SIGNATURE) {
+        let version = |ms: usize| {
+            let high = u32_at(fixed, ms)?;
+            let low = u32_at(fixed, ms + 4)?;
+            Some(format!(
+                "{}.{}.{}.{}",
+                high >> 16,
+                high & 0xffff,
+                low >> 16,
+                low & 0xffff
+            ))
+        };
+        info.file_version = version(8);
+        info.product_version = version(16);
+    }
+
+    // StringFileInfo → one table per language → strings; the first table wins
+    for file_info in children(data, &root) {
+        if file_info.key != "StringFileInfo" {
+            continue;
+        }
+        let Some(table) = children(data, &file_info).into_iter().next() else {
+            continue;
+        };
+        for string in children(data, &table) {
+            // wValueLength of strings is unreliable, read up to the terminator instead
+            let value = text(&data[string.value.0..string.children.1]);
+            match string.key.as_str() {
+                "ProductName" => info.product_name = value,
+                "FileDescription" => info.file_description = value,
+                "FileVersion" if info.file_version.is_none() => info.file_version = value,
+                "ProductVersion" if info.product_version.is_none() => info.product_version = value,
+                _ => {}
+            }
+        }
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::util::testing::{pe_image, version_resources, AMD64, I386, SECTION_OFFSET};
+
+    #[test]
+    fn x86_version_resource_is_read() {
+        let resources = version_resources();
+        let info = read_pe(&mut Cursor::new(pe_image(I386, Some(&resources)))).unwrap();
+
+        assert_eq!(info.machine, Machine::I386);
+        assert_eq!(info.machine.arch(), Some(Arch::X86));
+        let version = info.version.unwrap();
+        assert_eq!(version.file_version.as_deref(), Some("1.23.10.13515"));
+        assert_eq!(version.product_version.as_deref(), Some("1.23.10.0"));
+        assert_eq!(version.product_name.as_deref(), Some("StarCraft"));
+        assert_eq!(
+            version.file_description.as_deref(),
+            Some("StarCraft: Remastered")
+        );
+    }
+
+    #[test]
+    fn x64_headers_are_read() {
+        let resources = version_resources();
+        let info = read_pe(&mut Cursor::new(pe_image(AMD64, Some(&resources)))).unwrap();
+
+        assert_eq!(info.machine, Machine::Amd64);
+        assert_eq!(info.machine.arch(), Some(Arch::X86_64));
+        assert_eq!(
+            info.version.unwrap().file_version.as_deref(),
+            Some("1.23.10.13515")
+        );
+    }
+
+    #[test]
+    fn files_without_resources_have_no_version() {
+        let info = read_pe(&mut Cursor::new(pe_image(I386, None))).unwrap();
+        assert_eq!(info.machine, Machine::I386);
+        assert_eq!(info.version, None);
+    }
+
+    #[test]
+    fn other_machines_have_no_arch() {
+        let info = read_pe(&mut Cursor::new(pe_image(0xaa64, None))).unwrap();
+        assert_eq!(info.machine, Machine::Arm64);
+        assert_eq!(info.machine.arch(), None);
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let resources = version_resources();
+        let data = pe_image(I386, Some(&resources));
+
+        // Cut inside the resource table: the headers are fine, the version is not
+        let mut cut = Cursor::new(&data[..SECTION_OFFSET + 0x20]);
+        assert!(read_pe(&mut cut).is_err());
+        let mut cut = Cursor::new(&data[..SECTION_OFFSET + 0x20]);
+        assert_eq!(read_headers(&mut cut).unwrap().machine, Machine::I386);
+
+        // Cut inside the headers
+        assert!(read_pe(&mut Cursor::new(&data[..0x50])).is_err());
+        assert!(read_pe(&mut Cursor::new(&data[..0x10])).is_err());
+    }
+
+    #[test]
+    fn non_pe_files_are_rejected() {
+        let mut data = pe_image(I386, None);
+        data[0] = b'Z';
+        assert!(read_pe(&mut Cursor::new(&data)).is_err());
+
+        let mut data = pe_image(I386, None);
+        data[0x40] = b'N';
+        assert!(read_pe(&mut Cursor::new(&data)).is_err());
+    }
+}
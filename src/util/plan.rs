@@ -0,0 +1,378 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use super::config::Arch;
+use super::instance::LaunchOptions;
+use crate::t;
+
+/// A whole session described in a TOML file: which games to start and how.
+///
+/// ```toml
+/// [session]
+/// stagger_ms = 2000
+/// keep_guard = true
+/// on_exit = "keep"
+///
+/// [[instance]]
+/// label = "main"
+/// window = { x = 0, y = 0, width = 960, height = 720 }
+/// affinity = [0, 1]
+///
+/// [[instance]]
+/// label = "bot1"
+/// args = ["-windowed"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchPlan {
+    #[serde(default)]
+    pub session: Session,
+    #[serde(default, rename = "instance")]
+    pub instances: Vec<Spanned<PlannedInstance>>,
+}
+
+/// Settings for the plan as a whole.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Session {
+    /// Pause between two launches, in milliseconds
+    pub stagger_ms: u64,
+    /// Keep closing the instance mutex in the background while the session runs
+    pub keep_guard: bool,
+    pub on_exit: OnExit,
+    pub game_path: Option<PathBuf>,
+    pub arch: Option<Arch>,
+    /// Profile for instances that do not name their own
+    pub profile: Option<String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            stagger_ms: 1000,
+            keep_guard: false,
+            on_exit: OnExit::Kill,
+            game_path: None,
+            arch: None,
+            profile: None,
+        }
+    }
+}
+
+impl Session {
+    pub fn stagger(&self) -> Duration {
+        Duration::from_millis(self.stagger_ms)
+    }
+}
+
+/// What happens to the games when the session ends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnExit {
+    #[default]
+    Kill,
+    Keep,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedInstance {
+    pub label: Option<Spanned<String>>,
+    pub profile: Option<String>,
+    /// Extra arguments passed after `-launch`
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub window: Option<Spanned<WindowPlacement>>,
+    /// Logical processors the game may run on, counted from 0
+    pub affinity: Option<Spanned<Vec<u32>>>,
+}
+
+impl PlannedInstance {
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_ref().map(|label| label.get_ref().as_str())
+    }
+
+    pub fn window(&self) -> Option<WindowPlacement> {
+        self.window.as_ref().map(|window| *window.get_ref())
+    }
+
+    /// The affinity as a processor mask, `None` when unset.
+    pub fn affinity_mask(&self) -> Option<usize> {
+        self.affinity.as_ref().map(|cores| {
+            cores
+                .get_ref()
+                .iter()
+                .fold(0usize, |mask, &core| mask | (1usize << core))
+        })
+    }
+
+    pub fn launch_options(&self, default_profile: &str) -> LaunchOptions {
+        LaunchOptions {
+            label: self.label().map(str::to_string),
+            profile: self
+                .profile
+                .clone()
+                .unwrap_or_else(|| default_profile.to_string()),
+            args: self.args.clone(),
+            affinity: self.affinity_mask(),
+            ..LaunchOptions::default()
+        }
+    }
+}
+
+/// Where to put a game window; the size is left alone unless both dimensions are given.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WindowPlacement {
+    pub x: i32,
+    pub y: i32,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl WindowPlacement {
+    pub fn size(&self) -> Option<(i32, i32)> {
+        self.width.zip(self.height)
+    }
+}
+
+/// A problem found in a plan file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanError {
+    /// 1-based line in the plan file, when the problem has one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl LaunchPlan {
+    /// Reads and validates a plan file.
+    pub fn load(path: &Path) -> Result<LaunchPlan, Vec<PlanError>> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            vec![PlanError {
+                line: None,
+                message: e.to_string(),
+            }]
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Parses and validates a plan, reporting every problem at once.
+    pub fn parse(text: &str) -> Result<LaunchPlan, Vec<PlanError>> {
+        let plan: LaunchPlan = toml::from_str(text).map_err(|e| {
+            vec![PlanError {
+                line: e.span().map(|span| line_of(text, span.start)),
+                message: e.message().to_string(),
+            }]
+        })?;
+
+        let errors = plan.validate(text);
+        if errors.is_empty() {
+            Ok(plan)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate(&self, text: &str) -> Vec<PlanError> {
+        let mut errors = Vec::new();
+        let mut error = |offset: usize, message: String| {
+            errors.push(PlanError {
+                line: Some(line_of(text, offset)),
+                message,
+            })
+        };
+
+        if self.instances.is_empty() {
+            return vec![PlanError {
+                line: None,
+                message: t!("plan-no-instances"),
+            }];
+        }
+
+        let processors = std::thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+        let mut labels: HashMap<&str, usize> = HashMap::new();
+
+        for instance in &self.instances {
+            let instance = instance.get_ref();
+
+            if let Some(label) = &instance.label {
+                let start = label.span().start;
+                if label.get_ref().trim().is_empty() {
+                    error(start, t!("plan-label-empty"));
+                } else {
+                    match labels.entry(label.get_ref()) {
+                        Entry::Occupied(first) => error(
+                            start,
+                            t!(
+                                "plan-label-duplicate",
+                                label = label.get_ref(),
+                                line = line_of(text, *first.get())
+                            ),
+                        ),
+                        Entry::Vacant(slot) => {
+                            slot.insert(start);
+                        }
+                    }
+                }
+            }
+
+            if let Some(window) = &instance.window {
+                let start = window.span().start;
+                let placement = window.get_ref();
+                match (placement.width, placement.height) {
+                    (Some(width), Some(height)) if width <= 0 || height <= 0 => {
+                        error(start, t!("plan-window-size"))
+                    }
+                    (Some(_), None) | (None, Some(_)) => error(start, t!("plan-window-both")),
+                    _ => {}
+                }
+            }
+
+            if let Some(affinity) = &instance.affinity {
+                let start = affinity.span().start;
+                if affinity.get_ref().is_empty() {
+                    error(start, t!("plan-affinity-empty"));
+                }
+                for &core in affinity.get_ref() {
+                    if core >= usize::BITS || core >= processors {
+                        error(
+                            start,
+                            t!(
+                                "plan-affinity-range",
+                                core = core,
+                                processors = processors,
+                                last = processors - 1
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+/// 1-based line of a byte offset.
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(errors: &[PlanError]) -> Vec<Option<usize>> {
+        errors.iter().map(|error| error.line).collect()
+    }
+
+    #[test]
+    fn a_valid_plan_is_read() {
+        let plan = LaunchPlan::parse(
+            r#"
+[session]
+stagger_ms = 2000
+on_exit = "keep"
+profile = "practice"
+
+[[instance]]
+label = "main"
+window = { x = 0, y = 0, width = 960, height = 720 }
+affinity = [0]
+
+[[instance]]
+profile = "ladder"
+args = ["-windowed"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(plan.session.stagger(), Duration::from_millis(2000));
+        assert_eq!(plan.session.on_exit, OnExit::Keep);
+        assert_eq!(plan.instances.len(), 2);
+
+        let main = plan.instances[0].get_ref();
+        assert_eq!(main.label(), Some("main"));
+        assert_eq!(main.window().unwrap().size(), Some((960, 720)));
+        assert_eq!(main.affinity_mask(), Some(1));
+        assert_eq!(main.launch_options("practice").profile, "practice");
+
+        let bot = plan.instances[1].get_ref();
+        assert_eq!(bot.launch_options("practice").profile, "ladder");
+        assert_eq!(bot.launch_options("practice").args, ["-windowed"]);
+    }
+
+    #[test]
+    fn every_problem_is_reported_with_its_line() {
+        let text = r#"[[instance]]
+label = "main"
+
+[[instance]]
+label = "main"
+window = { x = 0, y = 0, width = 960 }
+
+[[instance]]
+label = " "
+window = { x = 0, y = 0, width = 0, height = 720 }
+affinity = []
+
+[[instance]]
+affinity = [4096]
+"#;
+        let errors = LaunchPlan::parse(text).unwrap_err();
+
+        assert_eq!(
+            lines(&errors),
+            [Some(5), Some(6), Some(9), Some(10), Some(11), Some(14)]
+        );
+        assert_eq!(
+            errors[0].message,
+            t!("plan-label-duplicate", label = "main", line = 2)
+        );
+        assert_eq!(errors[1].message, t!("plan-window-both"));
+        assert_eq!(errors[2].message, t!("plan-label-empty"));
+        assert_eq!(errors[3].message, t!("plan-window-size"));
+        assert_eq!(errors[4].message, t!("plan-affinity-empty"));
+        assert_eq!(
+            errors[0].to_string(),
+            format!("line 5: {}", errors[0].message)
+        );
+    }
+
+    #[test]
+    fn syntax_errors_point_at_their_line() {
+        let errors =
+            LaunchPlan::parse("[[instance]]\nlabel = \"main\"\ncolour = \"red\"\n").unwrap_err();
+        assert_eq!(lines(&errors), [Some(3)]);
+
+        let errors = LaunchPlan::parse("[session]\nstagger_ms = \"soon\"\n").unwrap_err();
+        assert_eq!(lines(&errors), [Some(2)]);
+    }
+
+    #[test]
+    fn a_plan_without_instances_is_refused() {
+        let errors = LaunchPlan::parse("[session]\nkeep_guard = true\n").unwrap_err();
+        assert_eq!(
+            errors,
+            [PlanError {
+                line: None,
+                message: t!("plan-no-instances"),
+            }]
+        );
+        assert_eq!(errors[0].to_string(), errors[0].message);
+    }
+}
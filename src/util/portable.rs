@@ -0,0 +1,45 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// File next to the executable that switches portable mode on; its content is ignored.
+pub const MARKER_FILE: &str = "sclauncher.portable";
+
+/// Settings of a portable copy, including the install it remembers.
+pub const CONFIG_FILE: &str = "sclauncher.toml";
+
+/// Install folders found by drive walks of a portable copy.
+pub const CACHE_FILE: &str = "installs.json";
+
+static PORTABLE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Folder of the running executable.
+pub fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+}
+
+/// Switches portable mode on for the rest of the process, whether or not the marker file
+/// is there. Only has an effect before the mode is first looked at.
+pub fn enable() -> bool {
+    PORTABLE_DIR.set(exe_dir()).is_ok()
+}
+
+/// The folder a portable copy keeps everything in, `None` when not portable.
+///
+/// Without [`enable`], the mode is on when [`MARKER_FILE`] sits next to the executable.
+pub fn dir() -> Option<&'static Path> {
+    PORTABLE_DIR
+        .get_or_init(|| exe_dir().filter(|dir| dir.join(MARKER_FILE).is_file()))
+        .as_deref()
+}
+
+/// Whether the registry is off limits and settings live next to the executable.
+pub fn enabled() -> bool {
+    dir().is_some()
+}
+
+/// [`CONFIG_FILE`] next to the executable, `None` when not portable.
+pub fn config_path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join(CONFIG_FILE))
+}
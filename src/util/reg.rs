@@ -1,9 +1,114 @@
 use std::path::PathBuf;
 
-use regex::Regex;
+use std::time::{Duration, Instant};
+
 use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
 use winreg::{enums::*, RegKey, HKEY};
 
+use super::config::Arch;
+use super::journal::{Journal, RegistryChange};
+use super::layout::InstallLayout;
+use super::pattern::KeyPattern;
+
+/// Uninstall keys Battle.net writes the install location to, native view first.
+pub const UNINSTALL_KEY_PATHS: [&str; 2] = [
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\StarCraft",
+    r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\StarCraft",
+];
+
+/// Uninstall roots searched for StarCraft entries other than the main one (PTR, old copies).
+const UNINSTALL_ROOTS: [&str; 2] = [
+    r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall",
+    r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+];
+
+/// Where the 1.16.1 installer and its patches record `InstallPath`.
+const LEGACY_KEY_PATHS: [&str; 2] = [
+    r"SOFTWARE\Blizzard Entertainment\Starcraft",
+    r"SOFTWARE\WOW6432Node\Blizzard Entertainment\Starcraft",
+];
+
+/// An uninstall entry that looks like a StarCraft install.
+#[derive(Debug, Clone)]
+pub struct UninstallEntry {
+    /// Full key path, e.g. `HKEY_LOCAL_MACHINE\SOFTWARE\...\Uninstall\StarCraft`
+    pub key_path: String,
+    pub display_name: Option<String>,
+    pub display_version: Option<String>,
+    pub install_location: String,
+}
+
+/// Lists every uninstall entry whose key or display name mentions StarCraft.
+pub fn starcraft_uninstall_entries() -> Vec<UninstallEntry> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let mut entries = Vec::new();
+
+    for root in UNINSTALL_ROOTS {
+        let Ok(uninstall) = hklm.open_subkey_with_flags(root, KEY_READ) else {
+            continue;
+        };
+        for name in uninstall.enum_keys().flatten() {
+            let Ok(key) = uninstall.open_subkey_with_flags(&name, KEY_READ) else {
+                continue;
+            };
+            let display_name: Option<String> = key.get_value("DisplayName").ok();
+            let mentions_starcraft = |text: &str| text.to_lowercase().contains("starcraft");
+            if !mentions_starcraft(&name)
+                && !display_name.as_deref().is_some_and(mentions_starcraft)
+            {
+                continue;
+            }
+            // StarCraft II shares the prefix
+            if display_name
+                .as_deref()
+                .unwrap_or(&name)
+                .to_lowercase()
+                .contains("starcraft ii")
+            {
+                continue;
+            }
+            let Ok(install_location) = key.get_value::<String, _>("InstallLocation") else {
+                continue;
+            };
+            entries.push(UninstallEntry {
+                key_path: format!("HKEY_LOCAL_MACHINE\\{}\\{}", root, name),
+                display_name,
+                display_version: key.get_value("DisplayVersion").ok(),
+                install_location,
+            });
+        }
+    }
+    entries
+}
+
+/// Lists `(key path, InstallPath)` of the classic 1.16.1 keys, machine-wide and per user.
+pub fn legacy_install_paths() -> Vec<(String, String)> {
+    let mut paths = Vec::new();
+    for (hive, hive_name) in [
+        (HKEY_LOCAL_MACHINE, "HKEY_LOCAL_MACHINE"),
+        (HKEY_CURRENT_USER, "HKEY_CURRENT_USER"),
+    ] {
+        for key_path in LEGACY_KEY_PATHS {
+            let install_path = RegKey::predef(hive)
+                .open_subkey_with_flags(key_path, KEY_READ)
+                .and_then(|key| key.get_value::<String, _>("InstallPath"));
+            if let Ok(install_path) = install_path {
+                paths.push((format!("{}\\{}", hive_name, key_path), install_path));
+            }
+        }
+    }
+    paths
+}
+
+/// Reads `InstallLocation` from one uninstall key under HKLM.
+pub fn read_install_location(subkey_path: &str) -> std::io::Result<String> {
+    RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(subkey_path, KEY_READ)?
+        .get_value("InstallLocation")
+}
+
 pub fn get_game_path(is_64bits: bool) -> Option<PathBuf> {
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let mut subkey_path = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\StarCraft";
@@ -21,9 +126,9 @@ pub fn get_game_path(is_64bits: bool) -> Option<PathBuf> {
 
     // Attempt to read the installation location
     let install_location: String = subkey.get_value("InstallLocation").ok()?;
-    let exe_subfolder = if is_64bits { "x86_64" } else { "x86" };
-    let game_path =
-        PathBuf::from(install_location).join(format!("{}\\StarCraft.exe", exe_subfolder));
+    let root = PathBuf::from(install_location);
+    let arch = if is_64bits { Arch::X86_64 } else { Arch::X86 };
+    let game_path = InstallLayout::detect(&root)?.exe(&root, arch);
 
     // Check if the constructed path exists
     if game_path.exists() {
@@ -33,117 +138,288 @@ pub fn get_game_path(is_64bits: bool) -> Option<PathBuf> {
     }
 }
 
-/// Sets the game path in the registry if the key does not already exist.
-///
-/// # Arguments
-/// * `new_path` - The new installation path to set if the key is created.
+/// What [`set_game_path`] did.
+#[derive(Debug, Clone)]
+pub enum GamePathUpdate {
+    /// The value was written; the change is in the journal
+    Written(RegistryChange),
+    /// The registry already points there
+    Unchanged,
+    /// Another install location is set and `replace` was not given
+    Conflict(String),
+}
+
+/// Points the uninstall key of the native registry view at `new_path`.
 ///
-/// # Returns
-/// * An `io::Result<()>` indicating success or failure.
-pub fn set_game_path(new_path: &str) -> std::io::Result<()> {
-    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+/// An existing, different location is only replaced with `replace`, so callers can ask
+/// first. Every write goes through the [`Journal`] and can be rolled back.
+pub fn set_game_path(new_path: &str, replace: bool) -> std::io::Result<GamePathUpdate> {
     let subkey_path = if cfg!(target_arch = "x86_64") {
-        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\StarCraft"
+        UNINSTALL_KEY_PATHS[0]
     } else {
-        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\StarCraft"
+        UNINSTALL_KEY_PATHS[1]
     };
 
-    // Create or open the key with write access
-    let (starcraft_key, disposition) =
-        hklm.create_subkey_with_flags(subkey_path, KEY_ALL_ACCESS)?;
+    let normalize = |path: &str| path.trim_end_matches(['\\', '/']).to_lowercase();
+    match read_install_location(subkey_path) {
+        Ok(current) if normalize(&current) == normalize(new_path) => Ok(GamePathUpdate::Unchanged),
+        Ok(current) if !replace => Ok(GamePathUpdate::Conflict(current)),
+        _ => {
+            let mut journal = Journal::open_default()?;
+            let change = journal.set_value(subkey_path, "InstallLocation", new_path)?;
+            Ok(GamePathUpdate::Written(change))
+        }
+    }
+}
 
-    match disposition {
-        REG_CREATED_NEW_KEY => {
-            // Only set the value if a new key has been created.
-            starcraft_key.set_value("InstallLocation", &new_path)?;
+/// A key found by a [`RegistrySearch`].
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// Full path starting with the hive name, e.g. `HKEY_LOCAL_MACHINE\SOFTWARE\...`
+    pub key_path: String,
+    /// The value asked for with [`RegistrySearch::value_name`]
+    pub value: Option<String>,
+}
 
-            let exe_subfolder = if cfg!(target_arch = "x86_64") {
-                "x86_64"
-            } else {
-                "x86"
-            };
-            let game_path =
-                PathBuf::from(new_path).join(format!("{}\\StarCraft.exe", exe_subfolder));
+/// A walk over a registry tree for keys with a matching name.
+///
+/// The walk runs on a blocking thread and hands out hits as it finds them; it stops at the
+/// timeout, when the token is cancelled or when the stream is dropped.
+#[derive(Debug, Clone)]
+pub struct RegistrySearch {
+    hive: HKEY,
+    start_path: String,
+    max_depth: Option<usize>,
+    pattern: Option<KeyPattern>,
+    value_name: Option<String>,
+    timeout: Option<Duration>,
+    cancel: CancellationToken,
+}
 
-            println!("Game path set to: {}", game_path.display());
-        }
-        REG_OPENED_EXISTING_KEY => {
-            // Do nothing if the key already exists.
+impl RegistrySearch {
+    /// Searches the whole `hive` for every key until narrowed down.
+    pub fn new(hive: HKEY) -> Self {
+        RegistrySearch {
+            hive,
+            start_path: String::new(),
+            max_depth: None,
+            pattern: None,
+            value_name: None,
+            timeout: None,
+            cancel: CancellationToken::new(),
         }
     }
 
-    Ok(())
-}
+    /// Starts below this key of the hive instead of at its root.
+    pub fn start_path(mut self, path: impl Into<String>) -> Self {
+        self.start_path = path.into();
+        self
+    }
 
-pub async fn async_registry_search(
-    root_key: HKEY,
-    search_term: &str,
-    value_name: &str,
-) -> Vec<(String, String)> {
-    let (tx, mut rx) = mpsc::channel(100);
-
-    let search_term = search_term.to_lowercase(); // Use lowercase for case insensitive checks
-    let value_name = value_name.to_string();
-
-    // Compile regex once
-    let search_regex = Regex::new(&format!(r"(?i)^{}$", regex::escape(&search_term))).unwrap();
-    let initial_path = "HKEY_LOCAL_MACHINE".to_string(); // Starting path
-
-    tokio::spawn(async move {
-        let reg_key = RegKey::predef(root_key);
-        if let Err(e) =
-            recursive_search(reg_key, initial_path, &value_name, &search_regex, tx).await
-        {
-            eprintln!("Error in recursive search: {}", e);
-        }
-    });
+    /// Descends at most this many levels below the start path; 1 only looks at its subkeys.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
 
-    let mut results = Vec::new();
-    while let Some(result) = rx.recv().await {
-        results.push(result);
+    pub fn key_name(mut self, pattern: KeyPattern) -> Self {
+        self.pattern = Some(pattern);
+        self
     }
 
-    results
-}
+    /// Only reports keys that hold this string value, and returns it with the hit.
+    pub fn value_name(mut self, name: impl Into<String>) -> Self {
+        self.value_name = Some(name.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops the walk once `token` is cancelled.
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Starts the walk; the stream ends when the walk does.
+    pub fn stream(self) -> impl Stream<Item = SearchHit> {
+        let (tx, rx) = mpsc::channel(100);
+        tokio::task::spawn_blocking(move || self.walk(tx));
+        ReceiverStream::new(rx)
+    }
+
+    /// Runs the walk to its end and returns every hit.
+    pub async fn collect(self) -> Vec<SearchHit> {
+        self.stream().collect().await
+    }
+
+    fn walk(self, tx: mpsc::Sender<SearchHit>) {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let mut label = hive_name(self.hive).to_string();
+        let mut start = RegKey::predef(self.hive);
+        if !self.start_path.is_empty() {
+            let start_path = self.start_path.trim_matches('\\');
+            start = match start.open_subkey_with_flags(start_path, KEY_READ) {
+                Ok(key) => key,
+                Err(_) => return,
+            };
+            label = format!("{}\\{}", label, start_path);
+        }
+
+        let mut stack = vec![(start, label, 0)];
+        while let Some((key, path, depth)) = stack.pop() {
+            if self.cancel.is_cancelled() || deadline.is_some_and(|at| Instant::now() >= at) {
+                return;
+            }
+            if self.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+
+            for name in key.enum_keys().flatten() {
+                let Ok(subkey) = key.open_subkey_with_flags(&name, KEY_READ) else {
+                    continue;
+                };
+                let subkey_path = format!("{}\\{}", path, name);
 
-async fn recursive_search(
-    root_key: RegKey,
-    root_path: String,
-    value_name: &str,
-    search_regex: &Regex,
-    tx: mpsc::Sender<(String, String)>,
-) -> tokio::io::Result<()> {
-    let mut stack = vec![(root_key, root_path)];
-
-    while let Some((reg_key, path)) = stack.pop() {
-        for subkey_name in reg_key.enum_keys() {
-            match subkey_name {
-                Ok(subkey_name) => {
-                    let full_subkey_path = format!("{}\\{}", path, subkey_name); // Build the full path
-
-                    // Case-insensitive check
-                    match reg_key.open_subkey(&subkey_name) {
-                        Ok(subkey) => {
-                            if search_regex.is_match(&subkey_name) {
-                                if let Ok(value) = subkey.get_value::<String, _>(value_name) {
-                                    tx.send((full_subkey_path.clone(), value)).await.map_err(
-                                        |e| {
-                                            tokio::io::Error::new(
-                                                tokio::io::ErrorKind::Other,
-                                                e.to_string(),
-                                            )
-                                        },
-                                    )?;
-                                }
-                            }
-                            stack.push((subkey, full_subkey_path));
+                let matched = self.pattern.as_ref().is_none_or(|p| p.matches(&name));
+                if matched {
+                    let value = self
+                        .value_name
+                        .as_ref()
+                        .and_then(|value_name| subkey.get_value::<String, _>(value_name).ok());
+                    if self.value_name.is_none() || value.is_some() {
+                        let hit = SearchHit {
+                            key_path: subkey_path.clone(),
+                            value,
+                        };
+                        // Nobody is listening any more
+                        if tx.blocking_send(hit).is_err() {
+                            return;
                         }
-                        Err(_) => continue,
                     }
                 }
-                Err(_) => continue,
+                stack.push((subkey, subkey_path, depth + 1));
+            }
+        }
+    }
+}
+
+/// The name a predefined key is shown with in key paths.
+fn hive_name(hive: HKEY) -> &'static str {
+    match hive {
+        HKEY_CLASSES_ROOT => "HKEY_CLASSES_ROOT",
+        HKEY_CURRENT_USER => "HKEY_CURRENT_USER",
+        HKEY_LOCAL_MACHINE => "HKEY_LOCAL_MACHINE",
+        HKEY_USERS => "HKEY_USERS",
+        HKEY_CURRENT_CONFIG => "HKEY_CURRENT_CONFIG",
+        _ => "HKEY",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A throwaway tree under `HKCU\Software`, deleted again on drop.
+    struct TestTree {
+        path: String,
+    }
+
+    impl TestTree {
+        fn new(name: &str, keys: &[&str]) -> Self {
+            let path = format!("Software\\sclauncher-test-{}-{}", name, std::process::id());
+            let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+            for key in keys {
+                hkcu.create_subkey(format!("{}\\{}", path, key)).unwrap();
             }
+            TestTree { path }
         }
+
+        fn search(&self) -> RegistrySearch {
+            RegistrySearch::new(HKEY_CURRENT_USER).start_path(&self.path)
+        }
+
+        /// Hit paths relative to the tree, sorted.
+        fn relative(&self, hits: Vec<SearchHit>) -> Vec<String> {
+            let prefix = format!("HKEY_CURRENT_USER\\{}\\", self.path);
+            let mut paths: Vec<String> = hits
+                .into_iter()
+                .map(|hit| hit.key_path.strip_prefix(&prefix).unwrap().to_string())
+                .collect();
+            paths.sort();
+            paths
+        }
+    }
+
+    impl Drop for TestTree {
+        fn drop(&mut self) {
+            let _ = RegKey::predef(HKEY_CURRENT_USER).delete_subkey_all(&self.path);
+        }
+    }
+
+    #[tokio::test]
+    async fn max_depth_limits_how_far_the_walk_descends() {
+        let tree = TestTree::new("depth", &["a\\b\\c", "d"]);
+
+        let hits = tree.search().max_depth(1).collect().await;
+        assert_eq!(tree.relative(hits), ["a", "d"]);
+
+        let hits = tree.search().max_depth(2).collect().await;
+        assert_eq!(tree.relative(hits), ["a", "a\\b", "d"]);
+
+        let hits = tree.search().collect().await;
+        assert_eq!(tree.relative(hits), ["a", "a\\b", "a\\b\\c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn key_names_and_values_narrow_the_hits() {
+        let tree = TestTree::new("names", &["StarCraft", "x\\starcraft", "StarCraft II"]);
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let key = hkcu
+            .open_subkey_with_flags(format!("{}\\x\\starcraft", tree.path), KEY_WRITE)
+            .unwrap();
+        key.set_value("InstallLocation", &"C:\\Games\\StarCraft")
+            .unwrap();
+
+        let hits = tree
+            .search()
+            .key_name(KeyPattern::exact("StarCraft"))
+            .collect()
+            .await;
+        assert_eq!(tree.relative(hits), ["StarCraft", "x\\starcraft"]);
+
+        let hits = tree
+            .search()
+            .key_name(KeyPattern::exact("StarCraft"))
+            .value_name("InstallLocation")
+            .collect()
+            .await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].value.as_deref(), Some("C:\\Games\\StarCraft"));
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_walk_reports_nothing() {
+        let tree = TestTree::new("cancel", &["a", "b"]);
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let hits = tree.search().cancel_token(token).collect().await;
+        assert!(hits.is_empty());
+
+        let hits = tree.search().timeout(Duration::ZERO).collect().await;
+        assert!(hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_missing_start_path_ends_the_walk() {
+        let hits = RegistrySearch::new(HKEY_CURRENT_USER)
+            .start_path("Software\\sclauncher-test-missing")
+            .collect()
+            .await;
+        assert!(hits.is_empty());
     }
-    Ok(())
 }
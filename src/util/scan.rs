@@ -0,0 +1,373 @@
+use std::ffi::{c_void, OsString};
+use std::os::windows::ffi::OsStringExt;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use windows::{
+    core::Result,
+    Wdk::{
+        Foundation::{
+            NtQueryObject, ObjectTypeInformation, OBJECT_INFORMATION_CLASS, OBJECT_NAME_INFORMATION,
+        },
+        System::Threading::ProcessHandleInformation,
+    },
+    Win32::{
+        Foundation::{
+            CloseHandle, DuplicateHandle, DUPLICATE_CLOSE_SOURCE, DUPLICATE_SAME_ACCESS, HANDLE,
+            NTSTATUS, STATUS_INFO_LENGTH_MISMATCH,
+        },
+        System::SystemServices::MAXIMUM_ALLOWED,
+        System::Threading::{
+            GetCurrentProcess, OpenProcess, PROCESS_DUP_HANDLE, PROCESS_QUERY_INFORMATION,
+        },
+    },
+};
+
+use super::custom_windows::{
+    self, PROCESS_HANDLE_SNAPSHOT_INFORMATION, PROCESS_HANDLE_TABLE_ENTRY_INFO,
+};
+use super::game::find_starcraft_pids;
+use super::name_query::{NameQueries, NameQueryHung, NAME_QUERY_TIMEOUT};
+use super::object_types::{ObjectTypeMap, MUTANT_TYPE};
+use crate::t;
+
+/// Name StarCraft gives the mutex it uses to refuse a second instance.
+pub const INSTANCE_MUTEX_NAME: &str = "Starcraft Check For Other Instances";
+
+const OBJECT_NAME_INFORMATION: OBJECT_INFORMATION_CLASS = OBJECT_INFORMATION_CLASS(0x1);
+const OBJECT_TYPES_INFORMATION: OBJECT_INFORMATION_CLASS = OBJECT_INFORMATION_CLASS(0x3);
+
+static OBJECT_TYPES: OnceLock<Option<ObjectTypeMap>> = OnceLock::new();
+
+static NAME_QUERIES: Mutex<NameQueries> = Mutex::new(NameQueries::new(
+    |handle| query_object_string(HANDLE(handle), OBJECT_NAME_INFORMATION),
+    NAME_QUERY_TIMEOUT,
+));
+
+/// What happened, or would happen, to a matching handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAction {
+    WouldClose,
+    Closed,
+    CloseFailed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedHandle {
+    /// Handle value inside the StarCraft process
+    pub handle: usize,
+    pub name: String,
+    pub action: HandleAction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScannedProcess {
+    pub pid: u32,
+    /// Number of handles the process has open
+    pub handle_count: usize,
+    pub matches: Vec<MatchedHandle>,
+    /// Handles whose name query did not return within [`NAME_QUERY_TIMEOUT`]
+    pub hung_handles: Vec<usize>,
+    /// Set when the process could not be opened or queried
+    pub error: Option<String>,
+}
+
+/// Result of one pass of the instance mutex scanner.
+#[derive(Debug, Clone, Serialize)]
+pub struct MutexScan {
+    pub dry_run: bool,
+    pub processes: Vec<ScannedProcess>,
+}
+
+impl MutexScan {
+    /// Number of handles closed (or that would be closed in a dry run).
+    pub fn affected(&self) -> usize {
+        self.processes
+            .iter()
+            .flat_map(|process| &process.matches)
+            .filter(|handle| handle.action != HandleAction::CloseFailed)
+            .count()
+    }
+}
+
+/// Looks for the instance mutex in every running StarCraft.exe once and closes it.
+///
+/// With `dry_run` nothing is closed; the report says which handles would be.
+pub fn scan_instance_mutexes(dry_run: bool) -> Result<MutexScan> {
+    let processes = find_starcraft_pids()?
+        .into_iter()
+        .map(|pid| scan_process(pid, dry_run))
+        .collect();
+    Ok(MutexScan { dry_run, processes })
+}
+
+/// Looks for the instance mutex in the one process `pid` and closes it, leaving every other
+/// StarCraft.exe alone.
+pub fn scan_process(pid: u32, dry_run: bool) -> ScannedProcess {
+    let mut scanned = ScannedProcess {
+        pid,
+        handle_count: 0,
+        matches: Vec::new(),
+        hung_handles: Vec::new(),
+        error: None,
+    };
+
+    // Enough to list and duplicate handles, nothing more
+    let process =
+        match unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_DUP_HANDLE, false, pid) } {
+            Ok(process) => process,
+            Err(e) => {
+                scanned.error = Some(t!("process-open-failed", error = e));
+                return scanned;
+            }
+        };
+
+    match query_process_handles(process) {
+        Ok(handles) => {
+            scanned.handle_count = handles.len();
+            let mutant = object_types().and_then(|types| types.index_of(MUTANT_TYPE));
+            for entry in handles {
+                // Only a mutex can be the instance mutex, and naming files or pipes is slow
+                let is_mutant = match mutant {
+                    Some(index) => entry.ObjectTypeIndex == index,
+                    None => {
+                        query_object_type(process, entry.HandleValue).as_deref()
+                            == Some(MUTANT_TYPE)
+                    }
+                };
+                if !is_mutant {
+                    continue;
+                }
+                let name = match query_object_name(pid, process, entry.HandleValue) {
+                    Ok(Some(name)) => name,
+                    Ok(None) => continue,
+                    Err(NameQueryHung) => {
+                        scanned.hung_handles.push(entry.HandleValue.0 as usize);
+                        continue;
+                    }
+                };
+                if !name.contains(INSTANCE_MUTEX_NAME) {
+                    continue;
+                }
+
+                let action = if dry_run {
+                    HandleAction::WouldClose
+                } else if close_remote_handle(process, entry.HandleValue) {
+                    HandleAction::Closed
+                } else {
+                    HandleAction::CloseFailed
+                };
+                scanned.matches.push(MatchedHandle {
+                    handle: entry.HandleValue.0 as usize,
+                    name,
+                    action,
+                });
+            }
+        }
+        Err(status) => {
+            scanned.error = Some(t!(
+                "handle-query-failed",
+                status = format!("{:#x}", status.0)
+            ))
+        }
+    }
+
+    unsafe {
+        let _ = CloseHandle(process);
+    }
+    scanned
+}
+
+/// Lists the handles `process` has open. It must be opened with `PROCESS_QUERY_INFORMATION`.
+pub(crate) fn query_process_handles(
+    process: HANDLE,
+) -> std::result::Result<Vec<PROCESS_HANDLE_TABLE_ENTRY_INFO>, NTSTATUS> {
+    // u64 storage keeps the buffer aligned for the structs read out of it
+    let mut buffer: Vec<u64> = Vec::new();
+    let mut length: u32 = 0;
+
+    let mut status = unsafe {
+        custom_windows::ZwQueryInformationProcess(
+            process,
+            ProcessHandleInformation,
+            std::ptr::null_mut(),
+            0,
+            &mut length,
+        )
+    };
+    while status == STATUS_INFO_LENGTH_MISMATCH {
+        buffer.resize((length as usize).div_ceil(8), 0);
+        status = unsafe {
+            custom_windows::ZwQueryInformationProcess(
+                process,
+                ProcessHandleInformation,
+                buffer.as_mut_ptr() as *mut c_void,
+                (buffer.len() * 8) as u32,
+                &mut length,
+            )
+        };
+    }
+    if status.is_err() {
+        return Err(status);
+    }
+
+    let handles = unsafe {
+        let base_ptr = buffer.as_ptr() as *const PROCESS_HANDLE_SNAPSHOT_INFORMATION;
+        let handles_ptr = base_ptr.add(1) as *const PROCESS_HANDLE_TABLE_ENTRY_INFO;
+        std::slice::from_raw_parts(handles_ptr, (*base_ptr).NumberOfHandles)
+    };
+    Ok(handles.to_vec())
+}
+
+/// The object type names of this boot, listed once per process; `None` when they cannot be.
+pub(crate) fn object_types() -> Option<&'static ObjectTypeMap> {
+    OBJECT_TYPES
+        .get_or_init(|| query_object_types().ok())
+        .as_ref()
+}
+
+fn query_object_types() -> std::result::Result<ObjectTypeMap, String> {
+    // Usually around 20 KiB, but the list grows with the drivers loaded
+    let mut buffer: Vec<u64> = vec![0; 0x1000];
+    loop {
+        let mut length: u32 = 0;
+        let status = unsafe {
+            NtQueryObject(
+                HANDLE::default(),
+                OBJECT_TYPES_INFORMATION,
+                Some(buffer.as_mut_ptr() as *mut c_void),
+                (buffer.len() * 8) as u32,
+                Some(&mut length),
+            )
+        };
+        if status == STATUS_INFO_LENGTH_MISMATCH {
+            let size = (length as usize).div_ceil(8).max(buffer.len() * 2);
+            buffer.resize(size, 0);
+            continue;
+        }
+        if status.is_err() {
+            return Err(format!("NTSTATUS({:#x})", status.0));
+        }
+
+        let bytes =
+            unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 8) };
+        return ObjectTypeMap::parse(bytes, std::mem::size_of::<usize>());
+    }
+}
+
+/// A handle of another process duplicated into this one, closed when dropped.
+struct LocalCopy(HANDLE);
+
+impl LocalCopy {
+    fn new(process: HANDLE, handle: HANDLE) -> Option<LocalCopy> {
+        let mut copy = HANDLE::default();
+        unsafe {
+            DuplicateHandle(
+                process,
+                handle,
+                GetCurrentProcess(),
+                &mut copy,
+                MAXIMUM_ALLOWED,
+                false,
+                DUPLICATE_SAME_ACCESS,
+            )
+        }
+        .ok()?;
+        Some(LocalCopy(copy))
+    }
+}
+
+impl Drop for LocalCopy {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Reads the object name behind a handle of process `pid`, leaving that handle open.
+///
+/// The query runs on a worker thread and is given up on after [`NAME_QUERY_TIMEOUT`]; a
+/// handle that hung once is not asked again (see [`NameQueries`]).
+pub(crate) fn query_object_name(
+    pid: u32,
+    process: HANDLE,
+    handle: HANDLE,
+) -> std::result::Result<Option<String>, NameQueryHung> {
+    let key = handle.0 as usize;
+    let mut queries = NAME_QUERIES.lock().unwrap_or_else(|e| e.into_inner());
+    if queries.is_hung(pid, key) {
+        return Err(NameQueryHung);
+    }
+    let Some(copy) = LocalCopy::new(process, handle) else {
+        return Ok(None);
+    };
+
+    let name = queries.query(pid, key, copy.0 .0);
+    if name.is_err() {
+        // The stuck thread still uses the copy; it leaks with the thread
+        std::mem::forget(copy);
+    }
+    name
+}
+
+/// Reads the type name (`Mutant`, `File`, `Event`, ...) of a handle of another process.
+pub(crate) fn query_object_type(process: HANDLE, handle: HANDLE) -> Option<String> {
+    let copy = LocalCopy::new(process, handle)?;
+    query_object_string(copy.0, ObjectTypeInformation)
+}
+
+/// Queries `class` of a local handle and reads the `UNICODE_STRING` the answer starts with,
+/// as both the name and the type information do.
+fn query_object_string(handle: HANDLE, class: OBJECT_INFORMATION_CLASS) -> Option<String> {
+    let mut buffer: Vec<u64> = Vec::new();
+    let mut length: u32 = 0;
+    let mut status = unsafe { NtQueryObject(handle, class, None, 0, Some(&mut length)) };
+    while status == STATUS_INFO_LENGTH_MISMATCH {
+        buffer.resize((length as usize).div_ceil(8), 0);
+        status = unsafe {
+            NtQueryObject(
+                handle,
+                class,
+                Some(buffer.as_mut_ptr() as *mut c_void),
+                (buffer.len() * 8) as u32,
+                Some(&mut length),
+            )
+        };
+    }
+    if status.is_err() || buffer.is_empty() {
+        return None;
+    }
+
+    let info = unsafe { &*(buffer.as_ptr() as *const OBJECT_NAME_INFORMATION) };
+    if info.Name.Length == 0 {
+        return None;
+    }
+    let name = unsafe {
+        std::slice::from_raw_parts(info.Name.Buffer.as_ptr(), info.Name.Length as usize / 2)
+    };
+    Some(OsString::from_wide(name).to_string_lossy().into_owned())
+}
+
+/// Closes a handle inside another process.
+fn close_remote_handle(process: HANDLE, handle: HANDLE) -> bool {
+    let mut copy = HANDLE::default();
+    let closed = unsafe {
+        DuplicateHandle(
+            process,
+            handle,
+            GetCurrentProcess(),
+            &mut copy,
+            MAXIMUM_ALLOWED,
+            false,
+            DUPLICATE_CLOSE_SOURCE,
+        )
+    };
+    if closed.is_ok() {
+        unsafe {
+            let _ = CloseHandle(copy);
+        }
+    }
+    closed.is_ok()
+}
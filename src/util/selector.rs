@@ -0,0 +1,299 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::instance::GameInstance;
+use crate::t;
+
+/// Chooses a subset of the instances tracked by `GameManager`.
+///
+/// Text form (used by the CLI):
+/// * `all` - every instance
+/// * `3` or `index:3` - the 3rd instance in launch order (1-based)
+/// * `pid:1234` - the instance with that process id
+/// * `oldest`, `oldest:2` - the first N instances in launch order
+/// * `newest`, `newest:2` - the last N instances in launch order
+/// * `label:main` - instances labelled exactly `main`
+/// * `label:bot*` or `glob:bot?` - instances whose label matches the glob
+/// * `profile:ladder` - instances launched with that profile
+/// * `adopted` - instances that were already running and got adopted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    All,
+    Index(usize),
+    Pid(u32),
+    Oldest(usize),
+    Newest(usize),
+    Label(String),
+    LabelGlob(String),
+    Profile(String),
+    Adopted,
+}
+
+impl Selector {
+    /// Returns the positions (in launch order) of the matching instances.
+    pub fn matches(&self, instances: &[GameInstance]) -> Vec<usize> {
+        let count = instances.len();
+        match self {
+            Selector::All => (0..count).collect(),
+            Selector::Index(index) => {
+                if *index >= 1 && *index <= count {
+                    vec![index - 1]
+                } else {
+                    Vec::new()
+                }
+            }
+            Selector::Pid(pid) => positions(instances, |instance| instance.pid == *pid),
+            Selector::Oldest(n) => (0..count.min(*n)).collect(),
+            Selector::Newest(n) => (count.saturating_sub(*n)..count).collect(),
+            Selector::Label(label) => positions(instances, |instance| {
+                instance.label.as_deref() == Some(label.as_str())
+            }),
+            Selector::LabelGlob(pattern) => positions(instances, |instance| {
+                instance
+                    .label
+                    .as_deref()
+                    .is_some_and(|label| glob_match(pattern, label))
+            }),
+            Selector::Profile(profile) => {
+                positions(instances, |instance| instance.profile == *profile)
+            }
+            Selector::Adopted => positions(instances, |instance| instance.adopted),
+        }
+    }
+}
+
+fn positions(instances: &[GameInstance], predicate: impl Fn(&GameInstance) -> bool) -> Vec<usize> {
+    instances
+        .iter()
+        .enumerate()
+        .filter(|(_, instance)| predicate(instance))
+        .map(|(position, _)| position)
+        .collect()
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(t!("selector-empty"));
+        }
+
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind.to_ascii_lowercase(), Some(value)),
+            None => (s.to_ascii_lowercase(), None),
+        };
+
+        let parse_count = |value: Option<&str>| -> Result<usize, String> {
+            match value {
+                None => Ok(1),
+                Some(v) => v
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| t!("selector-bad-count", count = v, selector = s)),
+            }
+        };
+        let require_value = |value: Option<&str>| -> Result<String, String> {
+            match value {
+                Some(v) if !v.is_empty() => Ok(v.to_string()),
+                _ => Err(t!("selector-needs-value", selector = s, kind = &kind)),
+            }
+        };
+
+        match kind.as_str() {
+            "all" if value.is_none() => Ok(Selector::All),
+            "adopted" if value.is_none() => Ok(Selector::Adopted),
+            "oldest" => parse_count(value).map(Selector::Oldest),
+            "newest" => parse_count(value).map(Selector::Newest),
+            "index" => match value.and_then(|v| v.parse::<usize>().ok()) {
+                Some(index) if index > 0 => Ok(Selector::Index(index)),
+                _ => Err(t!("selector-bad-index", selector = s)),
+            },
+            "pid" => value
+                .and_then(|v| v.parse::<u32>().ok())
+                .map(Selector::Pid)
+                .ok_or_else(|| t!("selector-bad-pid", selector = s)),
+            "label" => {
+                let label = require_value(value)?;
+                if label.contains(['*', '?']) {
+                    Ok(Selector::LabelGlob(label))
+                } else {
+                    Ok(Selector::Label(label))
+                }
+            }
+            "glob" => require_value(value).map(Selector::LabelGlob),
+            "profile" => require_value(value).map(Selector::Profile),
+            _ => match s.parse::<usize>() {
+                Ok(index) if index > 0 => Ok(Selector::Index(index)),
+                _ => Err(t!("selector-unknown", selector = s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::All => write!(f, "all"),
+            Selector::Index(index) => write!(f, "index:{}", index),
+            Selector::Pid(pid) => write!(f, "pid:{}", pid),
+            Selector::Oldest(n) => write!(f, "oldest:{}", n),
+            Selector::Newest(n) => write!(f, "newest:{}", n),
+            Selector::Label(label) => write!(f, "label:{}", label),
+            Selector::LabelGlob(pattern) => write!(f, "glob:{}", pattern),
+            Selector::Profile(profile) => write!(f, "profile:{}", profile),
+            Selector::Adopted => write!(f, "adopted"),
+        }
+    }
+}
+
+/// Case-insensitive glob match supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None; // (pattern index after '*', text index)
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last '*' swallow one more character
+            p = star_p;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::i18n::{set_language, Lang};
+    use crate::util::testing::game;
+
+    fn instances() -> Vec<GameInstance> {
+        let mut adopted = game(30, None, "default");
+        adopted.adopted = true;
+        vec![
+            game(10, Some("main"), "default"),
+            game(20, Some("bot1"), "ladder"),
+            adopted,
+            game(40, Some("Bot2"), "ladder"),
+        ]
+    }
+
+    fn select(text: &str) -> Vec<u32> {
+        let instances = instances();
+        let selector: Selector = text.parse().unwrap();
+        selector
+            .matches(&instances)
+            .into_iter()
+            .map(|position| instances[position].pid)
+            .collect()
+    }
+
+    #[test]
+    fn positions_and_counts() {
+        assert_eq!(select("all"), [10, 20, 30, 40]);
+        assert_eq!(select("ALL"), [10, 20, 30, 40]);
+        assert_eq!(select("2"), [20]);
+        assert_eq!(select("index:4"), [40]);
+        assert!(select("5").is_empty());
+        assert_eq!(select("oldest"), [10]);
+        assert_eq!(select("oldest:2"), [10, 20]);
+        assert_eq!(select("newest:3"), [20, 30, 40]);
+        assert_eq!(select("newest:9"), [10, 20, 30, 40]);
+        assert_eq!(select("adopted"), [30]);
+    }
+
+    #[test]
+    fn pids() {
+        assert_eq!(select("pid:30"), [30]);
+        assert_eq!(select(" pid:40 "), [40]);
+        assert!(select("pid:99").is_empty());
+    }
+
+    #[test]
+    fn labels_match_exactly_or_by_glob() {
+        assert_eq!(select("label:main"), [10]);
+        assert!(select("label:Main").is_empty());
+        // Globs ignore case and never match unlabelled games
+        assert_eq!(select("label:bot*"), [20, 40]);
+        assert_eq!(select("glob:?ot?"), [20, 40]);
+        assert_eq!(select("label:*"), [10, 20, 40]);
+        assert!(select("label:bot").is_empty());
+    }
+
+    #[test]
+    fn profiles_match_literally() {
+        assert_eq!(select("profile:ladder"), [20, 40]);
+        assert_eq!(select("profile:default"), [10, 30]);
+        assert!(select("profile:lad*").is_empty());
+    }
+
+    #[test]
+    fn invalid_selectors_are_refused() {
+        set_language(Lang::En);
+        for text in [
+            "",
+            "  ",
+            "0",
+            "index:0",
+            "index:x",
+            "pid:",
+            "pid:-1",
+            "oldest:0",
+            "newest:x",
+            "label:",
+            "glob:",
+            "profile:",
+            "all:2",
+            "adopted:1",
+            "everything",
+        ] {
+            assert!(text.parse::<Selector>().is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let selectors = [
+            Selector::All,
+            Selector::Index(3),
+            Selector::Pid(1234),
+            Selector::Oldest(2),
+            Selector::Newest(1),
+            Selector::Label("main".to_string()),
+            Selector::LabelGlob("bot*".to_string()),
+            Selector::LabelGlob("exact".to_string()),
+            Selector::Profile("ladder".to_string()),
+            Selector::Adopted,
+        ];
+        for selector in selectors {
+            assert_eq!(selector.to_string().parse::<Selector>(), Ok(selector));
+        }
+    }
+
+    #[test]
+    fn glob_match_handles_stars_and_question_marks() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(glob_match("a*c", "abcbc"));
+        assert!(!glob_match("a*c", "abcb"));
+        assert!(glob_match("??", "ab"));
+        assert!(!glob_match("??", "abc"));
+        assert!(glob_match("BOT*", "bot7"));
+    }
+}
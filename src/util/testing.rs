@@ -0,0 +1,284 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tempfile::TempDir;
+use tokio::sync::broadcast;
+
+use super::daemon::GameBackend;
+use super::events::GameEvent;
+use super::instance::{GameInstance, LaunchOptions};
+use super::limits::SystemInfo;
+use super::selector::Selector;
+
+/// Machine types of the executables the fixtures build.
+pub const I386: u16 = 0x014c;
+pub const AMD64: u16 = 0x8664;
+
+/// Where [`pe_image`] maps its one section, and where the section starts in the file.
+pub const SECTION_RVA: u32 = 0x1000;
+pub const SECTION_OFFSET: usize = 0x400;
+
+pub fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+pub fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// A PE32 ([`I386`]) or PE32+ image with one section holding `resources`.
+pub fn pe_image(machine: u16, resources: Option<&[u8]>) -> Vec<u8> {
+    // IMAGE_DIRECTORY_ENTRY_RESOURCE
+    const RESOURCE_DIRECTORY: usize = 2;
+
+    let pe32_plus = machine != I386;
+    let directories = if pe32_plus { 112 } else { 96 };
+    let optional_size = directories + 16 * 8;
+
+    let mut data = vec![0u8; SECTION_OFFSET];
+    data[..2].copy_from_slice(b"MZ");
+    put_u32(&mut data, 0x3c, 0x40);
+    data[0x40..0x44].copy_from_slice(b"PE\0\0");
+    put_u16(&mut data, 0x44, machine);
+    put_u16(&mut data, 0x46, 1);
+    put_u16(&mut data, 0x54, optional_size as u16);
+
+    let optional = 0x58;
+    put_u16(&mut data, optional, if pe32_plus { 0x20b } else { 0x10b });
+    put_u32(&mut data, optional + directories - 4, 16);
+    let resources = resources.unwrap_or_default();
+    if !resources.is_empty() {
+        let entry = optional + directories + RESOURCE_DIRECTORY * 8;
+        put_u32(&mut data, entry, SECTION_RVA);
+        put_u32(&mut data, entry + 4, resources.len() as u32);
+    }
+
+    let section = optional + optional_size;
+    data[section..section + 5].copy_from_slice(b".rsrc");
+    put_u32(&mut data, section + 8, resources.len() as u32);
+    put_u32(&mut data, section + 12, SECTION_RVA);
+    put_u32(&mut data, section + 16, resources.len() as u32);
+    put_u32(&mut data, section + 20, SECTION_OFFSET as u32);
+    data.extend_from_slice(resources);
+    data
+}
+
+fn utf16z(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+fn pad4(data: &mut Vec<u8>) {
+    data.resize((data.len() + 3) & !3, 0);
+}
+
+/// One `VS_VERSIONINFO` node; `value_length` counts UTF-16 units for text values.
+fn block(key: &str, value: &[u8], value_length: u16, is_text: bool, children: &[u8]) -> Vec<u8> {
+    let mut data = vec![0u8; 6];
+    data.extend(utf16z(key));
+    pad4(&mut data);
+    data.extend_from_slice(value);
+    if !children.is_empty() {
+        pad4(&mut data);
+        data.extend_from_slice(children);
+    }
+    let length = data.len() as u16;
+    put_u16(&mut data, 0, length);
+    put_u16(&mut data, 2, value_length);
+    put_u16(&mut data, 4, is_text as u16);
+    pad4(&mut data);
+    data
+}
+
+fn string(key: &str, value: &str) -> Vec<u8> {
+    let value = utf16z(value);
+    block(key, &value, (value.len() / 2) as u16, true, &[])
+}
+
+/// StarCraft 1.23.10.13515's `VS_VERSIONINFO`.
+fn version_info() -> Vec<u8> {
+    // VS_FIXEDFILEINFO.dwSignature
+    const SIGNATURE: u32 = 0xfeef_04bd;
+
+    let mut fixed = vec![0u8; 52];
+    put_u32(&mut fixed, 0, SIGNATURE);
+    put_u32(&mut fixed, 8, (1 << 16) | 23);
+    put_u32(&mut fixed, 12, (10 << 16) | 13515);
+    put_u32(&mut fixed, 16, (1 << 16) | 23);
+    put_u32(&mut fixed, 20, 10 << 16);
+
+    let strings = [
+        string("ProductName", "StarCraft"),
+        string("FileDescription", "StarCraft: Remastered"),
+    ]
+    .concat();
+    let table = block("040904b0", &[], 0, true, &strings);
+    let file_info = block("StringFileInfo", &[], 0, true, &table);
+    block("VS_VERSION_INFO", &fixed, 52, false, &file_info)
+}
+
+/// A resource section for [`pe_image`] holding [`version_info`]: type → name → language →
+/// data entry → `VS_VERSIONINFO`, as laid out by resource compilers.
+pub fn version_resources() -> Vec<u8> {
+    const RT_VERSION: u32 = 16;
+
+    let version = version_info();
+    let mut data = vec![0u8; 0x58];
+    for (directory, id, target) in [
+        (0x00, RT_VERSION, 0x8000_0018),
+        (0x18, 1, 0x8000_0030),
+        (0x30, 0x409, 0x48),
+    ] {
+        put_u16(&mut data, directory + 14, 1);
+        put_u32(&mut data, directory + 16, id);
+        put_u32(&mut data, directory + 20, target);
+    }
+    put_u32(&mut data, 0x48, SECTION_RVA + 0x58);
+    put_u32(&mut data, 0x4c, version.len() as u32);
+    data.extend_from_slice(&version);
+    data
+}
+
+/// Writes `contents` to `path`, creating the folders above it.
+pub fn write_file(path: &Path, contents: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+/// Writes a StarCraft.exe for `machine` at `path`.
+pub fn write_exe(path: &Path, machine: u16) {
+    write_file(path, &pe_image(machine, None));
+}
+
+/// A temporary folder holding an empty file at each of `files`, removed when dropped.
+pub fn install_tree(files: &[&str]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for file in files {
+        write_file(&dir.path().join(file), b"");
+    }
+    dir
+}
+
+/// A tracked game as the backends report it.
+pub fn game(pid: u32, label: Option<&str>, profile: &str) -> GameInstance {
+    GameInstance {
+        pid,
+        #[cfg(windows)]
+        handle: Default::default(),
+        label: label.map(str::to_string),
+        profile: profile.to_string(),
+        adopted: false,
+        game_path: None,
+    }
+}
+
+/// A [`GameBackend`] that tracks games in memory instead of starting processes.
+pub struct FakeBackend {
+    games: Mutex<Vec<GameInstance>>,
+    /// Games `adopt_running_games` finds, or the error it fails with
+    pub running: Mutex<Result<Vec<GameInstance>, String>>,
+    /// Every launch is refused while set
+    pub fail_launches: AtomicBool,
+    /// StarCraft.exe running besides the tracked games
+    pub untracked: AtomicU32,
+    /// Bytes of free memory reported to the launch limits
+    pub available: AtomicU64,
+    next_pid: AtomicU32,
+    guard: AtomicBool,
+    events: broadcast::Sender<GameEvent>,
+}
+
+impl FakeBackend {
+    pub fn new(games: Vec<GameInstance>) -> Self {
+        FakeBackend {
+            games: Mutex::new(games),
+            running: Mutex::new(Ok(Vec::new())),
+            fail_launches: AtomicBool::new(false),
+            untracked: AtomicU32::new(0),
+            available: AtomicU64::new(u64::MAX),
+            next_pid: AtomicU32::new(1000),
+            guard: AtomicBool::new(true),
+            events: broadcast::channel(16).0,
+        }
+    }
+
+    pub fn games(&self) -> Vec<GameInstance> {
+        self.games.lock().unwrap().clone()
+    }
+}
+
+impl SystemInfo for FakeBackend {
+    fn available_memory(&self) -> u64 {
+        self.available.load(Ordering::SeqCst)
+    }
+}
+
+impl GameBackend for FakeBackend {
+    async fn instances(&self) -> Vec<GameInstance> {
+        self.games()
+    }
+
+    fn running_games(&self) -> u32 {
+        self.games.lock().unwrap().len() as u32 + self.untracked.load(Ordering::SeqCst)
+    }
+
+    async fn launch(&self, game_path: PathBuf, options: LaunchOptions) -> Option<u32> {
+        if self.fail_launches.load(Ordering::SeqCst) {
+            return None;
+        }
+        let pid = self.next_pid.fetch_add(1, Ordering::SeqCst);
+        let mut instance = game(pid, options.label.as_deref(), &options.profile);
+        instance.game_path = Some(game_path);
+        self.games.lock().unwrap().push(instance);
+        let _ = self.events.send(GameEvent::Launched {
+            pid,
+            label: options.label,
+            profile: options.profile,
+        });
+        Some(pid)
+    }
+
+    async fn kill_selected(&self, selector: &Selector) -> Vec<u32> {
+        let mut games = self.games.lock().unwrap();
+        let mut matched = selector.matches(&games);
+        matched.sort_unstable();
+        let mut pids = Vec::new();
+        for index in matched.into_iter().rev() {
+            pids.push(games.remove(index).pid);
+        }
+        pids.reverse();
+        pids
+    }
+
+    async fn adopt_running_games(&self) -> Result<Vec<u32>, String> {
+        let running = self.running.lock().unwrap().clone()?;
+        let mut games = self.games.lock().unwrap();
+        let mut pids = Vec::new();
+        for mut instance in running {
+            if games.iter().all(|g| g.pid != instance.pid) {
+                instance.adopted = true;
+                pids.push(instance.pid);
+                games.push(instance);
+            }
+        }
+        Ok(pids)
+    }
+
+    fn guard_running(&self) -> bool {
+        self.guard.load(Ordering::SeqCst)
+    }
+
+    fn stop_guard(&self) -> bool {
+        self.guard.swap(false, Ordering::SeqCst)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        self.events.subscribe()
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::t;
+
+#[cfg(windows)]
+use windows::Win32::{
+    Foundation::{BOOL, HWND, LPARAM},
+    UI::WindowsAndMessaging::{
+        EnumWindows, GetSystemMetrics, GetWindow, GetWindowThreadProcessId, IsIconic,
+        IsWindowVisible, SetForegroundWindow, SetWindowPos, ShowWindow, GW_OWNER, SM_CXSCREEN,
+        SM_CYSCREEN, SWP_NOACTIVATE, SWP_NOSIZE, SWP_NOZORDER, SW_RESTORE,
+    },
+};
+
+#[cfg(windows)]
+struct WindowSearch {
+    pid: u32,
+    found: HWND,
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = &mut *(lparam.0 as *mut WindowSearch);
+
+    let mut window_pid: u32 = 0;
+    GetWindowThreadProcessId(hwnd, Some(&mut window_pid));
+
+    // Only visible, top-level (unowned) windows count as the "main" window
+    if window_pid == search.pid
+        && IsWindowVisible(hwnd).as_bool()
+        && GetWindow(hwnd, GW_OWNER).0 == 0
+    {
+        search.found = hwnd;
+        return BOOL(0); // stop enumerating
+    }
+
+    BOOL(1)
+}
+
+#[cfg(windows)]
+/// Finds the main top-level window owned by `pid`.
+pub fn find_main_window(pid: u32) -> Option<HWND> {
+    let mut search = WindowSearch {
+        pid,
+        found: HWND(0),
+    };
+
+    unsafe {
+        // EnumWindows reports an error when the callback stops early, so ignore it
+        let _ = EnumWindows(
+            Some(enum_windows_proc),
+            LPARAM(&mut search as *mut WindowSearch as isize),
+        );
+    }
+
+    if search.found.0 != 0 {
+        Some(search.found)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+/// Restores and brings the main window of `pid` to the foreground.
+pub fn focus_process_window(pid: u32) -> bool {
+    match find_main_window(pid) {
+        Some(hwnd) => unsafe {
+            if IsIconic(hwnd).as_bool() {
+                let _ = ShowWindow(hwnd, SW_RESTORE);
+            }
+            SetForegroundWindow(hwnd).as_bool()
+        },
+        None => false,
+    }
+}
+
+/// How game windows are arranged after launching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// Leave the windows where StarCraft puts them
+    #[default]
+    None,
+    /// Tile the windows over the primary screen
+    Grid,
+    /// Stack the windows with a small offset
+    Cascade,
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layout::None => "none",
+            Layout::Grid => "grid",
+            Layout::Cascade => "cascade",
+        })
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Layout::None),
+            "grid" => Ok(Layout::Grid),
+            "cascade" => Ok(Layout::Cascade),
+            _ => Err(t!("layout-unknown", layout = s)),
+        }
+    }
+}
+
+#[cfg(windows)]
+/// Moves (and optionally resizes) the main window of `pid`.
+pub fn move_process_window(pid: u32, x: i32, y: i32, size: Option<(i32, i32)>) -> bool {
+    let Some(hwnd) = find_main_window(pid) else {
+        return false;
+    };
+    let (width, height) = size.unwrap_or_default();
+    let mut flags = SWP_NOZORDER | SWP_NOACTIVATE;
+    if size.is_none() {
+        flags |= SWP_NOSIZE;
+    }
+    unsafe { SetWindowPos(hwnd, HWND(0), x, y, width, height, flags) }.is_ok()
+}
+
+#[cfg(windows)]
+/// Window position, and size when it should change.
+type Placement = (i32, i32, Option<(i32, i32)>);
+
+#[cfg(windows)]
+/// Arranges the main windows of `pids` in order, returning how many were moved.
+///
+/// Games that have not opened their window yet are skipped.
+pub fn arrange_windows(pids: &[u32], layout: Layout) -> usize {
+    const CASCADE_STEP: i32 = 32;
+
+    if pids.is_empty() {
+        return 0;
+    }
+
+    let positions: Vec<Placement> = match layout {
+        Layout::None => return 0,
+        Layout::Cascade => (0..pids.len() as i32)
+            .map(|i| (i * CASCADE_STEP, i * CASCADE_STEP, None))
+            .collect(),
+        Layout::Grid => {
+            let (screen_width, screen_height) =
+                unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+            let columns = (pids.len() as f64).sqrt().ceil() as i32;
+            let rows = (pids.len() as i32 + columns - 1) / columns;
+            let (width, height) = (screen_width / columns, screen_height / rows);
+            (0..pids.len() as i32)
+                .map(|i| {
+                    (
+                        (i % columns) * width,
+                        (i / columns) * height,
+                        Some((width, height)),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    pids.iter()
+        .zip(positions)
+        .filter(|(pid, (x, y, size))| move_process_window(**pid, *x, *y, *size))
+        .count()
+}